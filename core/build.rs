@@ -2,6 +2,9 @@ use std::path::Path;
 use std::process::Command;
 
 fn main() {
+    // sqlx::migrate! embeds core/migrations at compile time
+    println!("cargo:rerun-if-changed=migrations");
+
     // Skip dashboard build in docs.rs
     if std::env::var("DOCS_RS").is_ok() {
         return;
@@ -1,4 +1,4 @@
-CREATE TABLE super_accounts (
+CREATE TABLE IF NOT EXISTS users (
     account TEXT PRIMARY KEY,
     nationality TEXT,
     username TEXT NOT NULL,
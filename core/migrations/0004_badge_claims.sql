@@ -0,0 +1,14 @@
+-- Shared by both profiles: `account` points to super_accounts or users
+CREATE TABLE IF NOT EXISTS badge_claims (
+    badge_id INT NOT NULL,
+    account TEXT NOT NULL,
+    tier INT,
+    points INT,
+    block_number INT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    claimed_at TIMESTAMP DEFAULT NOW(),
+    PRIMARY KEY (badge_id, tier, account, block_number)
+);
+
+-- Databases created from the old scripts had a FK to super_accounts
+ALTER TABLE badge_claims DROP CONSTRAINT IF EXISTS badge_claims_account_fkey;
@@ -1,4 +1,4 @@
-CREATE TABLE vaults_transactions (
+CREATE TABLE IF NOT EXISTS vaults_transactions (
     id BIGSERIAL PRIMARY KEY,
     account TEXT NOT NULL,
     token TEXT NOT NULL,
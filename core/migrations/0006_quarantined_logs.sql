@@ -0,0 +1,14 @@
+-- Logs a strategy could not decode; skipped instead of failing the chunk
+CREATE TABLE IF NOT EXISTS quarantined_logs (
+    strategy_name TEXT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    log_index BIGINT NOT NULL,
+    block_number BIGINT NOT NULL,
+    error_kind TEXT NOT NULL,
+    error TEXT NOT NULL,
+    first_seen TIMESTAMP NOT NULL DEFAULT NOW(),
+    last_seen TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (strategy_name, tx_hash, log_index)
+);
+
+CREATE INDEX IF NOT EXISTS quarantined_logs_block_idx ON quarantined_logs (block_number);
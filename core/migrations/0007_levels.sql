@@ -0,0 +1,22 @@
+-- Points needed for each level; an account is at the highest level it reaches (0 below level 1)
+CREATE TABLE IF NOT EXISTS level_thresholds (
+    level INTEGER PRIMARY KEY CHECK (level > 0),
+    min_points INTEGER NOT NULL
+);
+
+INSERT INTO level_thresholds (level, min_points) VALUES
+    (1, 50), (2, 100), (3, 250), (4, 500), (5, 1000), (6, 2500), (7, 5000)
+ON CONFLICT (level) DO NOTHING;
+
+-- One row per level change caused by a claim, at the block of the account's latest claim in the chunk
+CREATE TABLE IF NOT EXISTS level_ups (
+    account TEXT NOT NULL,
+    level INTEGER NOT NULL,
+    previous_level INTEGER NOT NULL,
+    total_points INTEGER NOT NULL,
+    block_number INTEGER NOT NULL,
+    tx_hash TEXT NOT NULL,
+    PRIMARY KEY (account, level, block_number)
+);
+
+CREATE INDEX IF NOT EXISTS level_ups_block_idx ON level_ups (block_number);
@@ -0,0 +1,25 @@
+-- Kept so the current state can be rebuilt from the history
+ALTER TABLE badge_claims ADD COLUMN IF NOT EXISTS uri TEXT;
+ALTER TABLE badge_claims ADD COLUMN IF NOT EXISTS log_index INT;
+
+-- Latest claim per (account, badge_id), maintained from badge_claims
+CREATE TABLE IF NOT EXISTS account_badges (
+    account TEXT NOT NULL,
+    badge_id INT NOT NULL,
+    tier INT,
+    points INT,
+    uri TEXT,
+    last_block_number INT NOT NULL,
+    last_log_index INT,
+    last_tx_hash TEXT NOT NULL,
+    PRIMARY KEY (account, badge_id)
+);
+
+CREATE INDEX IF NOT EXISTS account_badges_badge_idx ON account_badges (badge_id);
+
+-- Claims indexed before this table existed; `uri` stays NULL until they are reindexed
+INSERT INTO account_badges (account, badge_id, tier, points, uri, last_block_number, last_log_index, last_tx_hash)
+SELECT DISTINCT ON (account, badge_id) account, badge_id, tier, points, uri, block_number, log_index, tx_hash
+FROM badge_claims
+ORDER BY account, badge_id, block_number DESC, log_index DESC NULLS LAST, tier DESC
+ON CONFLICT (account, badge_id) DO NOTHING;
@@ -0,0 +1,19 @@
+-- Current vault position per account and token, derived from vaults_transactions
+CREATE TABLE IF NOT EXISTS vault_balances (
+    account TEXT NOT NULL,
+    token TEXT NOT NULL,
+    balance NUMERIC(79, 0) NOT NULL,
+    deposited NUMERIC(79, 0) NOT NULL,
+    withdrawn NUMERIC(79, 0) NOT NULL,
+    last_block BIGINT NOT NULL,
+    PRIMARY KEY (account, token)
+);
+
+-- End-of-day (UTC) balance, one row per day since the position's first flow
+CREATE TABLE IF NOT EXISTS vault_balance_snapshots (
+    account TEXT NOT NULL,
+    token TEXT NOT NULL,
+    day DATE NOT NULL,
+    balance NUMERIC(79, 0) NOT NULL,
+    PRIMARY KEY (account, token, day)
+);
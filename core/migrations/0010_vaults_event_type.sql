@@ -0,0 +1,17 @@
+-- Which event a flow comes from; several kinds can move the same account and token in one tx
+ALTER TABLE vaults_transactions ADD COLUMN IF NOT EXISTS event_type TEXT;
+
+-- Compound rows were always the WETH market; stCELO rows are deposits and withdrawals
+UPDATE vaults_transactions
+SET event_type = CASE
+    WHEN token = '0x4200000000000000000000000000000000000006'
+        THEN CASE direction WHEN 'in' THEN 'supply' ELSE 'withdraw' END
+    ELSE CASE direction WHEN 'in' THEN 'deposit' ELSE 'withdraw' END
+END
+WHERE event_type IS NULL;
+
+ALTER TABLE vaults_transactions ALTER COLUMN event_type SET NOT NULL;
+
+ALTER TABLE vaults_transactions DROP CONSTRAINT IF EXISTS vaults_transactions_account_token_tx_hash_direction_key;
+CREATE UNIQUE INDEX IF NOT EXISTS vaults_transactions_flow_key
+    ON vaults_transactions (account, token, tx_hash, direction, event_type);
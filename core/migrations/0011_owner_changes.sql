@@ -0,0 +1,18 @@
+-- Owner additions and removals per account; `eoas` is derived from the latest change per owner
+CREATE TABLE IF NOT EXISTS owner_changes (
+    account TEXT NOT NULL,
+    owner TEXT NOT NULL,
+    change TEXT NOT NULL CHECK (change IN ('added', 'removed')),
+    -- created (initial owner), module (OwnerAdded), safe (AddedOwner / RemovedOwner), migration
+    source TEXT NOT NULL,
+    block_number BIGINT NOT NULL,
+    log_index INT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    PRIMARY KEY (account, owner, block_number, log_index)
+);
+
+CREATE INDEX IF NOT EXISTS owner_changes_block_idx ON owner_changes (block_number);
+
+
+-- Owners known before the history existed are seeded per accounts table by the
+-- owner strategies (`owners::seed`), since the table is configurable
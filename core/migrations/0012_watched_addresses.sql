@@ -0,0 +1,23 @@
+-- Addresses discovered by one strategy (e.g. account Safes) and watched by others
+CREATE TABLE IF NOT EXISTS watched_addresses (
+    registry TEXT NOT NULL,
+    address TEXT NOT NULL,
+    -- Block the address appeared at; consumers scan it from there
+    from_block BIGINT NOT NULL,
+    added_by TEXT NOT NULL,
+    added_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (registry, address)
+);
+
+-- Last block each consumer fetched logs of each address through
+CREATE TABLE IF NOT EXISTS watched_address_scans (
+    registry TEXT NOT NULL,
+    consumer TEXT NOT NULL,
+    address TEXT NOT NULL,
+    scanned_to BIGINT NOT NULL,
+    PRIMARY KEY (registry, consumer, address)
+);
+
+
+-- Accounts already indexed are registered per accounts table by the strategies
+-- watching them (`watch_existing`), since the table is configurable
@@ -0,0 +1,20 @@
+-- Threshold and module changes of each account's Safe (SafeSetup, ChangedThreshold,
+-- EnabledModule, DisabledModule); the account columns below are derived from it
+CREATE TABLE IF NOT EXISTS safe_setting_changes (
+    account TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('threshold', 'module_enabled', 'module_disabled')),
+    -- Set for module_enabled / module_disabled
+    module TEXT,
+    -- Set for threshold
+    threshold INT,
+    block_number BIGINT NOT NULL,
+    log_index INT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    PRIMARY KEY (account, block_number, log_index)
+);
+
+CREATE INDEX IF NOT EXISTS safe_setting_changes_block_idx ON safe_setting_changes (block_number);
+
+
+-- `threshold`, `module_enabled` and `module_detached` are added to the configured
+-- accounts table by the safe settings strategy (`prepare`)
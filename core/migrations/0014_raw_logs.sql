@@ -0,0 +1,19 @@
+-- Every log fetched with eth_getLogs, as returned by the RPC (archive.enabled)
+CREATE TABLE IF NOT EXISTS raw_logs (
+    chain_id BIGINT NOT NULL,
+    block_number BIGINT NOT NULL,
+    log_index INT NOT NULL,
+    address TEXT NOT NULL,
+    topics TEXT[] NOT NULL,
+    data BYTEA NOT NULL,
+    tx_hash TEXT NOT NULL,
+    tx_index INT,
+    block_hash TEXT NOT NULL,
+    block_timestamp BIGINT,
+    archived_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (chain_id, block_number, log_index)
+);
+
+CREATE INDEX IF NOT EXISTS raw_logs_address_idx ON raw_logs (chain_id, address, block_number);
+-- topic0 selects the event; most filters have it
+CREATE INDEX IF NOT EXISTS raw_logs_topic0_idx ON raw_logs (chain_id, (topics[1]), block_number);
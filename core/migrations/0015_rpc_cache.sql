@@ -0,0 +1,19 @@
+-- Blocks and receipts fetched while archiving, so archived logs can be replayed without the RPC
+CREATE TABLE IF NOT EXISTS block_cache (
+    chain_id BIGINT NOT NULL,
+    block_number BIGINT NOT NULL,
+    block_hash TEXT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    PRIMARY KEY (chain_id, block_number)
+);
+
+CREATE TABLE IF NOT EXISTS receipt_cache (
+    chain_id BIGINT NOT NULL,
+    tx_hash TEXT NOT NULL,
+    block_number BIGINT NOT NULL,
+    -- eth_getTransactionReceipt result as returned by the RPC
+    receipt JSONB NOT NULL,
+    PRIMARY KEY (chain_id, tx_hash)
+);
+
+CREATE INDEX IF NOT EXISTS receipt_cache_block_idx ON receipt_cache (chain_id, block_number);
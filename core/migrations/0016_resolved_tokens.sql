@@ -0,0 +1,13 @@
+-- Tokens read from contracts (a Comet market's baseToken(), ...), so rollback and
+-- offline replay know them without an eth_call
+CREATE TABLE IF NOT EXISTS resolved_tokens (
+    contract TEXT NOT NULL,
+    getter TEXT NOT NULL,
+    token TEXT NOT NULL,
+    PRIMARY KEY (contract, getter)
+);
+
+-- Compound rows were always the WETH market until markets became configurable
+INSERT INTO resolved_tokens (contract, getter, token)
+VALUES ('0xe36a30d249f7761327fd973001a32010b521b6fd', 'baseToken', '0x4200000000000000000000000000000000000006')
+ON CONFLICT (contract, getter) DO NOTHING;
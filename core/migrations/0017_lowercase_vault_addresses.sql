@@ -0,0 +1,22 @@
+-- Accounts and stCELO tokens were stored checksummed; every filter compares lowercase.
+-- A checksummed row whose lowercase twin exists is a duplicate of it.
+DELETE FROM vaults_transactions AS v
+USING vaults_transactions AS l
+WHERE (v.account <> lower(v.account) OR v.token <> lower(v.token))
+  AND l.account = lower(v.account) AND l.token = lower(v.token)
+  AND l.tx_hash = v.tx_hash AND l.direction = v.direction AND l.event_type = v.event_type
+  AND l.id <> v.id;
+
+UPDATE vaults_transactions SET account = lower(account), token = lower(token)
+WHERE account <> lower(account) OR token <> lower(token);
+
+-- Balances are derived; `rebuild` recomputes them if twins were merged here
+DELETE FROM vault_balance_snapshots AS s
+USING vault_balance_snapshots AS l
+WHERE s.token <> lower(s.token) AND l.account = s.account AND l.token = lower(s.token) AND l.day = s.day;
+UPDATE vault_balance_snapshots SET token = lower(token) WHERE token <> lower(token);
+
+DELETE FROM vault_balances AS b
+USING vault_balances AS l
+WHERE b.token <> lower(b.token) AND l.account = b.account AND l.token = lower(b.token);
+UPDATE vault_balances SET token = lower(token) WHERE token <> lower(token);
@@ -0,0 +1,15 @@
+-- Threshold and SuperChainModule state of each account's Safe, derived from
+-- safe_setting_changes by the safe settings strategy
+ALTER TABLE super_accounts
+    ADD COLUMN IF NOT EXISTS threshold INT,
+    ADD COLUMN IF NOT EXISTS module_enabled BOOLEAN,
+    ADD COLUMN IF NOT EXISTS module_detached BOOLEAN GENERATED ALWAYS AS (module_enabled IS FALSE) STORED;
+
+CREATE INDEX IF NOT EXISTS super_accounts_module_detached_idx ON super_accounts (account) WHERE module_detached;
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS threshold INT,
+    ADD COLUMN IF NOT EXISTS module_enabled BOOLEAN,
+    ADD COLUMN IF NOT EXISTS module_detached BOOLEAN GENERATED ALWAYS AS (module_enabled IS FALSE) STORED;
+
+CREATE INDEX IF NOT EXISTS users_module_detached_idx ON users (account) WHERE module_detached;
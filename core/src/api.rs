@@ -1,9 +1,10 @@
 use axum::{
     Json, Router,
-    extract::State,
+    body::Body,
+    extract::{Query, State},
     http::{Request, StatusCode, Method, header},
     middleware::{self, Next},
-    response::Response,
+    response::{IntoResponse, Response},
     routing::{get, post},
 };
 use tower_http::cors::{CorsLayer, Any};
@@ -12,8 +13,13 @@ use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::path::PathBuf;
+use sqlx::PgPool;
 use tokio::sync::RwLock;
 
+use crate::export::{self, Export, ExportQuery};
+use crate::graphql;
+use crate::query::{self, QueryTables};
+
 // ============================================================================
 // State
 // ============================================================================
@@ -35,6 +41,8 @@ pub struct IndexState {
     pub current: u64,
     pub strategy: Option<String>,
     pub is_reindex: bool,
+    /// Replay archived logs instead of calling the RPC.
+    pub offline: bool,
 }
 
 #[derive(Default)]
@@ -50,6 +58,9 @@ pub struct App {
     pub state: RwLock<State_>,
     paused: AtomicBool,
     api_key: String,
+    /// Database served by the read endpoints (`/export`, queries); without it they answer 503.
+    db: Option<PgPool>,
+    tables: QueryTables,
 }
 
 impl App {
@@ -58,9 +69,30 @@ impl App {
             state: RwLock::new(State_::default()),
             paused: AtomicBool::new(false),
             api_key,
+            db: None,
+            tables: QueryTables::default(),
         })
     }
 
+    /// An app whose read endpoints serve `db`, with the profile's `tables`.
+    pub fn with_db(api_key: String, db: PgPool, tables: QueryTables) -> Arc<Self> {
+        Arc::new(Self {
+            state: RwLock::new(State_::default()),
+            paused: AtomicBool::new(false),
+            api_key,
+            db: Some(db),
+            tables,
+        })
+    }
+
+    pub fn db(&self) -> Option<&PgPool> {
+        self.db.as_ref()
+    }
+
+    pub fn tables(&self) -> &QueryTables {
+        &self.tables
+    }
+
     pub fn is_paused(&self) -> bool {
         self.paused.load(Ordering::SeqCst)
     }
@@ -79,8 +111,8 @@ impl App {
 // Request/Response
 // ============================================================================
 
-#[derive(Serialize)]
-struct Resp { ok: bool, msg: String }
+#[derive(Debug, Serialize)]
+pub(crate) struct Resp { pub ok: bool, pub msg: String }
 
 #[derive(Serialize)]
 struct StatusResp {
@@ -100,6 +132,7 @@ struct IndexProgress {
     #[serde(skip_serializing_if = "Option::is_none")]
     strategy: Option<String>,
     is_reindex: bool,
+    offline: bool,
 }
 
 #[derive(Deserialize, Default)]
@@ -110,6 +143,9 @@ pub struct ReindexReq {
     pub to: Option<u64>,
     #[serde(default)]
     pub strategy: Option<String>,
+    /// Rebuild from the log archive, without RPC calls
+    #[serde(default)]
+    pub offline: bool,
 }
 
 // ============================================================================
@@ -133,6 +169,7 @@ async fn get_status(State(app): State<Arc<App>>) -> Json<StatusResp> {
             current: i.current,
             strategy: i.strategy.clone(),
             is_reindex: i.is_reindex,
+            offline: i.offline,
         }),
     })
 }
@@ -172,6 +209,7 @@ async fn reindex(
         current: 0,
         strategy: req.strategy,
         is_reindex: true,
+        offline: req.offline,
     });
     drop(s);
     
@@ -194,6 +232,41 @@ async fn reset(State(app): State<Arc<App>>) -> Json<Resp> {
     Json(Resp { ok: true, msg: "reset to default state".into() })
 }
 
+/// Streams a table as CSV, JSONL or Parquet (see [`crate::export`]).
+///
+/// The `200` goes out before the first row is read, so a failure after it
+/// (a database error mid-export) cannot change the status. The body then
+/// ends in an error instead: the connection is dropped without the chunked
+/// terminator (a reset stream on HTTP/2), and clients see a failed download
+/// rather than a truncated file that looks whole.
+async fn export_table(
+    State(app): State<Arc<App>>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, (StatusCode, Json<Resp>)> {
+    let fail = |code, msg: String| (code, Json(Resp { ok: false, msg }));
+    let db = app.db.clone().ok_or_else(|| fail(StatusCode::SERVICE_UNAVAILABLE, "no database attached".into()))?;
+    let export = Export::new(&db, app.tables(), &query).await.map_err(|e| fail(StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let format = export.format;
+    let file = format!("{}.{}", export.table(), format.extension());
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{file}\"")),
+        ],
+        export_body(export::stream(db, export)),
+    )
+        .into_response())
+}
+
+/// The chunks as a response body that ends in an error if the export does.
+fn export_body(chunks: export::Chunks) -> Body {
+    Body::from_stream(futures_util::stream::unfold(chunks, |mut chunks| async move {
+        let chunk = chunks.next().await?;
+        Some((chunk.map_err(std::io::Error::other), chunks))
+    }))
+}
+
 // ============================================================================
 // Auth & Router
 // ============================================================================
@@ -231,9 +304,17 @@ pub fn router_with_dashboard(app: Arc<App>, dashboard_path: Option<PathBuf>) ->
         .route("/resume", post(resume))
         .route("/reindex", post(reindex))
         .route("/reset", post(reset))
+        .route("/export", get(export_table))
         .layer(middleware::from_fn_with_state(app.clone(), auth))
         .with_state(app.clone());
 
+    // Read-only query routes (NO AUTH): the API key can pause, reset and
+    // reindex, so readers never need it
+    let read_api_routes = Router::new()
+        .merge(query::routes())
+        .merge(graphql::routes(&app))
+        .with_state(app.clone());
+
     // Public routes (NO AUTH)
     let public_routes: Router<()> = Router::new()
         .route("/health", get(health));
@@ -262,9 +343,118 @@ pub fn router_with_dashboard(app: Arc<App>, dashboard_path: Option<PathBuf>) ->
         router = router.merge(dr);
     }
     
-    // Add API routes under /api prefix (with auth middleware baked in)
-    router = router.nest("/api", protected_api_routes);
+    // Add API routes under /api prefix (auth middleware baked into the protected ones)
+    router = router.nest("/api", protected_api_routes.merge(read_api_routes));
 
     // Apply CORS globally
     router.layer(cors)
 }
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+    use crate::export::Format;
+
+    #[tokio::test]
+    async fn only_control_routes_need_the_key() {
+        use tower::Service;
+
+        let mut router = router(App::new("key".into()));
+        let mut status = async |method: Method, uri: &str, key: Option<&str>| {
+            let mut req = Request::builder().method(method).uri(uri);
+            if let Some(key) = key {
+                req = req.header("X-API-Key", key);
+            }
+            router.call(req.body(axum::body::Body::empty()).unwrap()).await.unwrap().status()
+        };
+
+        assert_eq!(status(Method::POST, "/api/reset", None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(Method::POST, "/api/pause", Some("nope")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(Method::POST, "/api/pause", Some("key")).await, StatusCode::OK);
+        assert_eq!(status(Method::GET, "/api/export?table=badge_claims", None).await, StatusCode::UNAUTHORIZED);
+        // No database behind this app: past the auth, the read routes answer 503
+        assert_eq!(status(Method::GET, "/api/badges/3/holders", None).await, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(status(Method::GET, "/api/vaults/transactions", Some("nope")).await, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(status(Method::GET, "/api/graphql", None).await, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    /// The raw HTTP/1.1 response to an export of `slow_accounts`, read until
+    /// the server closes the connection. With `interrupt`, the export's
+    /// database connection is killed once the body started.
+    async fn raw_export(db: &PgPool, format: Format, interrupt: bool) -> String {
+        let tables = QueryTables { accounts: "slow_accounts".into(), ..Default::default() };
+        let query = ExportQuery { table: "slow_accounts".into(), format, ..Default::default() };
+        let export = Export::new(db, &tables, &query).await.unwrap();
+        let body = export_body(export::stream(db.clone(), export));
+        let body = Arc::new(std::sync::Mutex::new(Some(body)));
+        let app = Router::new().route("/", get(move || async move { body.lock().unwrap().take().unwrap() }));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let mut conn = tokio::net::TcpStream::connect(addr).await.unwrap();
+        conn.write_all(b"GET / HTTP/1.1\r\nHost: test\r\nConnection: close\r\n\r\n").await.unwrap();
+
+        let mut raw = Vec::new();
+        let mut buf = vec![0u8; 64 * 1024];
+        let mut interrupted = !interrupt;
+        loop {
+            let n = match conn.read(&mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            raw.extend_from_slice(&buf[..n]);
+            if !interrupted && raw.len() > 16 * 1024 {
+                let killed: Vec<bool> = sqlx::query_scalar(
+                    "SELECT pg_terminate_backend(pid) FROM pg_stat_activity
+                     WHERE datname = current_database() AND state = 'active' AND query LIKE 'SELECT row_to_json(r)%'",
+                )
+                .fetch_all(db)
+                .await
+                .unwrap();
+                assert_eq!(killed, [true], "{format:?}: export query not running");
+                interrupted = true;
+            }
+        }
+        String::from_utf8_lossy(&raw).into_owned()
+    }
+
+    // The body has to be read while the export runs, as in the server
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn failed_export_aborts_the_body() {
+        let db = crate::testing::fresh_db("export_abort").await;
+        sqlx::query(
+            "INSERT INTO super_accounts (account, username, eoas, level, noun, total_points, total_badges, last_update_block_number)
+             SELECT '0x' || lpad(to_hex(n), 40, '0'), 'user' || n, '{}', 0, '{}', 0, 0, n
+             FROM generate_series(1, 50000) AS n",
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        // Pauses near the end, so the export is still reading when killed.
+        // The index keeps the export's ORDER BY from sorting (and so reading)
+        // every row before the first one goes out.
+        for sql in [
+            "CREATE INDEX ON super_accounts (last_update_block_number, account)",
+            "ANALYZE super_accounts",
+            "CREATE VIEW slow_accounts AS SELECT * FROM super_accounts
+             WHERE last_update_block_number <> 45000 OR pg_sleep(1)::text IS NOT NULL",
+        ] {
+            sqlx::query(sql).execute(&db).await.unwrap();
+        }
+
+        for format in [Format::Csv, Format::Parquet] {
+            let whole = raw_export(&db, format, false).await;
+            assert!(whole.starts_with("HTTP/1.1 200"), "{format:?}: {}", &whole[..whole.len().min(200)]);
+            assert!(whole.ends_with("\r\n0\r\n\r\n"), "{format:?}: complete export without its last chunk");
+
+            let cut = raw_export(&db, format, true).await;
+            assert!(cut.starts_with("HTTP/1.1 200"), "{format:?}: {}", &cut[..cut.len().min(200)]);
+            assert!(cut.len() < whole.len(), "{format:?}: export was not interrupted");
+            assert!(!cut.ends_with("\r\n0\r\n\r\n"), "{format:?}: failed export ended cleanly");
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Raw log archive: every log fetched with `eth_getLogs`, kept as the RPC
+//! returned it in `raw_logs`, keyed by `(chain_id, block_number, log_index)`.
+//!
+//! Strategies only keep the columns they project. With `archive.enabled`,
+//! the RPC client goes through an [`ArchiveLayer`], so the address, topics,
+//! data and hashes of each log survive, together with the blocks
+//! (`block_cache`) and receipts (`receipt_cache`) strategies fetched. The
+//! projections can then be rebuilt from the database (see [`crate::replay`]).
+
+use std::collections::HashMap;
+use std::task::{Context, Poll};
+
+use alloy::primitives::{Address, B256, Bytes, LogData};
+use alloy::rpc::json_rpc::{Id, RequestPacket, ResponsePacket, ResponsePayload};
+use alloy::rpc::types::Log;
+use alloy::transports::{TransportError, TransportErrorKind, TransportFut};
+use eyre::{Result, eyre};
+use serde_json::Value;
+use sqlx::{PgPool, QueryBuilder};
+use tower::{Layer, Service};
+
+pub const RAW_LOGS_TABLE: &str = "raw_logs";
+/// Number, hash and timestamp of every block fetched.
+pub const BLOCK_CACHE_TABLE: &str = "block_cache";
+/// Every receipt fetched, as JSON.
+pub const RECEIPT_CACHE_TABLE: &str = "receipt_cache";
+
+/// Writes logs, blocks and receipts of one chain.
+#[derive(Clone, Debug)]
+pub struct LogArchive {
+    db: PgPool,
+    chain_id: u64,
+}
+
+impl LogArchive {
+    pub fn new(db: PgPool, chain_id: u64) -> Self {
+        Self { db, chain_id }
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    pub fn db(&self) -> &PgPool {
+        &self.db
+    }
+
+    /// Stores `logs`; logs already archived are left as they are, pending
+    /// ones (without a block) are skipped. Returns the number of new rows.
+    pub async fn store(&self, logs: &[Log]) -> Result<u64> {
+        const BATCH_SIZE: usize = 5_000;
+
+        let mined: Vec<&Log> = logs.iter().filter(|l| l.block_number.is_some() && l.log_index.is_some()).collect();
+        let mut written = 0;
+        for chunk in mined.chunks(BATCH_SIZE) {
+            let mut qb = QueryBuilder::new(format!(
+                "INSERT INTO {RAW_LOGS_TABLE}
+                 (chain_id, block_number, log_index, address, topics, data, tx_hash, tx_index, block_hash, block_timestamp) "
+            ));
+            qb.push_values(chunk, |mut b, log| {
+                let topics: Vec<String> = log.topics().iter().map(|t| format!("{t:#x}")).collect();
+                b.push_bind(self.chain_id as i64)
+                    .push_bind(log.block_number.unwrap_or_default() as i64)
+                    .push_bind(log.log_index.unwrap_or_default() as i32)
+                    .push_bind(format!("{:#x}", log.address()))
+                    .push_bind(topics)
+                    .push_bind(log.data().data.to_vec())
+                    .push_bind(log.transaction_hash.map(|h| format!("{h:#x}")).unwrap_or_default())
+                    .push_bind(log.transaction_index.map(|i| i as i32))
+                    .push_bind(log.block_hash.map(|h| format!("{h:#x}")).unwrap_or_default())
+                    .push_bind(log.block_timestamp.map(|t| t as i64));
+            });
+            qb.push(" ON CONFLICT (chain_id, block_number, log_index) DO NOTHING");
+            written += qb.build().execute(&self.db).await?.rows_affected();
+        }
+        Ok(written)
+    }
+
+    /// Stores `(number, hash, timestamp)` of fetched blocks.
+    pub async fn store_blocks(&self, blocks: &[(u64, String, u64)]) -> Result<u64> {
+        if blocks.is_empty() {
+            return Ok(0);
+        }
+        let mut qb = QueryBuilder::new(format!(
+            "INSERT INTO {BLOCK_CACHE_TABLE} (chain_id, block_number, block_hash, timestamp) "
+        ));
+        qb.push_values(blocks, |mut b, (number, hash, timestamp)| {
+            b.push_bind(self.chain_id as i64)
+                .push_bind(*number as i64)
+                .push_bind(hash)
+                .push_bind(*timestamp as i64);
+        });
+        qb.push(" ON CONFLICT (chain_id, block_number) DO NOTHING");
+        Ok(qb.build().execute(&self.db).await?.rows_affected())
+    }
+
+    /// Stores `(tx_hash, block_number, receipt)` of fetched receipts.
+    pub async fn store_receipts(&self, receipts: &[(String, u64, Value)]) -> Result<u64> {
+        const BATCH_SIZE: usize = 1_000;
+
+        let mut written = 0;
+        for chunk in receipts.chunks(BATCH_SIZE) {
+            let mut qb = QueryBuilder::new(format!(
+                "INSERT INTO {RECEIPT_CACHE_TABLE} (chain_id, tx_hash, block_number, receipt) "
+            ));
+            qb.push_values(chunk, |mut b, (hash, number, receipt)| {
+                b.push_bind(self.chain_id as i64)
+                    .push_bind(hash)
+                    .push_bind(*number as i64)
+                    .push_bind(receipt);
+            });
+            qb.push(" ON CONFLICT (chain_id, tx_hash) DO NOTHING");
+            written += qb.build().execute(&self.db).await?.rows_affected();
+        }
+        Ok(written)
+    }
+
+    /// Archives what the RPC answered to the archived methods in `requests`.
+    async fn record(&self, requests: &HashMap<Id, String>, responses: &ResponsePacket) -> Result<()> {
+        let mut logs = Vec::new();
+        let mut blocks = Vec::new();
+        let mut receipts = Vec::new();
+        for response in responses.responses() {
+            let (Some(method), ResponsePayload::Success(payload)) = (requests.get(&response.id), &response.payload)
+            else {
+                continue;
+            };
+            match method.as_str() {
+                "eth_getLogs" => logs.extend(serde_json::from_str::<Vec<Log>>(payload.get())?),
+                "eth_getBlockByNumber" | "eth_getBlockByHash" => {
+                    let block: Value = serde_json::from_str(payload.get())?;
+                    if let (Some(number), Some(hash), Some(timestamp)) =
+                        (hex_u64(&block["number"]), block["hash"].as_str(), hex_u64(&block["timestamp"]))
+                    {
+                        blocks.push((number, hash.to_lowercase(), timestamp));
+                    }
+                }
+                "eth_getTransactionReceipt" => {
+                    let receipt: Value = serde_json::from_str(payload.get())?;
+                    if let (Some(hash), Some(number)) =
+                        (receipt["transactionHash"].as_str(), hex_u64(&receipt["blockNumber"]))
+                    {
+                        receipts.push((hash.to_lowercase(), number, receipt));
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.store(&logs).await?;
+        self.store_blocks(&blocks).await?;
+        self.store_receipts(&receipts).await?;
+        Ok(())
+    }
+}
+
+/// `"0x1a"` as a number.
+pub(crate) fn hex_u64(v: &Value) -> Option<u64> {
+    u64::from_str_radix(v.as_str()?.strip_prefix("0x")?, 16).ok()
+}
+
+/// A row of `raw_logs`, as selected by [`RAW_LOG_COLUMNS`].
+pub type RawLogRow = (i64, i32, String, Vec<String>, Vec<u8>, String, Option<i32>, String, Option<i64>);
+
+/// Columns of `raw_logs` in [`RawLogRow`] order.
+pub const RAW_LOG_COLUMNS: &str =
+    "block_number, log_index, address, topics, data, tx_hash, tx_index, block_hash, block_timestamp";
+
+/// Turns an archived row back into the log the RPC returned.
+pub fn log_from_row(row: RawLogRow) -> Result<Log> {
+    let (block_number, log_index, address, topics, data, tx_hash, tx_index, block_hash, block_timestamp) = row;
+    let topics = topics.iter().map(|t| t.parse::<B256>()).collect::<Result<Vec<_>, _>>()?;
+    let inner = LogData::new(topics, Bytes::from(data)).ok_or_else(|| eyre!("archived log with more than 4 topics"))?;
+    Ok(Log {
+        inner: alloy::primitives::Log { address: address.parse::<Address>()?, data: inner },
+        block_hash: Some(block_hash.parse()?),
+        block_number: Some(block_number as u64),
+        block_timestamp: block_timestamp.map(|t| t as u64),
+        transaction_hash: Some(tx_hash.parse()?),
+        transaction_index: tx_index.map(|i| i as u64),
+        log_index: Some(log_index as u64),
+        removed: false,
+    })
+}
+
+/// Transport layer that archives the answers to `eth_getLogs`,
+/// `eth_getBlockBy*` and `eth_getTransactionReceipt` (batched or not)
+/// before handing them back.
+#[derive(Clone, Debug)]
+pub struct ArchiveLayer {
+    archive: LogArchive,
+}
+
+impl ArchiveLayer {
+    pub fn new(archive: LogArchive) -> Self {
+        Self { archive }
+    }
+}
+
+impl<S> Layer<S> for ArchiveLayer {
+    type Service = ArchiveService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        ArchiveService { inner, archive: self.archive.clone() }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ArchiveService<S> {
+    inner: S,
+    archive: LogArchive,
+}
+
+impl<S> Service<RequestPacket> for ArchiveService<S>
+where
+    S: Service<RequestPacket, Response = ResponsePacket, Error = TransportError, Future = TransportFut<'static>>,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: RequestPacket) -> Self::Future {
+        let archived: HashMap<Id, String> = req
+            .requests()
+            .iter()
+            .filter(|r| {
+                matches!(
+                    r.method(),
+                    "eth_getLogs" | "eth_getBlockByNumber" | "eth_getBlockByHash" | "eth_getTransactionReceipt"
+                )
+            })
+            .map(|r| (r.id().clone(), r.method().to_string()))
+            .collect();
+        let archive = self.archive.clone();
+        let fut = self.inner.call(req);
+        Box::pin(async move {
+            let res = fut.await?;
+            if !archived.is_empty() {
+                // What is not archived must not be projected either
+                archive
+                    .record(&archived, &res)
+                    .await
+                    .map_err(|e| TransportErrorKind::custom_str(&format!("archiving rpc results: {e}")))?;
+            }
+            Ok(res)
+        })
+    }
+}
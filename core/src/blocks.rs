@@ -0,0 +1,116 @@
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+
+use alloy::eips::BlockNumberOrTag;
+use alloy::primitives::TxHash;
+use alloy::providers::Provider;
+use alloy::rpc::client::BatchRequest;
+use alloy::rpc::types::{Log, TransactionReceipt};
+use chrono::{DateTime, TimeZone, Utc};
+use eyre::{Result, eyre};
+use futures_util::{StreamExt, TryStreamExt, stream};
+
+/// Block timestamps for the logs of one chunk, fetched once per block.
+///
+/// Most RPCs leave `Log::block_timestamp` empty, so strategies that store a
+/// time need one `eth_getBlockByNumber` per distinct block.
+pub struct BlockTimestamps<P> {
+    provider: P,
+    cache: HashMap<u64, DateTime<Utc>>,
+}
+
+impl<P: Provider> BlockTimestamps<P> {
+    pub fn new(provider: P) -> Self {
+        Self { provider, cache: HashMap::new() }
+    }
+
+    /// Time of the block that emitted `log`.
+    pub async fn for_log(&mut self, log: &Log) -> Result<DateTime<Utc>> {
+        if let Some(ts) = log.block_timestamp {
+            return to_datetime(ts);
+        }
+        let n = log.block_number.ok_or_else(|| eyre!("log without block number (pending?)"))?;
+        self.get(n).await
+    }
+
+    pub async fn get(&mut self, block: u64) -> Result<DateTime<Utc>> {
+        if let Some(&cached) = self.cache.get(&block) {
+            return Ok(cached);
+        }
+        let datetime = fetch(&self.provider, block).await?;
+        self.cache.insert(block, datetime);
+        Ok(datetime)
+    }
+
+    /// Fetches the uncached ones among `blocks`, `concurrency` requests at a time.
+    pub async fn prefetch(&mut self, blocks: impl IntoIterator<Item = u64>, concurrency: usize) -> Result<()> {
+        let missing: BTreeSet<u64> = blocks.into_iter().filter(|b| !self.cache.contains_key(b)).collect();
+        let provider = &self.provider;
+        let fetched: Vec<(u64, DateTime<Utc>)> = stream::iter(missing)
+            .map(|b| async move { fetch(provider, b).await.map(|t| (b, t)) })
+            .buffer_unordered(concurrency.max(1))
+            .try_collect()
+            .await?;
+        self.cache.extend(fetched);
+        Ok(())
+    }
+}
+
+async fn fetch<P: Provider>(provider: &P, block: u64) -> Result<DateTime<Utc>> {
+    let ts = provider
+        .get_block_by_number(BlockNumberOrTag::Number(block))
+        .await?
+        .map(|b| b.header.timestamp)
+        .ok_or_else(|| eyre!("block {block} not found"))?;
+    to_datetime(ts)
+}
+
+/// Receipts of `hashes`, fetched with JSON-RPC batches of `batch_size` calls
+/// instead of one round trip each. Unknown transactions map to `None`.
+pub async fn receipts<P: Provider>(
+    provider: &P,
+    hashes: impl IntoIterator<Item = TxHash>,
+    batch_size: usize,
+) -> Result<HashMap<TxHash, Option<TransactionReceipt>>> {
+    let hashes: Vec<TxHash> = hashes.into_iter().collect::<BTreeSet<_>>().into_iter().collect();
+    let mut out = HashMap::with_capacity(hashes.len());
+
+    for chunk in hashes.chunks(batch_size.max(1)) {
+        let mut batch = BatchRequest::new(provider.client());
+        let waiters = chunk
+            .iter()
+            .map(|h| batch.add_call::<_, Option<TransactionReceipt>>("eth_getTransactionReceipt", &(h,)))
+            .collect::<Result<Vec<_>, _>>()?;
+        batch.send().await?;
+        for (h, w) in chunk.iter().zip(waiters) {
+            out.insert(*h, w.await?);
+        }
+    }
+    Ok(out)
+}
+
+fn to_datetime(ts: u64) -> Result<DateTime<Utc>> {
+    Utc.timestamp_opt(ts as i64, 0).single().ok_or_else(|| eyre!("invalid block timestamp {ts}"))
+}
+
+/// Strips NULs (rejected by Postgres TEXT) and control characters other than
+/// `\n`, `\r` and `\t`. Returns the number of NULs removed.
+pub fn sanitize_text(s: &str) -> (Cow<'_, str>, usize) {
+    let mut nul_count = 0usize;
+    let cleaned: String = s
+        .chars()
+        .filter(|&ch| {
+            if ch == '\0' {
+                nul_count += 1;
+                return false;
+            }
+            ch >= '\u{20}' || matches!(ch, '\n' | '\r' | '\t')
+        })
+        .collect();
+
+    if cleaned.len() == s.len() {
+        (Cow::Borrowed(s), 0)
+    } else {
+        (Cow::Owned(cleaned), nul_count)
+    }
+}
@@ -0,0 +1,318 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use alloy::providers::Provider;
+use eyre::{Result, ensure, eyre};
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::api::{App, router_with_dashboard};
+use crate::config::Config;
+use crate::indexer::{IndexerConfig, follow};
+use crate::query::QueryTables;
+use crate::resilience::RetryConfig;
+use crate::strategies::StrategyConfig;
+
+// ============================================================================
+// Settings
+// ============================================================================
+
+/// Adaptive chunk sizing, in blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkSettings {
+    pub initial: u64,
+    pub min: u64,
+    pub max: u64,
+}
+
+impl Default for ChunkSettings {
+    fn default() -> Self {
+        Self { initial: 100_000, min: 100, max: 200_000 }
+    }
+}
+
+/// Control API and dashboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiSettings {
+    pub bind: SocketAddr,
+    pub api_key: String,
+    pub dashboard: Option<PathBuf>,
+    /// Profile tables served by the query endpoints.
+    pub tables: QueryTables,
+}
+
+impl Default for ApiSettings {
+    fn default() -> Self {
+        Self {
+            bind: SocketAddr::from(([0, 0, 0, 0], 3000)),
+            api_key: "changeme".into(),
+            dashboard: default_dashboard_path(),
+            tables: QueryTables::default(),
+        }
+    }
+}
+
+impl ApiSettings {
+    /// `API_PORT`, `API_KEY` and `DASHBOARD_PATH`, falling back to the
+    /// defaults. An `API_PORT` that is not a port is an error.
+    pub fn from_env() -> Result<Self> {
+        let mut s = Self::default();
+        if let Ok(port) = std::env::var("API_PORT") {
+            let port = port.parse::<u16>().map_err(|e| eyre!("API_PORT={port:?} is invalid: {e}"))?;
+            s.bind.set_port(port);
+        }
+        if let Ok(key) = std::env::var("API_KEY") {
+            s.api_key = key;
+        }
+        if let Ok(path) = std::env::var("DASHBOARD_PATH") {
+            s.dashboard = Some(PathBuf::from(path));
+        }
+        Ok(s)
+    }
+}
+
+/// `dashboard/dist` relative to the current dir, if it was built.
+fn default_dashboard_path() -> Option<PathBuf> {
+    [PathBuf::from("dashboard/dist"), PathBuf::from("../dashboard/dist")]
+        .into_iter()
+        .find(|p| p.exists())
+}
+
+// ============================================================================
+// Builder
+// ============================================================================
+
+/// Typed replacement for the positional `run_indexer_and_follow` arguments.
+///
+/// ```ignore
+/// IndexerBuilder::new()
+///     .provider(provider)
+///     .db(pool)
+///     .strategies(strategies)
+///     .chunk_size(100_000)
+///     .confirmations(32)
+///     .poll_interval(Duration::from_secs(5))
+///     .api(ApiSettings::from_env()?)
+///     .run()
+///     .await?;
+/// ```
+pub struct IndexerBuilder<P>
+where
+    P: Provider + Clone + Send + Sync + 'static,
+{
+    provider: Option<P>,
+    offline: Option<P>,
+    db: Option<PgPool>,
+    strategies: Vec<StrategyConfig<P>>,
+    chunking: ChunkSettings,
+    confirmations: u64,
+    poll_interval: Duration,
+    api: Option<ApiSettings>,
+    retry: RetryConfig,
+}
+
+impl<P> Default for IndexerBuilder<P>
+where
+    P: Provider + Clone + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self {
+            provider: None,
+            offline: None,
+            db: None,
+            strategies: Vec::new(),
+            chunking: ChunkSettings::default(),
+            confirmations: 32,
+            poll_interval: Duration::from_secs(5),
+            api: Some(ApiSettings::default()),
+            retry: RetryConfig::default(),
+        }
+    }
+}
+
+impl<P> IndexerBuilder<P>
+where
+    P: Provider + Clone + Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Chunking, finality, polling, API and retry settings from a loaded [`Config`].
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            chunking: config.chunk_settings(),
+            confirmations: config.indexer.confirmations,
+            poll_interval: config.poll_interval(),
+            api: config.api_settings(),
+            retry: config.retry_config(),
+            ..Self::default()
+        }
+    }
+
+    pub fn provider(mut self, provider: P) -> Self {
+        self.provider = Some(provider);
+        self
+    }
+
+    /// Provider replaying the log archive, used by offline reindexes
+    /// (see [`crate::replay::provider`]).
+    pub fn offline(mut self, provider: P) -> Self {
+        self.offline = Some(provider);
+        self
+    }
+
+    pub fn db(mut self, db: PgPool) -> Self {
+        self.db = Some(db);
+        self
+    }
+
+    pub fn strategy(mut self, strategy: StrategyConfig<P>) -> Self {
+        self.strategies.push(strategy);
+        self
+    }
+
+    pub fn strategies(mut self, strategies: impl IntoIterator<Item = StrategyConfig<P>>) -> Self {
+        self.strategies.extend(strategies);
+        self
+    }
+
+    /// Initial chunk size; bounds default to `100 ..= 2 * initial`.
+    pub fn chunk_size(mut self, initial: u64) -> Self {
+        self.chunking = ChunkSettings { initial, min: 100.min(initial), max: initial * 2 };
+        self
+    }
+
+    pub fn chunking(mut self, chunking: ChunkSettings) -> Self {
+        self.chunking = chunking;
+        self
+    }
+
+    /// Blocks behind head considered final.
+    pub fn confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    pub fn api(mut self, api: ApiSettings) -> Self {
+        self.api = Some(api);
+        self
+    }
+
+    /// Runs without the HTTP control plane (tests, embedding).
+    pub fn without_api(mut self) -> Self {
+        self.api = None;
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryConfig) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn build(self) -> Result<Indexer<P>> {
+        let provider = self.provider.ok_or_else(|| eyre!("IndexerBuilder: provider is required"))?;
+        let db = self.db.ok_or_else(|| eyre!("IndexerBuilder: db is required"))?;
+        let ChunkSettings { initial, min, max } = self.chunking;
+        ensure!(min > 0, "chunk min must be > 0");
+        ensure!(min <= initial && initial <= max, "chunk sizes must satisfy min <= initial <= max");
+        ensure!(!self.poll_interval.is_zero(), "poll interval must be > 0");
+
+        let app = match &self.api {
+            Some(api) => App::with_db(api.api_key.clone(), db.clone(), api.tables.clone()),
+            None => App::new(String::new()),
+        };
+        Ok(Indexer {
+            provider,
+            offline: self.offline,
+            db,
+            strategies: self.strategies,
+            config: IndexerConfig::with_bounds(initial, min, max, self.retry),
+            confirmations: self.confirmations,
+            poll_interval: self.poll_interval,
+            api: self.api,
+            app,
+        })
+    }
+
+    pub async fn run(self) -> Result<()> {
+        self.build()?.run().await
+    }
+}
+
+// ============================================================================
+// Indexer
+// ============================================================================
+
+/// A configured indexer, ready to follow the chain.
+pub struct Indexer<P>
+where
+    P: Provider + Clone + Send + Sync + 'static,
+{
+    provider: P,
+    offline: Option<P>,
+    db: PgPool,
+    strategies: Vec<StrategyConfig<P>>,
+    config: IndexerConfig,
+    confirmations: u64,
+    poll_interval: Duration,
+    api: Option<ApiSettings>,
+    app: Arc<App>,
+}
+
+impl<P> Indexer<P>
+where
+    P: Provider + Clone + Send + Sync + 'static,
+{
+    /// Shared control state (pause, reindex, progress), also served by the API.
+    pub fn app(&self) -> Arc<App> {
+        self.app.clone()
+    }
+
+    pub fn strategies(&self) -> &[StrategyConfig<P>] {
+        &self.strategies
+    }
+
+    /// Starts the API (if enabled) and follows the chain until an error occurs.
+    pub async fn run(self) -> Result<()> {
+        info!(
+            chunk_size = self.config.chunk_manager.get(),
+            confirmations = self.confirmations,
+            poll_ms = self.poll_interval.as_millis() as u64,
+            "initialized adaptive chunk manager"
+        );
+
+        if let Some(api) = &self.api {
+            if api.dashboard.is_some() {
+                info!("API: http://{} (endpoints: /api/*, /dashboard)", api.bind);
+            } else {
+                info!("API: http://{} (endpoints: /status, /pause, /resume, /reindex, /export, /accounts, /badges, /vaults)", api.bind);
+                info!("Dashboard not found. Set DASHBOARD_PATH or build dashboard with 'npm run build'");
+            }
+            let r = router_with_dashboard(self.app.clone(), api.dashboard.clone());
+            let listener = tokio::net::TcpListener::bind(api.bind).await?;
+            tokio::spawn(async move {
+                axum::serve(listener, r).await.ok();
+            });
+        }
+
+        follow(
+            self.provider,
+            self.offline,
+            &self.db,
+            self.strategies,
+            &self.config,
+            self.confirmations,
+            self.poll_interval,
+            self.app,
+        )
+        .await
+    }
+}
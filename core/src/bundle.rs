@@ -0,0 +1,297 @@
+//! Portable log bundles: the archive of a block range (see [`crate::archive`])
+//! written to disk, so a new environment can load it instead of fetching it.
+//!
+//! A bundle is a directory with a `manifest.json` and one zstd-compressed
+//! JSONL file per span of blocks. Each line is a block (number, hash and
+//! timestamp), a log as the RPC returned it, or a receipt. Importing stores
+//! them back into `raw_logs`, `block_cache` and `receipt_cache`, and the
+//! manifest's tokens into `resolved_tokens`; the range can then be replayed
+//! offline (see [`crate::replay`]) and the indexer carries on from the RPC
+//! after the bundle's last block.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use alloy::rpc::types::Log;
+use eyre::{Result, WrapErr, ensure};
+use futures_util::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::archive::{BLOCK_CACHE_TABLE, LogArchive, RAW_LOG_COLUMNS, RAW_LOGS_TABLE, RECEIPT_CACHE_TABLE, RawLogRow, log_from_row};
+use crate::resolved::{self, ResolvedToken};
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// zstd level of the range files.
+const LEVEL: i32 = 9;
+/// Lines buffered per insert while importing.
+const BATCH_SIZE: usize = 5_000;
+
+/// `manifest.json`: what the bundle covers and which files hold it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub chain_id: u64,
+    pub from_block: u64,
+    pub to_block: u64,
+    pub files: Vec<RangeFile>,
+    /// Tokens read from contracts, which replay cannot `eth_call` for.
+    #[serde(default)]
+    pub resolved_tokens: Vec<ResolvedToken>,
+}
+
+/// One range file and how many rows of each kind it holds.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RangeFile {
+    pub name: String,
+    pub from_block: u64,
+    pub to_block: u64,
+    pub blocks: u64,
+    pub logs: u64,
+    pub receipts: u64,
+}
+
+/// A line of a range file.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum Line {
+    Block { number: u64, hash: String, timestamp: u64 },
+    Log { log: Log },
+    Receipt { tx_hash: String, block_number: u64, receipt: Value },
+}
+
+/// Writes the archive of `chain_id` for `from..=to` into `dir`, one file per
+/// `span` blocks, and its manifest last (a bundle without one is incomplete).
+pub async fn export(db: &PgPool, chain_id: u64, dir: &Path, from: u64, to: u64, span: u64) -> Result<Manifest> {
+    ensure!(from <= to, "from > to");
+    ensure!(span > 0, "span must be > 0");
+    std::fs::create_dir_all(dir).wrap_err_with(|| format!("creating {}", dir.display()))?;
+
+    let mut files = Vec::new();
+    let mut start = from;
+    while start <= to {
+        let end = start.saturating_add(span - 1).min(to);
+        let file = export_range(db, chain_id, dir, start, end).await?;
+        info!(file = file.name, blocks = file.blocks, logs = file.logs, receipts = file.receipts, "range exported");
+        files.push(file);
+        start = end + 1;
+    }
+
+    let resolved_tokens = resolved::all(db).await?;
+    let manifest = Manifest { chain_id, from_block: from, to_block: to, files, resolved_tokens };
+    std::fs::write(dir.join(MANIFEST_FILE), serde_json::to_vec_pretty(&manifest)?)?;
+    Ok(manifest)
+}
+
+async fn export_range(db: &PgPool, chain_id: u64, dir: &Path, from: u64, to: u64) -> Result<RangeFile> {
+    let name = format!("{chain_id}-{from:012}-{to:012}.jsonl.zst");
+    let path = dir.join(&name);
+    let out = File::create(&path).wrap_err_with(|| format!("creating {}", path.display()))?;
+    let mut out = zstd::Encoder::new(BufWriter::new(out), LEVEL)?;
+    let mut file = RangeFile { name, from_block: from, to_block: to, blocks: 0, logs: 0, receipts: 0 };
+
+    let sql = format!(
+        "SELECT block_number, block_hash, timestamp FROM {BLOCK_CACHE_TABLE}
+         WHERE chain_id = $1 AND block_number BETWEEN $2 AND $3 ORDER BY block_number"
+    );
+    let mut blocks = sqlx::query_as::<_, (i64, String, i64)>(&sql)
+        .bind(chain_id as i64)
+        .bind(from as i64)
+        .bind(to as i64)
+        .fetch(db);
+    while let Some((number, hash, timestamp)) = blocks.try_next().await? {
+        write_line(&mut out, &Line::Block { number: number as u64, hash, timestamp: timestamp as u64 })?;
+        file.blocks += 1;
+    }
+    drop(blocks);
+
+    let sql = format!(
+        "SELECT {RAW_LOG_COLUMNS} FROM {RAW_LOGS_TABLE}
+         WHERE chain_id = $1 AND block_number BETWEEN $2 AND $3 ORDER BY block_number, log_index"
+    );
+    let mut logs = sqlx::query_as::<_, RawLogRow>(&sql)
+        .bind(chain_id as i64)
+        .bind(from as i64)
+        .bind(to as i64)
+        .fetch(db);
+    while let Some(row) = logs.try_next().await? {
+        write_line(&mut out, &Line::Log { log: log_from_row(row)? })?;
+        file.logs += 1;
+    }
+    drop(logs);
+
+    let sql = format!(
+        "SELECT tx_hash, block_number, receipt FROM {RECEIPT_CACHE_TABLE}
+         WHERE chain_id = $1 AND block_number BETWEEN $2 AND $3 ORDER BY block_number, tx_hash"
+    );
+    let mut receipts = sqlx::query_as::<_, (String, i64, Value)>(&sql)
+        .bind(chain_id as i64)
+        .bind(from as i64)
+        .bind(to as i64)
+        .fetch(db);
+    while let Some((tx_hash, block_number, receipt)) = receipts.try_next().await? {
+        write_line(&mut out, &Line::Receipt { tx_hash, block_number: block_number as u64, receipt })?;
+        file.receipts += 1;
+    }
+    drop(receipts);
+
+    out.finish()?.flush()?;
+    Ok(file)
+}
+
+fn write_line(out: &mut impl Write, line: &Line) -> Result<()> {
+    serde_json::to_writer(&mut *out, line)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Reads the manifest of the bundle in `dir`.
+pub fn manifest(dir: &Path) -> Result<Manifest> {
+    let path = dir.join(MANIFEST_FILE);
+    let raw = std::fs::read(&path).wrap_err_with(|| format!("reading {} (incomplete bundle?)", path.display()))?;
+    serde_json::from_slice(&raw).wrap_err_with(|| format!("parsing {}", path.display()))
+}
+
+/// Loads every file of the bundle in `dir` into the archive, and its
+/// resolved tokens. Rows already stored are kept. A file whose row counts
+/// differ from the manifest is an error (truncated or edited), after what it
+/// held was stored.
+pub async fn import(archive: &LogArchive, dir: &Path) -> Result<Manifest> {
+    let manifest = manifest(dir)?;
+    ensure!(
+        manifest.chain_id == archive.chain_id(),
+        "bundle is for chain {}, the archive for {}",
+        manifest.chain_id,
+        archive.chain_id()
+    );
+    for file in &manifest.files {
+        let path: PathBuf = dir.join(&file.name);
+        let found = import_file(archive, &path).await.wrap_err_with(|| format!("importing {}", path.display()))?;
+        ensure!(
+            found == (file.blocks, file.logs, file.receipts),
+            "{}: found {found:?} blocks/logs/receipts, manifest says {:?}",
+            file.name,
+            (file.blocks, file.logs, file.receipts)
+        );
+        info!(file = file.name, blocks = file.blocks, logs = file.logs, receipts = file.receipts, "range imported");
+    }
+    resolved::store(archive.db(), &manifest.resolved_tokens).await?;
+    Ok(manifest)
+}
+
+async fn import_file(archive: &LogArchive, path: &Path) -> Result<(u64, u64, u64)> {
+    let reader = BufReader::new(zstd::Decoder::new(File::open(path)?)?);
+    let (mut blocks, mut logs, mut receipts) = (Vec::new(), Vec::new(), Vec::new());
+    let mut counts = (0, 0, 0);
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line).wrap_err_with(|| format!("line {}", i + 1))? {
+            Line::Block { number, hash, timestamp } => {
+                blocks.push((number, hash, timestamp));
+                counts.0 += 1;
+            }
+            Line::Log { log } => {
+                logs.push(log);
+                counts.1 += 1;
+            }
+            Line::Receipt { tx_hash, block_number, receipt } => {
+                receipts.push((tx_hash, block_number, receipt));
+                counts.2 += 1;
+            }
+        }
+        if blocks.len() + logs.len() + receipts.len() >= BATCH_SIZE {
+            flush(archive, &mut blocks, &mut logs, &mut receipts).await?;
+        }
+    }
+    flush(archive, &mut blocks, &mut logs, &mut receipts).await?;
+    Ok(counts)
+}
+
+async fn flush(
+    archive: &LogArchive,
+    blocks: &mut Vec<(u64, String, u64)>,
+    logs: &mut Vec<Log>,
+    receipts: &mut Vec<(String, u64, Value)>,
+) -> Result<()> {
+    archive.store_blocks(blocks).await?;
+    archive.store(logs).await?;
+    archive.store_receipts(receipts).await?;
+    blocks.clear();
+    logs.clear();
+    receipts.clear();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, B256, Bytes, LogData};
+    use serde_json::json;
+
+    use super::*;
+    use crate::testing::mined_log;
+
+    /// A log of `block` carrying its timestamp, as archived logs do.
+    fn stamped(block: u64, index: u64) -> Log {
+        let data = LogData::new_unchecked(vec![B256::repeat_byte(7), B256::with_last_byte(index as u8)], Bytes::from(vec![block as u8; 40]));
+        Log {
+            block_timestamp: Some(1_700_000_000 + block),
+            ..mined_log(Address::repeat_byte(block as u8), data, block, B256::repeat_byte(index as u8), index)
+        }
+    }
+
+    /// Every archived row of `db`, as text, in a stable order.
+    async fn dump(db: &PgPool) -> Vec<String> {
+        sqlx::query_scalar(&format!(
+            "SELECT x FROM (
+                 SELECT 1 AS k, concat_ws(' ', block_number, log_index, address, topics, data, tx_hash, tx_index, block_hash, block_timestamp) AS x FROM {RAW_LOGS_TABLE}
+                 UNION ALL SELECT 2, concat_ws(' ', block_number, block_hash, timestamp) FROM {BLOCK_CACHE_TABLE}
+                 UNION ALL SELECT 3, concat_ws(' ', tx_hash, block_number, receipt) FROM {RECEIPT_CACHE_TABLE}
+                 UNION ALL SELECT 4, concat_ws(' ', contract, getter, token) FROM resolved_tokens
+             ) AS t ORDER BY k, x"
+        ))
+        .fetch_all(db)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn bundle_round_trip() {
+        let source = LogArchive::new(crate::testing::fresh_db("bundle_source").await, 10);
+        source.store(&[stamped(2, 0), stamped(2, 1), stamped(4, 0), stamped(9, 3)]).await.unwrap();
+        source.store_blocks(&(1..=5).map(|n| (n, format!("{:#x}", B256::with_last_byte(n as u8)), 1_700_000_000 + n)).collect::<Vec<_>>()).await.unwrap();
+        let receipt = json!({ "status": "0x1", "logs": [], "blockNumber": "0x4" });
+        source.store_receipts(&[(format!("{:#x}", B256::repeat_byte(0)), 4, receipt)]).await.unwrap();
+        let token = ResolvedToken { contract: format!("{:#x}", Address::repeat_byte(1)), getter: "asset".into(), token: format!("{:#x}", Address::repeat_byte(2)) };
+        resolved::store(source.db(), std::slice::from_ref(&token)).await.unwrap();
+
+        let dir = std::env::temp_dir().join(format!("bundle-round-trip-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        // Block 9 is past the bundle and stays out of it
+        let exported = export(source.db(), 10, &dir, 1, 5, 2).await.unwrap();
+        let spans: Vec<_> = exported.files.iter().map(|f| (f.from_block, f.to_block, f.blocks, f.logs, f.receipts)).collect();
+        assert_eq!(spans, [(1, 2, 2, 2, 0), (3, 4, 2, 1, 1), (5, 5, 1, 0, 0)]);
+
+        let target = LogArchive::new(crate::testing::fresh_db("bundle_target").await, 10);
+        let imported = import(&target, &dir).await.unwrap();
+        assert_eq!((imported.from_block, imported.to_block), (1, 5));
+        sqlx::query(&format!("DELETE FROM {RAW_LOGS_TABLE} WHERE block_number > 5")).execute(source.db()).await.unwrap();
+        assert_eq!(dump(target.db()).await, dump(source.db()).await);
+
+        // Importing again keeps the rows; a manifest that disagrees with its files fails
+        import(&target, &dir).await.unwrap();
+        let mut edited = manifest(&dir).unwrap();
+        edited.files[1].logs += 1;
+        std::fs::write(dir.join(MANIFEST_FILE), serde_json::to_vec(&edited).unwrap()).unwrap();
+        let err = import(&target, &dir).await.unwrap_err().to_string();
+        assert!(err.contains("manifest says (2, 2, 1)"), "{err}");
+        assert!(import(&LogArchive::new(target.db().clone(), 1), &dir).await.is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,553 @@
+use std::path::{Path, PathBuf};
+
+use alloy::providers::{DynProvider, Provider, ProviderBuilder};
+use alloy::rpc::client::ClientBuilder;
+use clap::{Parser, Subcommand};
+use eyre::{Result, bail, ensure, eyre};
+use sqlx::PgPool;
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+
+use crate::archive::{ArchiveLayer, LogArchive, RAW_LOGS_TABLE};
+use crate::builder::IndexerBuilder;
+use crate::config::{Config, ConfigSchema};
+use crate::export::{Export, ExportQuery, Format};
+use crate::indexer::{IndexerConfig, RunReport, prepare, run_indexer};
+use crate::query::QueryTables;
+use crate::strategies::{ChunkProcessor, IndexedRangeDecorator, StrategyConfig};
+use crate::{bundle, db, declarative, export, quarantine, replay};
+
+// ============================================================================
+// Arguments
+// ============================================================================
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Config file (defaults to $CONFIG_FILE, then ./indexer.toml)
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Deployment profile (overrides $PROFILE and `profile` in the config file)
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
+    /// Print the effective config (secrets masked) and exit
+    #[arg(long, global = true)]
+    pub print_config: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Follow the chain and serve the API (default)
+    Run,
+    /// Index a block range for one strategy, then exit
+    Backfill {
+        /// Strategy name, as in `[strategies.<name>]`
+        #[arg(long)]
+        strategy: String,
+        /// First block (inclusive)
+        #[arg(long)]
+        from: u64,
+        /// Last block (inclusive)
+        #[arg(long)]
+        to: u64,
+        /// Replay archived logs instead of calling the RPC
+        #[arg(long)]
+        offline: bool,
+    },
+    /// Show indexed ranges per strategy
+    Status,
+    /// Create the database tables
+    Migrate,
+    /// Check database, RPC and contract addresses
+    Verify,
+    /// Recompute stored values from chain data (e.g. badge claim times)
+    Repair {
+        /// Only this strategy (default: all)
+        #[arg(long)]
+        strategy: Option<String>,
+    },
+    /// Process again the blocks of quarantined logs, releasing those that now go through
+    RetryQuarantined {
+        /// Only this strategy (default: all)
+        #[arg(long)]
+        strategy: Option<String>,
+    },
+    /// Recompute derived state (account totals, levels) from stored rows
+    Rebuild {
+        /// Only this strategy (default: all)
+        #[arg(long)]
+        strategy: Option<String>,
+    },
+    /// Write the log archive of a block range to a bundle directory
+    Export {
+        /// Bundle directory (created if missing)
+        #[arg(long)]
+        dir: PathBuf,
+        /// First block (default: first archived block)
+        #[arg(long)]
+        from: Option<u64>,
+        /// Last block (default: last archived block)
+        #[arg(long)]
+        to: Option<u64>,
+        /// Blocks per file
+        #[arg(long, default_value_t = 100_000)]
+        span: u64,
+    },
+    /// Load a bundle into the log archive and index its range offline
+    Import {
+        /// Bundle directory, as written by `export`
+        #[arg(long)]
+        dir: PathBuf,
+        /// Only this strategy (default: all)
+        #[arg(long)]
+        strategy: Option<String>,
+        /// Only load the archive, do not index
+        #[arg(long)]
+        no_replay: bool,
+    },
+    /// Export an indexed table as CSV, JSONL or Parquet
+    Dump {
+        /// Table, e.g. badge_claims (the profile's tables under `[tables]`)
+        #[arg(long)]
+        table: String,
+        #[arg(long, value_enum, default_value_t = Format::Csv)]
+        format: Format,
+        /// First block (inclusive)
+        #[arg(long)]
+        from_block: Option<u64>,
+        /// Last block (inclusive)
+        #[arg(long)]
+        to_block: Option<u64>,
+        /// Start time, RFC 3339 or YYYY-MM-DD (inclusive)
+        #[arg(long)]
+        from_time: Option<String>,
+        /// End time, RFC 3339 or YYYY-MM-DD (exclusive)
+        #[arg(long)]
+        to_time: Option<String>,
+        /// Only rows of this account
+        #[arg(long)]
+        account: Option<String>,
+        /// Output file (default: stdout)
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Delete rows written after a block and rewind indexed ranges
+    Rollback {
+        /// Last block to keep
+        #[arg(long)]
+        to: u64,
+        /// Only this strategy (default: all)
+        #[arg(long)]
+        strategy: Option<String>,
+    },
+}
+
+// ============================================================================
+// Entry point
+// ============================================================================
+
+/// Parses the command line and runs the selected command.
+///
+/// `profiles` are the deployments the binary knows; `strategies` builds the
+/// compiled strategies for the selected one (`config.profile`). Declarative
+/// ones from `strategies_file` are appended here.
+pub async fn run<F>(profiles: &[ConfigSchema], strategies: F) -> Result<()>
+where
+    F: FnOnce(&Config) -> Vec<StrategyConfig<DynProvider>>,
+{
+    let cli = Cli::parse();
+    let config = Config::load_profile(profiles, cli.profile.as_deref(), cli.config.as_deref())?;
+    if cli.print_config {
+        print!("{config}");
+        return Ok(());
+    }
+
+    let mut strats = strategies(&config);
+    if let Some(path) = &config.strategies_file {
+        strats.extend(declarative::load_strategies(path)?);
+    }
+
+    match cli.command.unwrap_or(Command::Run) {
+        Command::Run => {
+            let db = open_db(&config).await?;
+            let (provider, chain_id) = connect_archiving(&config, &db).await?;
+            info!(strategies = ?strats, "launching indexer");
+            let mut builder = IndexerBuilder::from_config(&config).provider(provider);
+            if let Some(chain_id) = chain_id {
+                builder = builder.offline(replay::provider(db.clone(), chain_id));
+            }
+            builder.db(db).strategies(strats).run().await
+        }
+        Command::Backfill { strategy, from, to, offline } => {
+            let db = open_db(&config).await?;
+            let provider = if offline {
+                replay::provider(db.clone(), archive_chain_id(&config).await?)
+            } else {
+                connect_archiving(&config, &db).await?.0
+            };
+            backfill(&config, provider, &db, strats, &strategy, from, to).await
+        }
+        Command::Status => {
+            let db = db::connect(&config.database).await?;
+            status(&config, &db, &strats).await
+        }
+        Command::Migrate => {
+            let db = db::connect(&config.database).await?;
+            let v = db::migrate(&db).await?;
+            println!("schema at version {}", v.current);
+            Ok(())
+        }
+        Command::Verify => verify(&config).await,
+        Command::Repair { strategy } => {
+            let db = open_db(&config).await?;
+            let (provider, _) = connect_archiving(&config, &db).await?;
+            repair(provider, &db, strats, strategy.as_deref()).await
+        }
+        Command::RetryQuarantined { strategy } => {
+            let db = open_db(&config).await?;
+            let (provider, _) = connect_archiving(&config, &db).await?;
+            retry_quarantined(provider, &db, strats, strategy.as_deref()).await
+        }
+        Command::Rebuild { strategy } => {
+            let db = open_db(&config).await?;
+            rebuild(&db, strats, strategy.as_deref()).await
+        }
+        Command::Rollback { to, strategy } => {
+            let db = open_db(&config).await?;
+            rollback(&db, strats, strategy.as_deref(), to).await
+        }
+        Command::Dump { table, format, from_block, to_block, from_time, to_time, account, out } => {
+            let db = open_db(&config).await?;
+            let query = ExportQuery { table, format, from_block, to_block, from_time, to_time, account };
+            dump(&config, &db, &query, out.as_deref()).await
+        }
+        Command::Export { dir, from, to, span } => {
+            let db = open_db(&config).await?;
+            export(&config, &db, &dir, from, to, span).await
+        }
+        Command::Import { dir, strategy, no_replay } => {
+            let db = open_db(&config).await?;
+            import(&config, &db, strats, &dir, strategy.as_deref(), no_replay).await
+        }
+    }
+}
+
+/// Connects and makes sure the schema is current, migrating if allowed.
+async fn open_db(config: &Config) -> Result<PgPool> {
+    let db = db::connect(&config.database).await?;
+    if config.database.migrate {
+        db::migrate(&db).await?;
+    } else {
+        let v = db::check_schema(&db).await?;
+        ensure!(
+            v.pending == 0,
+            "database schema is at version {} but this binary needs {} (run `migrate` or set database.migrate = true)",
+            v.current,
+            v.latest
+        );
+    }
+    Ok(db)
+}
+
+async fn connect(config: &Config) -> Result<DynProvider> {
+    Ok(ProviderBuilder::new().connect(&config.chain.rpc_url).await?.erased())
+}
+
+/// Connects like [`connect`], archiving fetched logs, blocks and receipts
+/// if `archive.enabled`; then also returns the archived chain id.
+async fn connect_archiving(config: &Config, db: &PgPool) -> Result<(DynProvider, Option<u64>)> {
+    if !config.archive.enabled {
+        return Ok((connect(config).await?, None));
+    }
+    let chain_id = archive_chain_id(config).await?;
+    let client = ClientBuilder::default()
+        .layer(ArchiveLayer::new(LogArchive::new(db.clone(), chain_id)))
+        .connect(&config.chain.rpc_url)
+        .await?;
+    info!(chain_id, "archiving fetched logs");
+    Ok((ProviderBuilder::new().connect_client(client).erased(), Some(chain_id)))
+}
+
+/// Chain the archive is keyed by: `chain.chain_id`, or the RPC's if unset.
+async fn archive_chain_id(config: &Config) -> Result<u64> {
+    Ok(match config.chain.chain_id {
+        0 => connect(config).await?.get_chain_id().await?,
+        id => id,
+    })
+}
+
+fn select(
+    strategies: Vec<StrategyConfig<DynProvider>>,
+    name: Option<&str>,
+) -> Result<Vec<StrategyConfig<DynProvider>>> {
+    let Some(name) = name else {
+        return Ok(strategies);
+    };
+    let known: Vec<_> = strategies.iter().map(|s| s.name).collect();
+    let picked: Vec<_> = strategies.into_iter().filter(|s| s.name == name).collect();
+    if picked.is_empty() {
+        bail!("unknown or disabled strategy '{name}' (enabled: {})", known.join(", "));
+    }
+    Ok(picked)
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+async fn backfill(
+    config: &Config,
+    provider: DynProvider,
+    db: &PgPool,
+    strategies: Vec<StrategyConfig<DynProvider>>,
+    name: &str,
+    from: u64,
+    to: u64,
+) -> Result<()> {
+    let mut strats = select(strategies, Some(name))?;
+    for s in &mut strats {
+        s.force_reindex = true;
+    }
+    prepare(db, &strats).await?;
+    let chunks = config.chunk_settings();
+    let indexer = IndexerConfig::with_bounds(chunks.initial, chunks.min, chunks.max, config.retry_config());
+    let report = run_indexer(provider, db, from, to, &indexer, strats, None).await?;
+    check_failures(&report)?;
+    info!(strategy = name, from, to, "backfill finished");
+    Ok(())
+}
+
+/// Lists the chunks `report` says failed and errors out if there are any,
+/// so the process exits non-zero.
+fn check_failures(report: &RunReport) -> Result<()> {
+    for f in &report.failed {
+        eprintln!("FAIL  {} {}..={}: {}", f.strategy, f.from, f.to, f.error);
+    }
+    ensure!(report.failed.is_empty(), "{} chunk(s) failed, their ranges are not marked indexed", report.failed.len());
+    Ok(())
+}
+
+async fn status(config: &Config, db: &PgPool, strategies: &[StrategyConfig<DynProvider>]) -> Result<()> {
+    let rows: Vec<(String, i64, i64, Option<chrono::NaiveDateTime>)> = sqlx::query_as(
+        "SELECT strategy_name, from_block, to_block, last_updated FROM indexed_ranges ORDER BY strategy_name",
+    )
+    .fetch_all(db)
+    .await?;
+
+    let v = db::check_schema(db).await?;
+    println!("schema: version {} (latest {}, {} pending)", v.current, v.latest, v.pending);
+
+    // Head is informative only, a dead RPC should not hide the DB state
+    let head = match connect(config).await {
+        Ok(p) => p.get_block_number().await.ok(),
+        Err(_) => None,
+    };
+    let safe = head.map(|h| h.saturating_sub(config.indexer.confirmations));
+    match (head, safe) {
+        (Some(h), Some(s)) => println!("head: {h} (safe: {s})"),
+        _ => println!("head: unavailable"),
+    }
+
+    println!("{:<32} {:>12} {:>12} {:>10}  last updated", "strategy", "from", "to", "behind");
+    for s in strategies {
+        match rows.iter().find(|r| r.0 == s.name) {
+            Some((_, from, to, updated)) => {
+                let behind = safe.map(|h| h.saturating_sub(*to as u64).to_string()).unwrap_or_default();
+                let updated = updated.map(|t| t.to_string()).unwrap_or_default();
+                println!("{:<32} {from:>12} {to:>12} {behind:>10}  {updated}", s.name);
+            }
+            None => println!("{:<32} {:>12} {:>12} {:>10}", s.name, "-", "-", "never"),
+        }
+    }
+    // Rows left behind by strategies that were renamed or disabled
+    for (name, from, to, _) in rows.iter().filter(|r| !strategies.iter().any(|s| s.name == r.0)) {
+        println!("{name:<32} {from:>12} {to:>12} {:>10}", "disabled");
+    }
+
+    let quarantined: Vec<(String, String, i64)> = sqlx::query_as(
+        "SELECT strategy_name, error_kind, COUNT(*) FROM quarantined_logs GROUP BY 1, 2 ORDER BY 1, 2",
+    )
+    .fetch_all(db)
+    .await?;
+    for (name, kind, n) in quarantined {
+        println!("quarantined: {name} {kind} x{n}");
+    }
+    Ok(())
+}
+
+async fn verify(config: &Config) -> Result<()> {
+    let mut failed = 0;
+    let mut check = |what: &str, res: Result<String>| match res {
+        Ok(detail) => println!("ok    {what}: {detail}"),
+        Err(e) => {
+            failed += 1;
+            println!("FAIL  {what}: {e}");
+        }
+    };
+
+    let db = db::connect(&config.database).await;
+    match &db {
+        Ok(pool) => {
+            check("database", Ok("connected".into()));
+            check(
+                "schema",
+                db::check_schema(pool).await.and_then(|v| {
+                    ensure!(v.pending == 0, "version {} of {}, run `migrate`", v.current, v.latest);
+                    Ok(format!("version {}", v.current))
+                }),
+            );
+        }
+        Err(e) => check("database", Err(eyre!("{e}"))),
+    }
+
+    match connect(config).await {
+        Ok(provider) => {
+            let expected = config.chain.chain_id;
+            check(
+                "rpc chain id",
+                provider.get_chain_id().await.map_err(Into::into).and_then(|id| {
+                    ensure!(expected == 0 || id == expected, "{id}, profile '{}' expects {expected}", config.profile);
+                    Ok(id.to_string())
+                }),
+            );
+            check("rpc head", provider.get_block_number().await.map(|b| b.to_string()).map_err(Into::into));
+            let lists = config.contract_lists.iter().flat_map(|(key, addrs)| addrs.iter().map(move |a| (key, a)));
+            for (key, address) in config.contracts.iter().chain(lists) {
+                let code = provider.get_code_at(*address).await;
+                check(
+                    &format!("contract {key}"),
+                    code.map_err(Into::into).and_then(|c| {
+                        if c.is_empty() {
+                            Err(eyre!("no code at {address}"))
+                        } else {
+                            Ok(format!("{address} ({} bytes)", c.len()))
+                        }
+                    }),
+                );
+            }
+        }
+        Err(e) => check("rpc", Err(e)),
+    }
+
+    if failed > 0 {
+        bail!("{failed} check(s) failed");
+    }
+    Ok(())
+}
+
+async fn repair(
+    provider: DynProvider,
+    db: &PgPool,
+    strategies: Vec<StrategyConfig<DynProvider>>,
+    name: Option<&str>,
+) -> Result<()> {
+    for s in select(strategies, name)? {
+        let updated = s.processor.repair(provider.clone(), db).await?;
+        println!("{:<32} {updated} row(s) repaired", s.name);
+    }
+    Ok(())
+}
+
+async fn retry_quarantined(
+    provider: DynProvider,
+    db: &PgPool,
+    strategies: Vec<StrategyConfig<DynProvider>>,
+    name: Option<&str>,
+) -> Result<()> {
+    let strats = select(strategies, name)?;
+    prepare(db, &strats).await?;
+    for s in strats {
+        let (blocks, left) = quarantine::retry(provider.clone(), db, &s).await?;
+        println!("{:<32} {blocks} block(s) retried, {left} log(s) still quarantined", s.name);
+    }
+    Ok(())
+}
+
+async fn rebuild(db: &PgPool, strategies: Vec<StrategyConfig<DynProvider>>, name: Option<&str>) -> Result<()> {
+    for s in select(strategies, name)? {
+        let updated = s.processor.rebuild(db).await?;
+        println!("{:<32} {updated} row(s) rebuilt", s.name);
+    }
+    Ok(())
+}
+
+async fn rollback(
+    db: &PgPool,
+    strategies: Vec<StrategyConfig<DynProvider>>,
+    name: Option<&str>,
+    to: u64,
+) -> Result<()> {
+    for s in select(strategies, name)? {
+        let processor = IndexedRangeDecorator::new(s.processor, s.name, false);
+        let removed = processor.rollback(db, to).await?;
+        println!("{:<32} {removed} row(s) removed", s.name);
+    }
+    Ok(())
+}
+
+async fn export(config: &Config, db: &PgPool, dir: &Path, from: Option<u64>, to: Option<u64>, span: u64) -> Result<()> {
+    let chain_id = archive_chain_id(config).await?;
+    let (first, last): (Option<i64>, Option<i64>) = sqlx::query_as(&format!(
+        "SELECT MIN(block_number), MAX(block_number) FROM {RAW_LOGS_TABLE} WHERE chain_id = $1"
+    ))
+    .bind(chain_id as i64)
+    .fetch_one(db)
+    .await?;
+    let (Some(from), Some(to)) = (from.or(first.map(|b| b as u64)), to.or(last.map(|b| b as u64))) else {
+        bail!("nothing archived for chain {chain_id} (enable archive.enabled or pass --from and --to)");
+    };
+    let manifest = bundle::export(db, chain_id, dir, from, to, span).await?;
+    println!("{} file(s), blocks {from}..={to}, in {}", manifest.files.len(), dir.display());
+    Ok(())
+}
+
+/// Loads the bundle, then indexes its range from the archive, so that `run`
+/// only asks the RPC for the blocks after it.
+async fn import(
+    config: &Config,
+    db: &PgPool,
+    strategies: Vec<StrategyConfig<DynProvider>>,
+    dir: &Path,
+    name: Option<&str>,
+    no_replay: bool,
+) -> Result<()> {
+    // The bundle says which chain it holds; only an explicit config can disagree
+    let chain_id = match config.chain.chain_id {
+        0 => bundle::manifest(dir)?.chain_id,
+        id => id,
+    };
+    let manifest = bundle::import(&LogArchive::new(db.clone(), chain_id), dir).await?;
+    println!("{} file(s) imported, blocks {}..={}", manifest.files.len(), manifest.from_block, manifest.to_block);
+    if no_replay {
+        return Ok(());
+    }
+
+    let strats = select(strategies, name)?;
+    prepare(db, &strats).await?;
+    let chunks = config.chunk_settings();
+    let indexer = IndexerConfig::with_bounds(chunks.initial, chunks.min, chunks.max, config.retry_config());
+    let provider = replay::provider(db.clone(), chain_id);
+    let report = run_indexer(provider, db, manifest.from_block, manifest.to_block, &indexer, strats, None).await?;
+    check_failures(&report)?;
+    info!(from = manifest.from_block, to = manifest.to_block, "bundle indexed, `run` continues from the rpc");
+    Ok(())
+}
+
+async fn dump(config: &Config, db: &PgPool, query: &ExportQuery, out: Option<&Path>) -> Result<()> {
+    let export = Export::new(db, &QueryTables::from_tables(&config.tables), query).await?;
+    let mut writer: Box<dyn tokio::io::AsyncWrite + Unpin + Send> = match out {
+        Some(path) => Box::new(tokio::fs::File::create(path).await?),
+        None => Box::new(tokio::io::stdout()),
+    };
+    let mut chunks = export::stream(db.clone(), export);
+    while let Some(chunk) = chunks.next().await {
+        writer.write_all(&chunk?).await?;
+    }
+    writer.flush().await?;
+    Ok(())
+}
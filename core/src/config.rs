@@ -0,0 +1,859 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use alloy::primitives::Address;
+use alloy::providers::Provider;
+use eyre::{Result, WrapErr, bail, eyre};
+use serde::{Deserialize, Serialize};
+
+use crate::builder::{ApiSettings, ChunkSettings};
+use crate::declarative::check_ident;
+use crate::query::QueryTables;
+use crate::resilience::RetryConfig;
+use crate::strategies::{ChunkProcessor, StrategyConfig};
+
+// ============================================================================
+// Schema (deployment profiles provided by the binary)
+// ============================================================================
+
+/// A contract address the binary needs, with its legacy env var and default.
+#[derive(Debug, Clone, Copy)]
+pub struct ContractDef {
+    pub key: &'static str,
+    pub env: &'static str,
+    pub default: Address,
+}
+
+/// A list of addresses of the same kind (e.g. lending markets), set as a TOML
+/// array or a comma-separated env var. A single address is accepted too.
+#[derive(Debug, Clone, Copy)]
+pub struct ContractListDef {
+    pub key: &'static str,
+    pub env: &'static str,
+    pub default: &'static [Address],
+}
+
+/// A built-in strategy and its default start block.
+#[derive(Debug, Clone, Copy)]
+pub struct StrategyDef {
+    pub name: &'static str,
+    pub from_block: u64,
+}
+
+/// A table a strategy writes to, overridable under `[tables]`.
+#[derive(Debug, Clone, Copy)]
+pub struct TableDef {
+    pub key: &'static str,
+    pub default: &'static str,
+}
+
+/// A deployment profile: the chain, and what it accepts under `[contracts]`,
+/// `[tables]` and `[strategies]`.
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigSchema {
+    /// Selected with `profile = "..."`, `PROFILE` or `--profile`.
+    pub name: &'static str,
+    /// Expected chain id, 0 to skip the check.
+    pub chain_id: u64,
+    pub contracts: &'static [ContractDef],
+    pub contract_lists: &'static [ContractListDef],
+    pub tables: &'static [TableDef],
+    pub strategies: &'static [StrategyDef],
+}
+
+// ============================================================================
+// Effective configuration
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub profile: String,
+    pub chain: ChainConfig,
+    pub database: DatabaseConfig,
+    pub indexer: IndexerSettings,
+    pub api: ApiConfig,
+    pub retry: RetrySettings,
+    pub contracts: BTreeMap<String, Address>,
+    pub contract_lists: BTreeMap<String, Vec<Address>>,
+    pub tables: BTreeMap<String, String>,
+    pub strategies: BTreeMap<String, StrategySettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strategies_file: Option<PathBuf>,
+    pub levels: LevelsConfig,
+    pub archive: ArchiveConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChainConfig {
+    pub rpc_url: String,
+    /// Checked against the RPC by `verify`; defaults to the profile's chain.
+    pub chain_id: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    /// Apply pending migrations on startup; otherwise refuse to start until `migrate` runs.
+    pub migrate: bool,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self { url: String::new(), max_connections: 5, migrate: true }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IndexerSettings {
+    pub chunk_size: u64,
+    pub chunk_min: u64,
+    /// Defaults to twice `chunk_size`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_max: Option<u64>,
+    pub confirmations: u64,
+    pub poll_secs: u64,
+    /// Addresses per `eth_getLogs` filter when watching discovered addresses.
+    pub address_batch: usize,
+}
+
+impl Default for IndexerSettings {
+    fn default() -> Self {
+        Self {
+            chunk_size: 100_000,
+            chunk_min: 100,
+            chunk_max: None,
+            confirmations: 32,
+            poll_secs: 5,
+            address_batch: 500,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    pub bind: SocketAddr,
+    pub api_key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dashboard_path: Option<PathBuf>,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        let api = ApiSettings::default();
+        Self {
+            enabled: true,
+            bind: api.bind,
+            api_key: api.api_key,
+            dashboard_path: api.dashboard,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RetrySettings {
+    pub max_retries: u32,
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub backoff_multiplier: f64,
+}
+
+impl Default for RetrySettings {
+    fn default() -> Self {
+        let r = RetryConfig::default();
+        Self {
+            max_retries: r.max_retries,
+            initial_delay_ms: r.initial_delay_ms,
+            max_delay_ms: r.max_delay_ms,
+            backoff_multiplier: r.backoff_multiplier,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StrategySettings {
+    #[serde(default = "enabled")]
+    pub enabled: bool,
+    pub from_block: u64,
+    #[serde(default)]
+    pub reindex: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LevelsConfig {
+    /// Points needed for level 1, 2, ...; empty keeps the `level_thresholds` table as is.
+    #[serde(default)]
+    pub thresholds: Vec<i32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArchiveConfig {
+    /// Store every fetched log in `raw_logs`.
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+// ============================================================================
+// File layer: every field optional so partial files merge over defaults
+// ============================================================================
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    profile: Option<String>,
+    chain: Option<PartialChain>,
+    database: Option<PartialDatabase>,
+    indexer: Option<PartialIndexer>,
+    api: Option<PartialApi>,
+    retry: Option<PartialRetry>,
+    #[serde(default)]
+    contracts: BTreeMap<String, Address>,
+    #[serde(default)]
+    contract_lists: BTreeMap<String, Vec<Address>>,
+    #[serde(default)]
+    tables: BTreeMap<String, String>,
+    #[serde(default)]
+    strategies: BTreeMap<String, PartialStrategy>,
+    strategies_file: Option<PathBuf>,
+    levels: Option<LevelsConfig>,
+    archive: Option<ArchiveConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PartialChain {
+    rpc_url: Option<String>,
+    chain_id: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PartialDatabase {
+    url: Option<String>,
+    max_connections: Option<u32>,
+    migrate: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PartialIndexer {
+    chunk_size: Option<u64>,
+    chunk_min: Option<u64>,
+    chunk_max: Option<u64>,
+    confirmations: Option<u64>,
+    poll_secs: Option<u64>,
+    address_batch: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PartialApi {
+    enabled: Option<bool>,
+    bind: Option<SocketAddr>,
+    api_key: Option<String>,
+    dashboard_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PartialRetry {
+    max_retries: Option<u32>,
+    initial_delay_ms: Option<u64>,
+    max_delay_ms: Option<u64>,
+    backoff_multiplier: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PartialStrategy {
+    enabled: Option<bool>,
+    from_block: Option<u64>,
+    reindex: Option<bool>,
+}
+
+macro_rules! merge {
+    ($dst:expr, $src:expr, $($field:ident),+) => {
+        {$( if let Some(v) = $src.$field { $dst.$field = v; } )+}
+    };
+}
+
+// ============================================================================
+// Loading
+// ============================================================================
+
+/// Default config file, used when it exists and no path is given.
+pub const DEFAULT_CONFIG_FILE: &str = "indexer.toml";
+
+impl Config {
+    /// Defaults from `schema`, then the TOML file, then env overrides; the
+    /// result is validated and every problem is reported at once.
+    pub fn load(schema: &ConfigSchema, path: Option<&Path>) -> Result<Self> {
+        Self::load_profile(std::slice::from_ref(schema), None, path)
+    }
+
+    /// Like [`Config::load`], picking the schema among `profiles` by name:
+    /// `profile` (e.g. `--profile`), then `PROFILE`, then the file's
+    /// `profile` key. A single profile is selected implicitly.
+    pub fn load_profile(profiles: &[ConfigSchema], profile: Option<&str>, path: Option<&Path>) -> Result<Self> {
+        let env = Env::from_process();
+
+        let path = path
+            .map(Path::to_path_buf)
+            .or_else(|| env.get("CONFIG_FILE").map(PathBuf::from))
+            .or_else(|| Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|p| p.exists()));
+        let file = match path {
+            Some(path) => {
+                let raw = std::fs::read_to_string(&path)
+                    .wrap_err_with(|| format!("reading config file {}", path.display()))?;
+                let file: ConfigFile = toml::from_str(&raw)
+                    .wrap_err_with(|| format!("invalid config file {}", path.display()))?;
+                Some(file)
+            }
+            None => None,
+        };
+
+        let names = || profiles.iter().map(|p| p.name).collect::<Vec<_>>().join(", ");
+        let wanted = profile
+            .or_else(|| env.get("PROFILE"))
+            .or_else(|| file.as_ref().and_then(|f| f.profile.as_deref()));
+        let schema = match (wanted, profiles) {
+            (Some(name), _) => profiles
+                .iter()
+                .find(|p| p.name == name)
+                .ok_or_else(|| eyre!("unknown profile '{name}' (expected one of: {})", names()))?,
+            (None, [only]) => only,
+            (None, _) => bail!("no profile selected, set `profile`, PROFILE or --profile to one of: {}", names()),
+        };
+
+        let mut cfg = Self::defaults(schema);
+        let mut errors = Vec::new();
+        if let Some(file) = file {
+            errors.extend(cfg.apply_file(schema, file));
+        }
+        errors.extend(cfg.apply_env(schema, &env));
+        errors.extend(cfg.validate());
+        report(errors)?;
+        Ok(cfg)
+    }
+
+    fn defaults(schema: &ConfigSchema) -> Self {
+        Self {
+            profile: schema.name.to_string(),
+            chain: ChainConfig { rpc_url: String::new(), chain_id: schema.chain_id },
+            database: DatabaseConfig::default(),
+            indexer: IndexerSettings::default(),
+            api: ApiConfig::default(),
+            retry: RetrySettings::default(),
+            contracts: schema.contracts.iter().map(|c| (c.key.to_string(), c.default)).collect(),
+            contract_lists: schema.contract_lists.iter().map(|c| (c.key.to_string(), c.default.to_vec())).collect(),
+            tables: schema.tables.iter().map(|t| (t.key.to_string(), t.default.to_string())).collect(),
+            strategies: schema
+                .strategies
+                .iter()
+                .map(|s| {
+                    let settings = StrategySettings { enabled: true, from_block: s.from_block, reindex: false };
+                    (s.name.to_string(), settings)
+                })
+                .collect(),
+            strategies_file: None,
+            levels: LevelsConfig::default(),
+            archive: ArchiveConfig::default(),
+        }
+    }
+
+    fn apply_file(&mut self, schema: &ConfigSchema, file: ConfigFile) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if let Some(c) = file.chain {
+            merge!(self.chain, c, rpc_url, chain_id);
+        }
+        if let Some(d) = file.database {
+            merge!(self.database, d, url, max_connections, migrate);
+        }
+        if let Some(i) = file.indexer {
+            merge!(self.indexer, i, chunk_size, chunk_min, confirmations, poll_secs, address_batch);
+            if i.chunk_max.is_some() {
+                self.indexer.chunk_max = i.chunk_max;
+            }
+        }
+        if let Some(a) = file.api {
+            merge!(self.api, a, enabled, bind, api_key);
+            if a.dashboard_path.is_some() {
+                self.api.dashboard_path = a.dashboard_path;
+            }
+        }
+        if let Some(r) = file.retry {
+            merge!(self.retry, r, max_retries, initial_delay_ms, max_delay_ms, backoff_multiplier);
+        }
+        for (key, addr) in file.contracts {
+            // Lists that used to be a single contract keep accepting `contracts.<key> = "0x..."`
+            match (self.contracts.get_mut(&key), self.contract_lists.get_mut(&key)) {
+                (Some(slot), _) => *slot = addr,
+                (None, Some(list)) => *list = vec![addr],
+                (None, None) => errors.push(format!("unknown key contracts.{key} (expected one of: {})", schema.contract_keys())),
+            }
+        }
+        for (key, addrs) in file.contract_lists {
+            match self.contract_lists.get_mut(&key) {
+                Some(slot) => *slot = addrs,
+                None => errors.push(format!(
+                    "unknown key contract_lists.{key} (expected one of: {})",
+                    schema.contract_list_keys()
+                )),
+            }
+        }
+        for (key, table) in file.tables {
+            match self.tables.get_mut(&key) {
+                Some(slot) => *slot = table,
+                None => errors.push(format!("unknown key tables.{key} (expected one of: {})", schema.table_keys())),
+            }
+        }
+        for (name, s) in file.strategies {
+            match self.strategies.get_mut(&name) {
+                Some(slot) => merge!(slot, s, enabled, from_block, reindex),
+                None => errors.push(format!("unknown key strategies.{name} (expected one of: {})", schema.strategy_names())),
+            }
+        }
+        if file.strategies_file.is_some() {
+            self.strategies_file = file.strategies_file;
+        }
+        if let Some(levels) = file.levels {
+            self.levels = levels;
+        }
+        if let Some(archive) = file.archive {
+            self.archive = archive;
+        }
+
+        errors
+    }
+
+    fn apply_env(&mut self, schema: &ConfigSchema, env: &Env) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        env.set(&mut self.chain.rpc_url, "RPC_URL", &mut errors);
+        env.set(&mut self.chain.chain_id, "CHAIN_ID", &mut errors);
+        env.set(&mut self.database.url, "DATABASE_URL", &mut errors);
+        env.set(&mut self.database.max_connections, "DATABASE_MAX_CONNECTIONS", &mut errors);
+        env.set(&mut self.database.migrate, "DATABASE_MIGRATE", &mut errors);
+        env.set(&mut self.indexer.chunk_size, "CHUNK_SIZE", &mut errors);
+        env.set(&mut self.indexer.confirmations, "CONFIRMATIONS", &mut errors);
+        env.set(&mut self.indexer.poll_secs, "POLL_SECS", &mut errors);
+        env.set(&mut self.indexer.address_batch, "ADDRESS_BATCH", &mut errors);
+        env.set(&mut self.archive.enabled, "ARCHIVE_LOGS", &mut errors);
+        env.set(&mut self.api.api_key, "API_KEY", &mut errors);
+        let mut port = self.api.bind.port();
+        env.set(&mut port, "API_PORT", &mut errors);
+        self.api.bind.set_port(port);
+        if let Some(path) = env.get("DASHBOARD_PATH") {
+            self.api.dashboard_path = Some(PathBuf::from(path));
+        }
+        if let Some(path) = env.get("STRATEGIES_FILE") {
+            self.strategies_file = Some(PathBuf::from(path));
+        }
+
+        let mut known = BTreeSet::new();
+        for c in schema.contracts {
+            known.insert(c.env.to_string());
+            if let Some(slot) = self.contracts.get_mut(c.key) {
+                env.set(slot, c.env, &mut errors);
+            }
+        }
+        for c in schema.contract_lists {
+            known.insert(c.env.to_string());
+            if let Some(raw) = env.get(c.env) {
+                match raw.split(',').map(|a| a.trim().parse::<Address>()).collect::<std::result::Result<Vec<_>, _>>() {
+                    Ok(addrs) => {
+                        self.contract_lists.insert(c.key.to_string(), addrs);
+                    }
+                    Err(e) => errors.push(format!("{}={raw:?} is invalid: {e}", c.env)),
+                }
+            }
+        }
+        for s in schema.strategies {
+            let prefix = format!("STRAT_{}", s.name.to_ascii_uppercase());
+            let slot = self.strategies.get_mut(s.name).expect("schema strategies have defaults");
+            for (suffix, target) in [("_FROM", &mut slot.from_block as &mut dyn SetFromStr), ("_REINDEX", &mut slot.reindex), ("_ENABLED", &mut slot.enabled)] {
+                let key = format!("{prefix}{suffix}");
+                env.set_dyn(target, &key, &mut errors);
+                known.insert(key);
+            }
+        }
+        // A misspelt STRAT_* variable would otherwise be silently ignored
+        for key in env.keys().filter(|k| k.starts_with("STRAT_")) {
+            if !known.contains(key) {
+                errors.push(format!("unknown environment variable {key}"));
+            }
+        }
+
+        errors
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.chain.rpc_url.is_empty() {
+            errors.push("chain.rpc_url is required (or RPC_URL)".to_string());
+        } else if !["http://", "https://", "ws://", "wss://"].iter().any(|p| self.chain.rpc_url.starts_with(p)) {
+            errors.push(format!("chain.rpc_url must be an http(s) or ws(s) URL, got '{}'", mask_url(&self.chain.rpc_url)));
+        }
+        if self.database.url.is_empty() {
+            errors.push("database.url is required (or DATABASE_URL)".to_string());
+        } else if !self.database.url.starts_with("postgres://") && !self.database.url.starts_with("postgresql://") {
+            errors.push("database.url must be a postgres:// URL".to_string());
+        }
+        if self.database.max_connections == 0 {
+            errors.push("database.max_connections must be > 0".to_string());
+        }
+
+        let i = &self.indexer;
+        if i.chunk_min == 0 {
+            errors.push("indexer.chunk_min must be > 0".to_string());
+        }
+        let chunks = self.chunk_settings();
+        if !(chunks.min <= chunks.initial && chunks.initial <= chunks.max) {
+            errors.push(format!(
+                "indexer chunk sizes must satisfy chunk_min <= chunk_size <= chunk_max, got {} <= {} <= {}",
+                chunks.min, chunks.initial, chunks.max
+            ));
+        }
+        if i.poll_secs == 0 {
+            errors.push("indexer.poll_secs must be > 0".to_string());
+        }
+        if i.address_batch == 0 {
+            errors.push("indexer.address_batch must be > 0".to_string());
+        }
+
+        if self.api.enabled && self.api.api_key.is_empty() {
+            errors.push("api.api_key must not be empty when the API is enabled".to_string());
+        }
+
+        let r = &self.retry;
+        if r.max_retries == 0 {
+            errors.push("retry.max_retries must be > 0".to_string());
+        }
+        if r.initial_delay_ms > r.max_delay_ms {
+            errors.push(format!("retry.initial_delay_ms ({}) exceeds retry.max_delay_ms ({})", r.initial_delay_ms, r.max_delay_ms));
+        }
+        if !(r.backoff_multiplier >= 1.0 && r.backoff_multiplier.is_finite()) {
+            errors.push(format!("retry.backoff_multiplier must be >= 1, got {}", r.backoff_multiplier));
+        }
+
+        for (key, addr) in &self.contracts {
+            if addr.is_zero() {
+                errors.push(format!("contracts.{key} must not be the zero address"));
+            }
+        }
+        let t = &self.levels.thresholds;
+        if t.first().is_some_and(|&p| p <= 0) || t.windows(2).any(|w| w[0] >= w[1]) {
+            errors.push(format!("levels.thresholds must be positive and strictly increasing, got {t:?}"));
+        }
+        for (key, addrs) in &self.contract_lists {
+            if addrs.is_empty() {
+                errors.push(format!("contract_lists.{key} must not be empty"));
+            }
+            if addrs.iter().any(|a| a.is_zero()) {
+                errors.push(format!("contract_lists.{key} must not contain the zero address"));
+            }
+        }
+        // Table names are spliced into SQL
+        for (key, table) in &self.tables {
+            if let Err(e) = check_ident(table) {
+                errors.push(format!("tables.{key}: {e}"));
+            }
+        }
+
+        errors
+    }
+
+    // ------------------------------------------------------------------------
+    // Accessors
+    // ------------------------------------------------------------------------
+
+    /// Address of a contract declared in the binary's schema.
+    pub fn contract(&self, key: &str) -> Address {
+        *self.contracts.get(key).unwrap_or_else(|| panic!("contract '{key}' is not in the config schema"))
+    }
+
+    /// Addresses of a contract list declared in the binary's schema.
+    pub fn contract_list(&self, key: &str) -> &[Address] {
+        self.contract_lists.get(key).unwrap_or_else(|| panic!("contract list '{key}' is not in the config schema"))
+    }
+
+    /// Table name for a key declared in the profile's schema.
+    pub fn table(&self, key: &str) -> &str {
+        self.tables.get(key).unwrap_or_else(|| panic!("table '{key}' is not in the config schema"))
+    }
+
+    pub fn strategy(&self, name: &str) -> &StrategySettings {
+        self.strategies.get(name).unwrap_or_else(|| panic!("strategy '{name}' is not in the config schema"))
+    }
+
+    /// `processor` wired with its `[strategies.<name>]` settings, or `None` when disabled.
+    pub fn strategy_config<P, T>(&self, name: &'static str, processor: T) -> Option<StrategyConfig<P>>
+    where
+        P: Provider + Clone + Send + Sync + 'static,
+        T: ChunkProcessor<P> + Send + Sync + 'static,
+    {
+        let s = self.strategy(name);
+        s.enabled.then(|| StrategyConfig::new(processor, name, s.from_block, s.reindex))
+    }
+
+    pub fn chunk_settings(&self) -> ChunkSettings {
+        let i = &self.indexer;
+        ChunkSettings {
+            initial: i.chunk_size,
+            min: i.chunk_min,
+            max: i.chunk_max.unwrap_or(i.chunk_size.saturating_mul(2)),
+        }
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.indexer.poll_secs)
+    }
+
+    pub fn api_settings(&self) -> Option<ApiSettings> {
+        self.api.enabled.then(|| ApiSettings {
+            bind: self.api.bind,
+            api_key: self.api.api_key.clone(),
+            dashboard: self.api.dashboard_path.clone(),
+            tables: QueryTables::from_tables(&self.tables),
+        })
+    }
+
+    pub fn retry_config(&self) -> RetryConfig {
+        RetryConfig {
+            max_retries: self.retry.max_retries,
+            initial_delay_ms: self.retry.initial_delay_ms,
+            max_delay_ms: self.retry.max_delay_ms,
+            backoff_multiplier: self.retry.backoff_multiplier,
+        }
+    }
+
+    /// Effective config as TOML, with secrets masked.
+    pub fn to_redacted_toml(&self) -> Result<String> {
+        let mut shown = self.clone();
+        shown.chain.rpc_url = mask_url(&shown.chain.rpc_url);
+        shown.database.url = mask_url(&shown.database.url);
+        if !shown.api.api_key.is_empty() {
+            shown.api.api_key = "********".into();
+        }
+        Ok(toml::to_string_pretty(&shown)?)
+    }
+}
+
+impl ConfigSchema {
+    fn contract_keys(&self) -> String {
+        self.contracts.iter().map(|c| c.key).collect::<Vec<_>>().join(", ")
+    }
+
+    fn contract_list_keys(&self) -> String {
+        self.contract_lists.iter().map(|c| c.key).collect::<Vec<_>>().join(", ")
+    }
+
+    fn table_keys(&self) -> String {
+        self.tables.iter().map(|t| t.key).collect::<Vec<_>>().join(", ")
+    }
+
+    fn strategy_names(&self) -> String {
+        self.strategies.iter().map(|s| s.name).collect::<Vec<_>>().join(", ")
+    }
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_redacted_toml() {
+            Ok(s) => f.write_str(&s),
+            Err(_) => Err(fmt::Error),
+        }
+    }
+}
+
+fn report(errors: Vec<String>) -> Result<()> {
+    if errors.is_empty() {
+        return Ok(());
+    }
+    bail!("invalid configuration:\n  - {}", errors.join("\n  - "))
+}
+
+/// Hides credentials (`user:pass@`) and everything after the host (path,
+/// query), where API keys travel.
+fn mask_url(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let (authority, tail) = rest.split_at(rest.find(['/', '?', '#']).unwrap_or(rest.len()));
+    let host = match authority.rsplit_once('@') {
+        Some((_, host)) => format!("****@{host}"),
+        None => authority.to_string(),
+    };
+    match tail {
+        "" | "/" => format!("{scheme}://{host}{tail}"),
+        _ => format!("{scheme}://{host}{}****", &tail[..1]),
+    }
+}
+
+// ============================================================================
+// Env layer
+// ============================================================================
+
+/// Snapshot of the process env, so parsing is strict and testable.
+struct Env(BTreeMap<String, String>);
+
+trait SetFromStr {
+    fn set_from_str(&mut self, raw: &str) -> std::result::Result<(), String>;
+}
+
+impl<T: FromStr> SetFromStr for T
+where
+    T::Err: fmt::Display,
+{
+    fn set_from_str(&mut self, raw: &str) -> std::result::Result<(), String> {
+        *self = raw.trim().parse().map_err(|e: T::Err| e.to_string())?;
+        Ok(())
+    }
+}
+
+impl Env {
+    fn from_process() -> Self {
+        Self(std::env::vars().collect())
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    fn keys(&self) -> impl Iterator<Item = &String> {
+        self.0.keys()
+    }
+
+    fn set<T: SetFromStr>(&self, target: &mut T, key: &str, errors: &mut Vec<String>) {
+        self.set_dyn(target, key, errors);
+    }
+
+    fn set_dyn(&self, target: &mut dyn SetFromStr, key: &str, errors: &mut Vec<String>) {
+        if let Some(raw) = self.get(key)
+            && let Err(e) = target.set_from_str(raw)
+        {
+            errors.push(format!("{key}={raw:?} is invalid: {e}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::address;
+
+    use super::*;
+
+    const MARKET: Address = address!("0x00000000000000000000000000000000000000cc");
+    const SCHEMA: ConfigSchema = ConfigSchema {
+        name: "test",
+        chain_id: 10,
+        contracts: &[ContractDef {
+            key: "badges",
+            env: "STRAT_BADGES_MINTED_ADDR",
+            default: address!("0x00000000000000000000000000000000000000bb"),
+        }],
+        contract_lists: &[ContractListDef { key: "comet", env: "STRAT_VAULTS_ADDR", default: &[MARKET] }],
+        tables: &[TableDef { key: "accounts", default: "super_accounts" }],
+        strategies: &[StrategyDef { name: "badges_minted", from_block: 100 }],
+    };
+
+    /// Defaults, then `file`, then `env`, then validation, as
+    /// [`Config::load_profile`] does; `Err` holds every problem found.
+    fn load(file: &str, env: &[(&str, &str)]) -> std::result::Result<Config, Vec<String>> {
+        let env = Env(env.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect());
+        let mut cfg = Config::defaults(&SCHEMA);
+        let mut errors = cfg.apply_file(&SCHEMA, toml::from_str(file).unwrap());
+        errors.extend(cfg.apply_env(&SCHEMA, &env));
+        errors.extend(cfg.validate());
+        if errors.is_empty() { Ok(cfg) } else { Err(errors) }
+    }
+
+    const MINIMAL: &str = "[chain]\nrpc_url = \"https://rpc.example\"\n[database]\nurl = \"postgres://db/indexer\"\n[api]\napi_key = \"secret\"\n";
+
+    #[test]
+    fn env_overrides_file() {
+        let file = format!("{MINIMAL}[indexer]\nchunk_size = 500\n[strategies.badges_minted]\nfrom_block = 200\n");
+        let cfg = load(
+            &file,
+            &[
+                ("CHUNK_SIZE", "2000"),
+                ("API_PORT", "9000"),
+                ("STRAT_BADGES_MINTED_FROM", "300"),
+                ("STRAT_BADGES_MINTED_ENABLED", "false"),
+                ("STRAT_VAULTS_ADDR", "0x00000000000000000000000000000000000000c1, 0x00000000000000000000000000000000000000c2"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(cfg.indexer.chunk_size, 2000);
+        // chunk_max follows chunk_size unless set
+        assert_eq!(cfg.chunk_settings().max, 4000);
+        assert_eq!(cfg.api.bind.port(), 9000);
+        let s = cfg.strategy("badges_minted");
+        assert_eq!((s.from_block, s.enabled), (300, false));
+        assert_eq!(cfg.contract_list("comet").len(), 2);
+        assert_eq!(cfg.table("accounts"), "super_accounts");
+    }
+
+    #[test]
+    fn every_problem_is_reported() {
+        let file = format!(
+            "{MINIMAL}[indexer]\nchunk_size = 10\nchunk_min = 50\n[contracts]\nbadgez = \"{MARKET}\"\n[tables]\naccounts = \"users; DROP TABLE x\"\n"
+        );
+        let errors = load(&file, &[("API_PORT", "http"), ("POLL_SECS", "-1"), ("STRAT_BADGES_MINTED_FORM", "1")]).unwrap_err();
+        let expected = [
+            "unknown key contracts.badgez",
+            "POLL_SECS=\"-1\" is invalid",
+            "API_PORT=\"http\" is invalid",
+            "unknown environment variable STRAT_BADGES_MINTED_FORM",
+            "chunk_min <= chunk_size <= chunk_max, got 50 <= 10 <= 20",
+            "tables.accounts",
+        ];
+        assert_eq!(errors.len(), expected.len(), "{errors:#?}");
+        for (error, expected) in errors.iter().zip(expected) {
+            assert!(error.contains(expected), "{error:?} does not mention {expected:?}");
+        }
+    }
+
+    #[test]
+    fn required_and_ranged_values() {
+        let errors = load("[api]\napi_key = \"\"\n[retry]\nbackoff_multiplier = 0.5\n[levels]\nthresholds = [10, 10]\n", &[]).unwrap_err();
+        assert!(errors.iter().any(|e| e.starts_with("chain.rpc_url is required")));
+        assert!(errors.iter().any(|e| e.starts_with("database.url is required")));
+        assert!(errors.iter().any(|e| e.starts_with("api.api_key must not be empty")));
+        assert!(errors.iter().any(|e| e.starts_with("retry.backoff_multiplier")));
+        assert!(errors.iter().any(|e| e.starts_with("levels.thresholds")));
+
+        let errors = load(MINIMAL, &[("RPC_URL", "ftp://rpc"), ("STRAT_VAULTS_ADDR", "0xnope")]).unwrap_err();
+        assert_eq!(errors.len(), 2, "{errors:#?}");
+    }
+
+    #[test]
+    fn urls_are_masked() {
+        assert_eq!(mask_url("postgres://user:pass@db:5432/indexer"), "postgres://****@db:5432/****");
+        assert_eq!(mask_url("https://rpc.example/v2/KEY"), "https://rpc.example/****");
+        assert_eq!(mask_url("http://localhost:8545"), "http://localhost:8545");
+        assert_eq!(mask_url("https://rpc.example?apikey=SECRET"), "https://rpc.example?****");
+        assert_eq!(mask_url("https://rpc.example/?apikey=SECRET"), "https://rpc.example/****");
+        assert_eq!(mask_url("https://rpc.example/v2?key=a@b"), "https://rpc.example/****");
+    }
+}
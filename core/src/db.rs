@@ -1,7 +1,10 @@
-use eyre::Result;
+use eyre::{Result, bail, ensure};
+use sqlx::migrate::Migrator;
 use sqlx::{PgPool, postgres::PgPoolOptions};
 use std::env;
 
+use crate::config::DatabaseConfig;
+
 pub async fn connect_db() -> Result<PgPool> {
     let url = env::var("DATABASE_URL")?;
     let pool = PgPoolOptions::new()
@@ -10,3 +13,66 @@ pub async fn connect_db() -> Result<PgPool> {
         .await?;
     Ok(pool)
 }
+
+pub async fn connect(config: &DatabaseConfig) -> Result<PgPool> {
+    let pool = PgPoolOptions::new()
+        .max_connections(config.max_connections)
+        .connect(&config.url)
+        .await?;
+    Ok(pool)
+}
+
+/// Versioned schema from `core/migrations`, embedded at compile time.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Where the database schema stands relative to [`MIGRATOR`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaVersion {
+    /// Highest applied migration, 0 on an empty database.
+    pub current: i64,
+    /// Highest migration embedded in this binary.
+    pub latest: i64,
+    /// Embedded migrations not applied yet.
+    pub pending: usize,
+}
+
+/// Refuses databases this binary cannot work with: migrations it does not
+/// know about (a newer binary ran), edited migrations, or a failed one.
+pub async fn check_schema(db: &PgPool) -> Result<SchemaVersion> {
+    let latest = MIGRATOR.iter().map(|m| m.version).max().unwrap_or(0);
+
+    let exists: Option<String> = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations')::text")
+        .fetch_one(db)
+        .await?;
+    let applied: Vec<(i64, bool, Vec<u8>)> = if exists.is_some() {
+        sqlx::query_as("SELECT version, success, checksum FROM _sqlx_migrations ORDER BY version")
+            .fetch_all(db)
+            .await?
+    } else {
+        Vec::new()
+    };
+
+    for (version, success, checksum) in &applied {
+        ensure!(*success, "migration {version} failed half-way, fix the database by hand");
+        let Some(m) = MIGRATOR.iter().find(|m| m.version == *version) else {
+            bail!("database schema is at version {version}, newer than this binary supports ({latest})");
+        };
+        ensure!(*m.checksum == **checksum, "migration {version} ({}) was modified after being applied", m.description);
+    }
+
+    Ok(SchemaVersion {
+        current: applied.last().map(|a| a.0).unwrap_or(0),
+        latest,
+        pending: MIGRATOR.iter().filter(|m| !applied.iter().any(|a| a.0 == m.version)).count(),
+    })
+}
+
+/// Checks compatibility, then applies pending migrations.
+pub async fn migrate(db: &PgPool) -> Result<SchemaVersion> {
+    let before = check_schema(db).await?;
+    if before.pending > 0 {
+        MIGRATOR.run(db).await?;
+        tracing::info!(from = before.current, to = before.latest, "schema migrated");
+    }
+    Ok(SchemaVersion { current: before.latest, pending: 0, ..before })
+}
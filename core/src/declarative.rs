@@ -0,0 +1,728 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use alloy::dyn_abi::{DynSolValue, EventExt};
+use alloy::eips::BlockNumberOrTag;
+use alloy::json_abi::{Event, JsonAbi, Param};
+use alloy::primitives::Address;
+use alloy::providers::Provider;
+use alloy::rpc::types::{Filter, Log};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use eyre::{Result, WrapErr, bail, eyre};
+use serde::Deserialize;
+use sqlx::types::BigDecimal;
+use sqlx::{PgPool, QueryBuilder};
+
+use crate::blocks::{BlockTimestamps, sanitize_text};
+use crate::strategies::{ChunkProcessor, Stats, StrategyConfig};
+
+// ============================================================================
+// Spec (TOML / YAML)
+// ============================================================================
+
+/// Root of a declarative strategies file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeclarativeFile {
+    #[serde(default)]
+    pub strategies: Vec<DeclarativeSpec>,
+}
+
+/// One event → table mapping, e.g.
+///
+/// ```toml
+/// [[strategies]]
+/// name = "owner_added_log"
+/// address = "0x1Ee397850c3CA629d965453B3cF102E9A8806Ded"
+/// event = "event OwnerAdded(address indexed safe, address indexed newOwner, string superChainId)"
+/// from_block = 125901059
+/// table = "owner_added_log"
+/// conflict_key = ["account", "owner"]
+/// update_columns = ["block_number", "tx_hash"]
+///
+/// [strategies.columns]
+/// account = "event.safe"
+/// owner = "event.newOwner"
+/// block_number = "log.block_number"
+/// tx_hash = "log.tx_hash"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeclarativeSpec {
+    pub name: String,
+    pub address: Address,
+    /// Human-readable signature, or the event name (or full signature, for
+    /// overloads) when `abi` is set.
+    pub event: String,
+    /// JSON ABI file (plain ABI array or a compiler artifact with an `abi` key).
+    /// Relative paths are resolved against the strategies file.
+    #[serde(default)]
+    pub abi: Option<PathBuf>,
+    pub from_block: u64,
+    #[serde(default)]
+    pub reindex: bool,
+    pub table: String,
+    /// Column name → expression (see [`Expr`]).
+    pub columns: HashMap<String, String>,
+    #[serde(default)]
+    pub conflict_key: Vec<String>,
+    /// Columns overwritten on conflict. Empty means `DO NOTHING`.
+    #[serde(default)]
+    pub update_columns: Vec<String>,
+}
+
+/// Reads a `.toml`, `.yaml` or `.yml` strategies file.
+pub fn read_file(path: &Path) -> Result<DeclarativeFile> {
+    let raw = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("reading strategies file {}", path.display()))?;
+    let file = match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&raw)?,
+        Some("yaml" | "yml") => serde_yaml::from_str(&raw)?,
+        _ => bail!("unsupported strategies file extension: {}", path.display()),
+    };
+    Ok(file)
+}
+
+/// Loads and validates every strategy of the file into ready-to-run configs.
+pub fn load_strategies<P>(path: &Path) -> Result<Vec<StrategyConfig<P>>>
+where
+    P: Provider + Clone + Send + Sync + 'static,
+{
+    let file = read_file(path)?;
+    let base = path.parent().unwrap_or(Path::new("."));
+    let mut seen = HashSet::new();
+    let mut out = Vec::with_capacity(file.strategies.len());
+    for mut spec in file.strategies {
+        if let Some(abi) = spec.abi.as_mut().filter(|p| p.is_relative()) {
+            *abi = base.join(&*abi);
+        }
+        if !seen.insert(spec.name.clone()) {
+            bail!("duplicate declarative strategy name: {}", spec.name);
+        }
+        let processor = DeclarativeProcessor::new(spec.clone())
+            .wrap_err_with(|| format!("invalid declarative strategy '{}'", spec.name))?;
+        // Strategy names live for the whole process
+        let name: &'static str = Box::leak(spec.name.into_boxed_str());
+        out.push(StrategyConfig::new(processor, name, spec.from_block, spec.reindex));
+    }
+    Ok(out)
+}
+
+// ============================================================================
+// Expressions
+// ============================================================================
+
+/// Value source for a mapped column.
+///
+/// - `event`: every decoded parameter as a JSONB object (positional keys when
+///   unnamed)
+/// - `event.<param>[.<field>...]`: decoded parameter, by name or position,
+///   walking into tuple/struct fields by name (JSON ABI) or position
+/// - `log.address`, `log.block_number`, `log.block_hash`, `log.tx_hash`, `log.log_index`
+/// - `block.timestamp`
+/// - literals: `'text'`, integers, `true`, `false`, `null`
+///
+/// Any expression may end in `::<type>` to cast the bound value in SQL.
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Payload,
+    Param { index: usize, path: Vec<usize> },
+    LogAddress,
+    BlockNumber,
+    BlockHash,
+    TxHash,
+    LogIndex,
+    BlockTimestamp,
+    Literal(SqlValue),
+}
+
+impl Expr {
+    fn parse(raw: &str, event: &Event) -> Result<Self> {
+        let s = raw.trim();
+        if s == "event" {
+            return Ok(Self::Payload);
+        }
+        if let Some(path) = s.strip_prefix("event.") {
+            let mut parts = path.split('.');
+            let name = parts.next().unwrap_or_default();
+            // Unnamed parameters (`event Foo(address, uint256)`) by position
+            let index = event
+                .inputs
+                .iter()
+                .position(|p| p.name == name)
+                .or_else(|| name.parse().ok().filter(|&i: &usize| i < event.inputs.len()))
+                .ok_or_else(|| eyre!("event {} has no parameter '{name}'", event.name))?;
+            let param = &event.inputs[index];
+            if param.indexed && parts.clone().next().is_some() {
+                bail!("indexed parameter '{name}' is only available as its topic hash");
+            }
+            let (mut ty, mut components) = (&param.ty, &param.components);
+            let mut field_path = Vec::new();
+            for field in parts {
+                // Arrays of tuples decode as arrays: `components` describe
+                // their elements, not fields of the value itself
+                if !is_tuple(ty) {
+                    bail!("'{path}': {ty} is not a tuple, it has no field '{field}'");
+                }
+                // Human-readable signatures carry no component names, so
+                // positional access (`event.noun.0`) is accepted too
+                let pos = components
+                    .iter()
+                    .position(|c| c.name == field)
+                    .or_else(|| field.parse().ok().filter(|&i: &usize| i < components.len()))
+                    .ok_or_else(|| eyre!("'{path}': {ty} has no field '{field}'"))?;
+                field_path.push(pos);
+                (ty, components) = (&components[pos].ty, &components[pos].components);
+            }
+            return Ok(Self::Param { index, path: field_path });
+        }
+        Ok(match s {
+            "log.address" => Self::LogAddress,
+            "log.block_number" => Self::BlockNumber,
+            "log.block_hash" => Self::BlockHash,
+            "log.tx_hash" => Self::TxHash,
+            "log.log_index" => Self::LogIndex,
+            "block.timestamp" => Self::BlockTimestamp,
+            "null" => Self::Literal(SqlValue::Null),
+            "true" => Self::Literal(SqlValue::Bool(true)),
+            "false" => Self::Literal(SqlValue::Bool(false)),
+            _ if s.len() >= 2 && s.starts_with('\'') && s.ends_with('\'') => {
+                Self::Literal(SqlValue::Text(s[1..s.len() - 1].to_string()))
+            }
+            _ => match s.parse::<i64>() {
+                Ok(n) => Self::Literal(SqlValue::Int(n)),
+                Err(_) => bail!("invalid column expression '{raw}'"),
+            },
+        })
+    }
+}
+
+/// Whether a parameter of ABI type `ty` decodes to a tuple (and not, say, an
+/// array of them).
+fn is_tuple(ty: &str) -> bool {
+    (ty == "tuple" || ty.starts_with('(')) && !ty.ends_with(']')
+}
+
+/// SQL types accepted in `::<type>` casts.
+const CASTS: &[&str] = &[
+    "text", "numeric", "bigint", "integer", "int", "smallint", "boolean", "bytea",
+    "jsonb", "json", "timestamptz", "timestamp", "date", "text[]",
+];
+
+/// Splits a trailing `::<type>` cast off a column expression.
+fn split_cast(raw: &str) -> Result<(&str, Option<String>)> {
+    if raw.trim_end().ends_with('\'') {
+        return Ok((raw, None));
+    }
+    match raw.rsplit_once("::") {
+        Some((expr, ty)) => {
+            let ty = ty.trim().to_ascii_lowercase();
+            if !CASTS.contains(&ty.as_str()) {
+                bail!("unsupported cast '::{ty}' in '{raw}'");
+            }
+            Ok((expr, Some(ty)))
+        }
+        None => Ok((raw, None)),
+    }
+}
+
+// ============================================================================
+// Values
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum SqlValue {
+    Null,
+    Text(String),
+    Int(i64),
+    Numeric(BigDecimal),
+    Bool(bool),
+    Timestamp(DateTime<Utc>),
+    Json(serde_json::Value),
+}
+
+impl SqlValue {
+    /// Scalars map to typed binds; tuples, structs and arrays become JSONB.
+    fn from_dyn(v: &DynSolValue, components: &[Param]) -> Result<Self> {
+        Ok(match v {
+            DynSolValue::Address(a) => Self::Text(format!("{:#x}", a)),
+            DynSolValue::Bool(b) => Self::Bool(*b),
+            DynSolValue::Uint(u, _) => Self::Numeric(u.to_string().parse()?),
+            DynSolValue::Int(i, _) => Self::Numeric(i.to_string().parse()?),
+            DynSolValue::FixedBytes(w, size) => {
+                Self::Text(format!("0x{}", alloy::hex::encode(&w[..*size])))
+            }
+            DynSolValue::Bytes(b) => Self::Text(format!("0x{}", alloy::hex::encode(b))),
+            DynSolValue::String(s) => Self::Text(sanitize_text(s).0.into_owned()),
+            other => Self::Json(dyn_to_json(other, components)),
+        })
+    }
+}
+
+/// JSON rendering of a decoded value. Tuples become objects keyed by the ABI
+/// component names (positional keys when unnamed); integers that do not fit
+/// in 64 bits are rendered as decimal strings.
+fn dyn_to_json(v: &DynSolValue, components: &[Param]) -> serde_json::Value {
+    use serde_json::Value;
+    match v {
+        DynSolValue::Address(a) => Value::String(format!("{:#x}", a)),
+        DynSolValue::Function(f) => Value::String(format!("0x{}", alloy::hex::encode(f.as_slice()))),
+        DynSolValue::Bool(b) => Value::Bool(*b),
+        DynSolValue::Uint(u, _) => u64::try_from(*u)
+            .map(Value::from)
+            .unwrap_or_else(|_| Value::String(u.to_string())),
+        DynSolValue::Int(i, _) => i64::try_from(*i)
+            .map(Value::from)
+            .unwrap_or_else(|_| Value::String(i.to_string())),
+        DynSolValue::FixedBytes(w, size) => {
+            Value::String(format!("0x{}", alloy::hex::encode(&w[..*size])))
+        }
+        DynSolValue::Bytes(b) => Value::String(format!("0x{}", alloy::hex::encode(b))),
+        DynSolValue::String(s) => Value::String(sanitize_text(s).0.into_owned()),
+        DynSolValue::Array(items) | DynSolValue::FixedArray(items) => {
+            Value::Array(items.iter().map(|i| dyn_to_json(i, components)).collect())
+        }
+        DynSolValue::Tuple(items) => Value::Object(
+            items
+                .iter()
+                .enumerate()
+                .map(|(i, item)| match components.get(i) {
+                    Some(c) if !c.name.is_empty() => {
+                        (c.name.clone(), dyn_to_json(item, &c.components))
+                    }
+                    Some(c) => (i.to_string(), dyn_to_json(item, &c.components)),
+                    None => (i.to_string(), dyn_to_json(item, &[])),
+                })
+                .collect(),
+        ),
+        #[allow(unreachable_patterns)]
+        other => Value::String(format!("{other:?}")),
+    }
+}
+
+fn push_value(b: &mut sqlx::query_builder::Separated<'_, '_, sqlx::Postgres, &str>, v: &SqlValue) {
+    match v {
+        // A bound NULL would be typed text; a literal takes the column's type
+        SqlValue::Null => b.push("NULL"),
+        SqlValue::Text(s) => b.push_bind(s.clone()),
+        SqlValue::Int(n) => b.push_bind(*n),
+        SqlValue::Numeric(n) => b.push_bind(n.clone()),
+        SqlValue::Bool(x) => b.push_bind(*x),
+        SqlValue::Timestamp(t) => b.push_bind(*t),
+        SqlValue::Json(j) => b.push_bind(j.clone()),
+    };
+}
+
+// ============================================================================
+// Processor
+// ============================================================================
+
+/// Generic [`ChunkProcessor`]: filter → decode → map → upsert.
+#[derive(Clone)]
+pub struct DeclarativeProcessor {
+    spec: DeclarativeSpec,
+    event: Event,
+    /// (column, expression, cast), in a stable order.
+    columns: Vec<(String, Expr, Option<String>)>,
+}
+
+impl DeclarativeProcessor {
+    pub fn new(spec: DeclarativeSpec) -> Result<Self> {
+        let event = match &spec.abi {
+            Some(path) => event_from_abi(path, &spec.event)?,
+            None => Event::parse(&spec.event)
+                .map_err(|e| eyre!("invalid event signature '{}': {e}", spec.event))?,
+        };
+        if event.anonymous {
+            bail!("anonymous events are not supported");
+        }
+
+        check_ident(&spec.table)?;
+        if spec.columns.is_empty() {
+            bail!("at least one column mapping is required");
+        }
+        let mut columns = Vec::with_capacity(spec.columns.len());
+        for (col, raw) in &spec.columns {
+            check_ident(col)?;
+            let (expr, cast) = split_cast(raw)?;
+            columns.push((col.clone(), Expr::parse(expr, &event)?, cast));
+        }
+        columns.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for col in spec.conflict_key.iter().chain(&spec.update_columns) {
+            if !spec.columns.contains_key(col) {
+                bail!("column '{col}' is not mapped");
+            }
+        }
+        if !spec.update_columns.is_empty() && spec.conflict_key.is_empty() {
+            bail!("update_columns requires a conflict_key");
+        }
+        if let Some(col) = spec.update_columns.iter().find(|c| spec.conflict_key.contains(c)) {
+            bail!("column '{col}' is both a conflict key and an update column");
+        }
+        // Rollbacks delete the rows after a block, so they need it
+        if !columns.iter().any(|(_, e, _)| *e == Expr::BlockNumber) {
+            bail!("a column must map log.block_number");
+        }
+
+        Ok(Self { spec, event, columns })
+    }
+
+    fn needs_timestamp(&self) -> bool {
+        self.columns.iter().any(|(_, e, _)| *e == Expr::BlockTimestamp)
+    }
+
+    fn eval(&self, expr: &Expr, params: &[DynSolValue], log: &Log, ts: Option<DateTime<Utc>>) -> Result<SqlValue> {
+        Ok(match expr {
+            Expr::Payload => SqlValue::Json(serde_json::Value::Object(
+                self.event
+                    .inputs
+                    .iter()
+                    .zip(params)
+                    .enumerate()
+                    .map(|(i, (p, v))| {
+                        let key = if p.name.is_empty() { i.to_string() } else { p.name.clone() };
+                        (key, dyn_to_json(v, &p.components))
+                    })
+                    .collect(),
+            )),
+            Expr::Param { index, path } => {
+                let param = &self.event.inputs[*index];
+                let (mut value, mut components) = (&params[*index], &param.components);
+                for &i in path {
+                    value = match value {
+                        DynSolValue::Tuple(items) => items
+                            .get(i)
+                            .ok_or_else(|| eyre!("field {i} missing in {}", param.name))?,
+                        _ => bail!("{} is not a tuple", param.name),
+                    };
+                    components = &components[i].components;
+                }
+                SqlValue::from_dyn(value, components)?
+            }
+            Expr::LogAddress => SqlValue::Text(format!("{:#x}", log.address())),
+            Expr::BlockNumber => log
+                .block_number
+                .map(|n| SqlValue::Int(n as i64))
+                .unwrap_or(SqlValue::Null),
+            Expr::BlockHash => log
+                .block_hash
+                .map(|h| SqlValue::Text(format!("{:#x}", h)))
+                .unwrap_or(SqlValue::Null),
+            Expr::TxHash => log
+                .transaction_hash
+                .map(|h| SqlValue::Text(format!("{:#x}", h)))
+                .unwrap_or(SqlValue::Null),
+            Expr::LogIndex => log
+                .log_index
+                .map(|n| SqlValue::Int(n as i64))
+                .unwrap_or(SqlValue::Null),
+            Expr::BlockTimestamp => ts.map(SqlValue::Timestamp).unwrap_or(SqlValue::Null),
+            Expr::Literal(v) => v.clone(),
+        })
+    }
+
+    /// Decoded params in declaration order (indexed and body interleaved).
+    fn decode(&self, log: &Log) -> Result<Vec<DynSolValue>> {
+        let decoded = self.event.decode_log(log.data())?;
+        let (mut indexed, mut body) = (decoded.indexed.into_iter(), decoded.body.into_iter());
+        self.event
+            .inputs
+            .iter()
+            .map(|p| {
+                if p.indexed { indexed.next() } else { body.next() }
+                    .ok_or_else(|| eyre!("missing value for parameter '{}'", p.name))
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl<P> ChunkProcessor<P> for DeclarativeProcessor
+where
+    P: Provider + Clone + Send + Sync + 'static,
+{
+    async fn process(&self, provider: P, db: &PgPool, from: u64, to: u64) -> Result<Stats> {
+        let t0 = std::time::Instant::now();
+        tracing::info!(from, to, strategy = %self.spec.name, "processing event range");
+
+        let filter = Filter::new()
+            .address(self.spec.address)
+            .event_signature(self.event.selector())
+            .from_block(BlockNumberOrTag::Number(from))
+            .to_block(BlockNumberOrTag::Number(to));
+        let logs = provider.get_logs(&filter).await?;
+
+        if logs.is_empty() {
+            tracing::info!(from, to, "no logs found in range");
+            return Ok(Stats::default());
+        }
+
+        let mut timestamps = BlockTimestamps::new(&provider);
+        // Keyed by the conflict key so one statement never touches the same row twice
+        let mut rows: Vec<Vec<SqlValue>> = Vec::with_capacity(logs.len());
+        let mut by_key: HashMap<Vec<String>, usize> = HashMap::new();
+
+        for log in &logs {
+            let params = self.decode(log).wrap_err_with(|| {
+                format!("decoding {} at tx {:?}", self.event.name, log.transaction_hash)
+            })?;
+
+            let ts = if self.needs_timestamp() { Some(timestamps.for_log(log).await?) } else { None };
+
+            let row = self
+                .columns
+                .iter()
+                .map(|(_, e, _)| self.eval(e, &params, log, ts))
+                .collect::<Result<Vec<_>>>()?;
+
+            if self.spec.conflict_key.is_empty() {
+                rows.push(row);
+                continue;
+            }
+            let key: Vec<String> = self
+                .columns
+                .iter()
+                .zip(&row)
+                .filter(|((c, _, _), _)| self.spec.conflict_key.contains(c))
+                .map(|(_, v)| format!("{v:?}"))
+                .collect();
+            match by_key.get(&key) {
+                Some(&i) => rows[i] = row,
+                None => {
+                    by_key.insert(key, rows.len());
+                    rows.push(row);
+                }
+            }
+        }
+
+        let column_list = self.columns.iter().map(|(c, _, _)| c.as_str()).collect::<Vec<_>>().join(", ");
+        let max_rows = u16::MAX as usize / self.columns.len();
+        let mut rows_written = 0u64;
+
+        for chunk in rows.chunks(max_rows) {
+            let mut qb = QueryBuilder::new(format!("INSERT INTO {} ({column_list}) ", self.spec.table));
+            qb.push_values(chunk.iter(), |mut b, row| {
+                for ((_, _, cast), v) in self.columns.iter().zip(row) {
+                    push_value(&mut b, v);
+                    if let Some(ty) = cast {
+                        b.push_unseparated(format!("::{ty}"));
+                    }
+                }
+            });
+            if !self.spec.conflict_key.is_empty() {
+                qb.push(format!(" ON CONFLICT ({}) ", self.spec.conflict_key.join(", ")));
+                if self.spec.update_columns.is_empty() {
+                    qb.push("DO NOTHING");
+                } else {
+                    let sets: Vec<String> = self
+                        .spec
+                        .update_columns
+                        .iter()
+                        .map(|c| format!("{c} = EXCLUDED.{c}"))
+                        .collect();
+                    qb.push(format!("DO UPDATE SET {}", sets.join(", ")));
+                }
+            }
+            rows_written += qb.build().execute(db).await?.rows_affected();
+        }
+
+        let took_ms = t0.elapsed().as_millis();
+        tracing::info!(from, to, logs = logs.len(), rows_written, took_ms, "chunk processed");
+        Ok(Stats {
+            logs_found: logs.len(),
+            rows_written,
+            from_block: from,
+            to_block: to,
+            took_ms,
+        })
+    }
+
+    async fn rollback(&self, db: &PgPool, to_block: u64) -> Result<u64> {
+        let Some((col, _, _)) = self.columns.iter().find(|(_, e, _)| *e == Expr::BlockNumber) else {
+            bail!("{}: no column maps log.block_number", self.spec.name);
+        };
+        let res = sqlx::query(&format!("DELETE FROM {} WHERE {col} > $1", self.spec.table))
+            .bind(to_block as i64)
+            .execute(db)
+            .await?;
+        Ok(res.rows_affected())
+    }
+
+    fn box_clone(&self) -> Box<dyn ChunkProcessor<P> + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+/// Finds `event` in a JSON ABI file, by name or, for overloaded events, by
+/// full signature (`Transfer(address,address,uint256)`).
+fn event_from_abi(path: &Path, event: &str) -> Result<Event> {
+    let raw = std::fs::read_to_string(path)
+        .wrap_err_with(|| format!("reading ABI file {}", path.display()))?;
+    let value: serde_json::Value = serde_json::from_str(&raw)?;
+    // Compiler artifacts (Foundry, Hardhat) nest the ABI under "abi"
+    let abi: JsonAbi = match value.get("abi") {
+        Some(inner) => serde_json::from_value(inner.clone())?,
+        None => serde_json::from_value(value)?,
+    };
+
+    let name = event.split('(').next().unwrap_or(event).trim();
+    let candidates = abi
+        .event(name)
+        .ok_or_else(|| eyre!("event '{name}' not found in {}", path.display()))?;
+    match candidates.as_slice() {
+        [single] if !event.contains('(') => Ok(single.clone()),
+        _ if !event.contains('(') => {
+            bail!("event '{name}' is overloaded in {}, use its full signature", path.display())
+        }
+        many => many
+            .iter()
+            .find(|e| e.signature() == event.replace(' ', ""))
+            .cloned()
+            .ok_or_else(|| eyre!("no overload of '{name}' matches '{event}'")),
+    }
+}
+
+/// Table and column names are spliced into SQL, so only plain identifiers
+/// (optionally schema-qualified) are accepted.
+pub(crate) fn check_ident(s: &str) -> Result<()> {
+    let ok = !s.is_empty()
+        && s.split('.').count() <= 2
+        && s.split('.').all(|part| {
+            let mut chars = part.chars();
+            matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+    if !ok {
+        bail!("invalid SQL identifier '{s}'");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(sig: &str) -> Event {
+        Event::parse(sig).unwrap()
+    }
+
+    #[test]
+    fn log_fields_and_literals() {
+        let ev = event("event Transfer(address indexed from, address indexed to, uint256 value)");
+        let parse = |raw: &str| Expr::parse(raw, &ev).unwrap();
+        assert_eq!(parse("event"), Expr::Payload);
+        assert_eq!(parse(" event.value "), Expr::Param { index: 2, path: vec![] });
+        assert_eq!(parse("log.address"), Expr::LogAddress);
+        assert_eq!(parse("log.tx_hash"), Expr::TxHash);
+        assert_eq!(parse("block.timestamp"), Expr::BlockTimestamp);
+        assert_eq!(parse("'transfer'"), Expr::Literal(SqlValue::Text("transfer".into())));
+        assert_eq!(parse("-7"), Expr::Literal(SqlValue::Int(-7)));
+        assert_eq!(parse("false"), Expr::Literal(SqlValue::Bool(false)));
+        assert_eq!(parse("null"), Expr::Literal(SqlValue::Null));
+
+        assert!(Expr::parse("event.amount", &ev).is_err());
+        assert!(Expr::parse("log.topics", &ev).is_err());
+        assert!(Expr::parse("'unterminated", &ev).is_err());
+        // Indexed params are only their topic
+        assert!(Expr::parse("event.from.0", &ev).is_err());
+    }
+
+    #[test]
+    fn casts() {
+        assert_eq!(split_cast("event.value::numeric").unwrap(), ("event.value", Some("numeric".into())));
+        assert_eq!(split_cast("block.timestamp :: DATE").unwrap(), ("block.timestamp ", Some("date".into())));
+        assert_eq!(split_cast("log.address").unwrap(), ("log.address", None));
+        // `::` inside a text literal is not a cast
+        assert_eq!(split_cast("'a::b'").unwrap(), ("'a::b'", None));
+        assert!(split_cast("event.value::varchar").is_err());
+    }
+
+    fn spec(extra: &str) -> DeclarativeSpec {
+        toml::from_str(&format!(
+            "name = \"transfers\"\n\
+             address = \"0x0000000000000000000000000000000000000001\"\n\
+             event = \"event Transfer(address indexed from, address indexed to, uint256 value)\"\n\
+             from_block = 1\n\
+             table = \"transfers\"\n{extra}"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn columns_of_a_log() {
+        let columns = "[columns]\nsender = \"event.from\"\nvalue = \"event.value::numeric\"\nblock = \"log.block_number\"\nkind = \"'transfer'\"\n";
+        let p = DeclarativeProcessor::new(spec(columns)).unwrap();
+        let from = Address::repeat_byte(0xaa);
+        let log = Log {
+            inner: alloy::primitives::Log::new_unchecked(
+                Address::repeat_byte(1),
+                vec![p.event.selector(), from.into_word(), Address::repeat_byte(0xbb).into_word()],
+                alloy::primitives::U256::from(5).to_be_bytes_vec().into(),
+            ),
+            block_number: Some(9),
+            ..Default::default()
+        };
+        let params = p.decode(&log).unwrap();
+        let row: Vec<_> = p.columns.iter().map(|(c, e, cast)| (c.as_str(), p.eval(e, &params, &log, None).unwrap(), cast.clone())).collect();
+        assert_eq!(
+            row,
+            [
+                ("block", SqlValue::Int(9), None),
+                ("kind", SqlValue::Text("transfer".into()), None),
+                ("sender", SqlValue::Text(format!("{from:#x}")), None),
+                ("value", SqlValue::Numeric(BigDecimal::from(5)), Some("numeric".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_specs() {
+        let err = |extra: &str| DeclarativeProcessor::new(spec(extra)).err().unwrap().to_string();
+        assert!(err("[columns]\nvalue = \"event.amount\"\n").contains("no parameter"));
+        assert!(err("[columns]\n\"bad name\" = \"event.value\"\n").contains("bad name"));
+        assert!(err("update_columns = [\"value\"]\n[columns]\nvalue = \"event.value\"\n").contains("conflict_key"));
+        assert!(err("conflict_key = [\"owner\"]\n[columns]\nvalue = \"event.value\"\n").contains("not mapped"));
+        assert!(err("[columns]\nvalue = \"event.value\"\n").contains("log.block_number"));
+    }
+
+    #[test]
+    fn tuple_fields_by_position() {
+        let ev = event("event Settled((uint256,(address,bytes32)) noun, address winner)");
+        assert_eq!(
+            Expr::parse("event.noun.1.0", &ev).unwrap(),
+            Expr::Param { index: 0, path: vec![1, 0] }
+        );
+        assert!(Expr::parse("event.noun.2", &ev).is_err());
+        assert!(Expr::parse("event.winner.0", &ev).is_err());
+    }
+
+    #[test]
+    fn tuple_array_has_no_fields() {
+        let ev = event("event Batch((address,uint256)[] items, (uint256,uint256)[2] pairs)");
+        assert_eq!(Expr::parse("event.items", &ev).unwrap(), Expr::Param { index: 0, path: vec![] });
+        let err = Expr::parse("event.items.0", &ev).unwrap_err().to_string();
+        assert!(err.contains("not a tuple"), "{err}");
+        assert!(Expr::parse("event.pairs.1", &ev).is_err());
+    }
+
+    #[test]
+    fn unnamed_params_by_position() {
+        let ev = event("event Transfer(address indexed, address indexed, uint256)");
+        assert_eq!(Expr::parse("event.2", &ev).unwrap(), Expr::Param { index: 2, path: vec![] });
+        assert!(Expr::parse("event.3", &ev).is_err());
+
+        // A name wins over a position
+        let ev = event("event Moved(uint256 from, uint256 to)");
+        assert_eq!(Expr::parse("event.to", &ev).unwrap(), Expr::Param { index: 1, path: vec![] });
+        assert_eq!(Expr::parse("event.0", &ev).unwrap(), Expr::Param { index: 0, path: vec![] });
+    }
+
+    #[test]
+    fn indexed_tuple_has_no_fields() {
+        let ev = event("event Set((uint256,uint256) indexed pair)");
+        assert!(Expr::parse("event.pair", &ev).is_ok());
+        assert!(Expr::parse("event.pair.0", &ev).is_err());
+    }
+}
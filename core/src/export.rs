@@ -0,0 +1,489 @@
+//! Exports of indexed tables as CSV, JSONL or Parquet, for analytics dumps.
+//!
+//! Only the tables of [`tables`] can be exported. Rows are read with a
+//! cursor and encoded in chunks, so the result is never held in memory:
+//! [`stream`] hands the encoded bytes over a bounded channel, which both the
+//! `dump` command and `GET /api/export` drain.
+
+use std::sync::Arc;
+
+use arrow_array::builder::{BooleanBuilder, Float64Builder, Int32Builder, Int64Builder, StringBuilder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use chrono::{DateTime, NaiveDate, Utc};
+use eyre::{Result, bail, eyre};
+use futures_util::TryStreamExt;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use tokio::sync::mpsc;
+
+use crate::query::QueryTables;
+
+/// A table that can be exported, and the columns its filters apply to.
+#[derive(Clone, Debug)]
+pub struct ExportTable {
+    pub name: String,
+    /// Block column, for `from_block` / `to_block` and row order.
+    pub block: &'static str,
+    /// Time column, for `from_time` / `to_time`.
+    pub time: Option<&'static str>,
+}
+
+impl ExportTable {
+    fn new(name: &str, block: &'static str, time: Option<&'static str>) -> Self {
+        Self { name: name.to_string(), block, time }
+    }
+}
+
+/// The exportable tables: the profile's accounts, claims and badges tables
+/// (the ones the query API reads) and the tables every profile shares.
+pub fn tables(query: &QueryTables) -> Vec<ExportTable> {
+    vec![
+        ExportTable::new(&query.accounts, "last_update_block_number", None),
+        ExportTable::new(&query.badge_claims, "block_number", Some("claimed_at")),
+        ExportTable::new(&query.account_badges, "last_block_number", None),
+        ExportTable::new("vaults_transactions", "tx_block", Some("block_time")),
+        ExportTable::new("vault_balances", "last_block", None),
+        ExportTable::new("owner_changes", "block_number", None),
+        ExportTable::new("safe_setting_changes", "block_number", None),
+    ]
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Csv,
+    Jsonl,
+    Parquet,
+}
+
+impl Format {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::Csv => "text/csv",
+            Format::Jsonl => "application/x-ndjson",
+            Format::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Jsonl => "jsonl",
+            Format::Parquet => "parquet",
+        }
+    }
+}
+
+/// What to export: the table, the output format and optional filters.
+/// Blocks are inclusive; times are RFC 3339 or plain dates (UTC midnight),
+/// `from_time` inclusive and `to_time` exclusive.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ExportQuery {
+    pub table: String,
+    #[serde(default)]
+    pub format: Format,
+    pub from_block: Option<u64>,
+    pub to_block: Option<u64>,
+    pub from_time: Option<String>,
+    pub to_time: Option<String>,
+    pub account: Option<String>,
+}
+
+/// Value kinds of exported columns. `NUMERIC` is read as text so that
+/// `NUMERIC(78, 0)` amounts keep every digit; anything else not listed
+/// (text, timestamps, arrays, JSON) goes to CSV and Parquet as text.
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    Int32,
+    Int64,
+    Bool,
+    Float,
+    Numeric,
+    Text,
+}
+
+#[derive(Clone, Debug)]
+struct Column {
+    name: String,
+    kind: Kind,
+}
+
+/// A validated export, ready to [`stream`].
+#[derive(Clone, Debug)]
+pub struct Export {
+    pub format: Format,
+    table: ExportTable,
+    columns: Vec<Column>,
+    from_block: Option<u64>,
+    to_block: Option<u64>,
+    from_time: Option<DateTime<Utc>>,
+    to_time: Option<DateTime<Utc>>,
+    account: Option<String>,
+}
+
+impl Export {
+    /// Checks the query against the [`tables`] of `allowed` and reads the
+    /// table's columns.
+    pub async fn new(db: &PgPool, allowed: &QueryTables, query: &ExportQuery) -> Result<Self> {
+        let known = tables(allowed);
+        let names: Vec<_> = known.iter().map(|t| t.name.clone()).collect();
+        let table = known.into_iter().find(|t| t.name == query.table).ok_or_else(|| {
+            eyre!("table '{}' cannot be exported (known: {})", query.table, names.join(", "))
+        })?;
+        if let (Some(from), Some(to)) = (query.from_block, query.to_block)
+            && from > to
+        {
+            bail!("from_block > to_block");
+        }
+        let from_time = query.from_time.as_deref().map(parse_time).transpose()?;
+        let to_time = query.to_time.as_deref().map(parse_time).transpose()?;
+        if (from_time.is_some() || to_time.is_some()) && table.time.is_none() {
+            bail!("table '{}' has no time column, filter by block instead", table.name);
+        }
+
+        let (schema, name) = match table.name.split_once('.') {
+            Some((schema, name)) => (Some(schema), name),
+            None => (None, table.name.as_str()),
+        };
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            "SELECT column_name::text, data_type::text FROM information_schema.columns
+             WHERE table_schema = COALESCE($1, current_schema()) AND table_name = $2 ORDER BY ordinal_position",
+        )
+        .bind(schema)
+        .bind(name)
+        .fetch_all(db)
+        .await?;
+        if rows.is_empty() {
+            bail!("table '{}' does not exist (run `migrate`?)", table.name);
+        }
+        let columns = rows
+            .into_iter()
+            .map(|(name, data_type)| {
+                let kind = match data_type.as_str() {
+                    "smallint" | "integer" => Kind::Int32,
+                    "bigint" => Kind::Int64,
+                    "boolean" => Kind::Bool,
+                    "real" | "double precision" => Kind::Float,
+                    "numeric" => Kind::Numeric,
+                    _ => Kind::Text,
+                };
+                Column { name, kind }
+            })
+            .collect();
+
+        Ok(Self {
+            format: query.format,
+            table,
+            columns,
+            from_block: query.from_block,
+            to_block: query.to_block,
+            from_time,
+            to_time,
+            account: query.account.as_ref().map(|a| a.to_lowercase()),
+        })
+    }
+
+    pub fn table(&self) -> &str {
+        &self.table.name
+    }
+
+    /// One JSON object per row (columns in table order), in block order.
+    fn sql(&self) -> QueryBuilder<'_, Postgres> {
+        let select: Vec<String> = self
+            .columns
+            .iter()
+            .map(|c| match c.kind {
+                Kind::Numeric => format!("\"{0}\"::text AS \"{0}\"", c.name),
+                _ => format!("\"{}\"", c.name),
+            })
+            .collect();
+        let mut qb = QueryBuilder::new(format!(
+            "SELECT row_to_json(r)::text FROM (SELECT {} FROM {} WHERE TRUE",
+            select.join(", "),
+            self.table.name
+        ));
+        let block = self.table.block;
+        if let Some(from) = self.from_block {
+            qb.push(format!(" AND {block} >= ")).push_bind(from as i64);
+        }
+        if let Some(to) = self.to_block {
+            qb.push(format!(" AND {block} <= ")).push_bind(to as i64);
+        }
+        if let Some(time) = self.table.time {
+            if let Some(from) = self.from_time {
+                qb.push(format!(" AND {time} >= ")).push_bind(from);
+            }
+            if let Some(to) = self.to_time {
+                qb.push(format!(" AND {time} < ")).push_bind(to);
+            }
+        }
+        if let Some(account) = &self.account {
+            qb.push(" AND account = ").push_bind(account);
+        }
+        qb.push(format!(") AS r ORDER BY r.{block}, r.account"));
+        qb
+    }
+}
+
+fn parse_time(s: &str) -> Result<DateTime<Utc>> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Ok(t.with_timezone(&Utc));
+    }
+    let day = NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| eyre!("invalid time '{s}' (RFC 3339 or YYYY-MM-DD)"))?;
+    Ok(day.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+}
+
+/// Encoded chunks of at least this size are handed over.
+const CHUNK_BYTES: usize = 64 * 1024;
+/// Rows per Parquet row group.
+const ROW_GROUP: usize = 10_000;
+
+/// Runs the export in the background. Chunks arrive in order; a closed
+/// [`Chunks`] stops it.
+pub fn stream(db: PgPool, export: Export) -> Chunks {
+    let (tx, rx) = mpsc::channel(4);
+    tokio::spawn(async move {
+        if let Err(e) = run(&db, &export, &tx).await {
+            tracing::warn!(table = export.table(), "export failed: {e}");
+            tx.send(Err(e)).await.ok();
+        }
+    });
+    Chunks { rx, ended: false }
+}
+
+/// Encoded chunks of a running export. Only a complete export ends cleanly
+/// (`None`): one that failed, or whose task died, yields an error first,
+/// after the chunks encoded before it.
+pub struct Chunks {
+    rx: mpsc::Receiver<Result<Chunk>>,
+    ended: bool,
+}
+
+enum Chunk {
+    Data(Vec<u8>),
+    /// Every row was encoded.
+    End,
+}
+
+impl Chunks {
+    pub async fn next(&mut self) -> Option<Result<Vec<u8>>> {
+        if self.ended {
+            return None;
+        }
+        match self.rx.recv().await {
+            Some(Ok(Chunk::Data(bytes))) => Some(Ok(bytes)),
+            Some(Ok(Chunk::End)) => {
+                self.ended = true;
+                None
+            }
+            Some(Err(e)) => {
+                self.ended = true;
+                Some(Err(e))
+            }
+            None => {
+                self.ended = true;
+                Some(Err(eyre!("export stopped before its last row")))
+            }
+        }
+    }
+}
+
+async fn run(db: &PgPool, export: &Export, tx: &mpsc::Sender<Result<Chunk>>) -> Result<()> {
+    let mut encoder = Encoder::new(export.format, &export.columns)?;
+    let mut qb = export.sql();
+    let mut rows = qb.build_query_scalar::<String>().fetch(db);
+    let mut count = 0u64;
+    while let Some(row) = rows.try_next().await? {
+        encoder.push(row)?;
+        count += 1;
+        if encoder.buffered() >= CHUNK_BYTES && tx.send(Ok(Chunk::Data(encoder.take()?))).await.is_err() {
+            return Ok(());
+        }
+    }
+    tx.send(Ok(Chunk::Data(encoder.finish()?))).await.ok();
+    tx.send(Ok(Chunk::End)).await.ok();
+    tracing::info!(table = export.table(), rows = count, format = ?export.format, "table exported");
+    Ok(())
+}
+
+enum Encoder {
+    Csv(Box<csv::Writer<Vec<u8>>>, Vec<Column>),
+    Jsonl(Vec<u8>),
+    Parquet(Box<ParquetEncoder>),
+}
+
+struct ParquetEncoder {
+    writer: ArrowWriter<Vec<u8>>,
+    schema: SchemaRef,
+    columns: Vec<Column>,
+    rows: Vec<Value>,
+}
+
+impl Encoder {
+    fn new(format: Format, columns: &[Column]) -> Result<Self> {
+        Ok(match format {
+            Format::Csv => {
+                let mut w = csv::Writer::from_writer(Vec::new());
+                w.write_record(columns.iter().map(|c| &c.name))?;
+                Encoder::Csv(Box::new(w), columns.to_vec())
+            }
+            Format::Jsonl => Encoder::Jsonl(Vec::new()),
+            Format::Parquet => {
+                let fields: Vec<Field> = columns
+                    .iter()
+                    .map(|c| {
+                        let t = match c.kind {
+                            Kind::Int32 => DataType::Int32,
+                            Kind::Int64 => DataType::Int64,
+                            Kind::Bool => DataType::Boolean,
+                            Kind::Float => DataType::Float64,
+                            Kind::Numeric | Kind::Text => DataType::Utf8,
+                        };
+                        Field::new(&c.name, t, true)
+                    })
+                    .collect();
+                let schema: SchemaRef = Arc::new(Schema::new(fields));
+                let props = WriterProperties::builder()
+                    .set_compression(Compression::ZSTD(ZstdLevel::default()))
+                    .set_max_row_group_size(ROW_GROUP)
+                    .build();
+                let writer = ArrowWriter::try_new(Vec::new(), schema.clone(), Some(props))?;
+                Encoder::Parquet(Box::new(ParquetEncoder { writer, schema, columns: columns.to_vec(), rows: Vec::new() }))
+            }
+        })
+    }
+
+    fn push(&mut self, row: String) -> Result<()> {
+        match self {
+            Encoder::Csv(w, columns) => {
+                let row: Value = serde_json::from_str(&row)?;
+                w.write_record(columns.iter().map(|c| cell(&row[&c.name])))?;
+            }
+            Encoder::Jsonl(buf) => {
+                buf.extend_from_slice(row.as_bytes());
+                buf.push(b'\n');
+            }
+            Encoder::Parquet(p) => {
+                p.rows.push(serde_json::from_str(&row)?);
+                if p.rows.len() >= ROW_GROUP {
+                    p.write_rows()?;
+                    p.writer.flush()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn buffered(&mut self) -> usize {
+        match self {
+            // What the csv writer flushed; it keeps a small buffer of its own
+            Encoder::Csv(w, _) => w.get_ref().len(),
+            Encoder::Jsonl(buf) => buf.len(),
+            Encoder::Parquet(p) => p.writer.inner_mut().len(),
+        }
+    }
+
+    fn take(&mut self) -> Result<Vec<u8>> {
+        Ok(match self {
+            Encoder::Csv(w, _) => {
+                let full = std::mem::replace(w, Box::new(csv::Writer::from_writer(Vec::new())));
+                (*full).into_inner().map_err(|e| eyre!("csv: {}", e.error()))?
+            }
+            Encoder::Jsonl(buf) => std::mem::take(buf),
+            Encoder::Parquet(p) => std::mem::take(p.writer.inner_mut()),
+        })
+    }
+
+    fn finish(mut self) -> Result<Vec<u8>> {
+        match &mut self {
+            Encoder::Csv(..) | Encoder::Jsonl(_) => {}
+            Encoder::Parquet(p) => {
+                p.write_rows()?;
+                p.writer.finish()?;
+            }
+        }
+        self.take()
+    }
+}
+
+impl ParquetEncoder {
+    fn write_rows(&mut self) -> Result<()> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        let arrays: Vec<ArrayRef> = self.columns.iter().map(|c| column_array(c, &self.rows)).collect();
+        let batch = RecordBatch::try_new(self.schema.clone(), arrays)?;
+        self.writer.write(&batch)?;
+        self.rows.clear();
+        Ok(())
+    }
+}
+
+fn column_array(column: &Column, rows: &[Value]) -> ArrayRef {
+    let values = rows.iter().map(|r| &r[&column.name]);
+    match column.kind {
+        Kind::Int32 => {
+            let mut b = Int32Builder::with_capacity(rows.len());
+            values.for_each(|v| b.append_option(v.as_i64().map(|n| n as i32)));
+            Arc::new(b.finish())
+        }
+        Kind::Int64 => {
+            let mut b = Int64Builder::with_capacity(rows.len());
+            values.for_each(|v| b.append_option(v.as_i64()));
+            Arc::new(b.finish())
+        }
+        Kind::Bool => {
+            let mut b = BooleanBuilder::with_capacity(rows.len());
+            values.for_each(|v| b.append_option(v.as_bool()));
+            Arc::new(b.finish())
+        }
+        Kind::Float => {
+            let mut b = Float64Builder::with_capacity(rows.len());
+            values.for_each(|v| b.append_option(v.as_f64()));
+            Arc::new(b.finish())
+        }
+        Kind::Numeric | Kind::Text => {
+            let mut b = StringBuilder::new();
+            values.for_each(|v| b.append_option((!v.is_null()).then(|| cell(v))));
+            Arc::new(b.finish())
+        }
+    }
+}
+
+/// A JSON value as text: NULL is empty, strings unquoted, arrays and
+/// objects as JSON.
+fn cell(v: &Value) -> String {
+    match v {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn chunks_without_end_are_an_error() {
+        let (tx, rx) = mpsc::channel(4);
+        let mut chunks = Chunks { rx, ended: false };
+        tx.send(Ok(Chunk::Data(b"a,b\n".to_vec()))).await.unwrap();
+        drop(tx);
+        assert_eq!(chunks.next().await.unwrap().unwrap(), b"a,b\n");
+        assert!(chunks.next().await.unwrap().is_err());
+        assert!(chunks.next().await.is_none());
+
+        let (tx, rx) = mpsc::channel(4);
+        let mut chunks = Chunks { rx, ended: false };
+        tx.send(Ok(Chunk::End)).await.unwrap();
+        drop(tx);
+        assert!(chunks.next().await.is_none());
+    }
+}
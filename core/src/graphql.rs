@@ -0,0 +1,743 @@
+//! GraphQL schema over the tables of [`crate::query`], served at
+//! `POST /api/graphql` (`GET` returns the SDL).
+//!
+//! Relations that fan out from a list (an account's badges, claims and flows,
+//! the account of a badge, claim or flow) go through [`DataLoader`]s, so a
+//! page of N rows costs one query per relation instead of N. Paginated
+//! relations take each owner's page (an account's, or a badge's claims) in
+//! one lateral join (see [`PagesByOwner`]). Paginated fields are Relay
+//! connections whose cursors are the ones of the REST endpoints.
+//!
+//! Queries are bounded in depth and in complexity: a field costs 1, and a
+//! list its page size times its selection.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use async_graphql::connection::{Connection, Edge};
+use async_graphql::dataloader::{DataLoader, Loader};
+use async_graphql::{
+    ComplexObject, Context, EmptyMutation, EmptySubscription, Enum, Error, InputObject, Json as GqlJson, Object,
+    OutputType, Result, Schema, SimpleObject,
+};
+use axum::{Extension, Json, Router, http::StatusCode, routing::get};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+use crate::api::App;
+use crate::query::{self, BadgeFilter, Failure, Listing, Order, PageParams, QueryTables, TransactionFilter};
+
+pub type IndexerSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Deepest selection a query may nest (account → badges → account → ...).
+const MAX_DEPTH: usize = 10;
+/// Most a query may cost, e.g. 20 fields of 500 holders, or 4 fields of the
+/// 50 flows of each of 50 holders.
+const MAX_COMPLEXITY: usize = 10_000;
+
+/// Builds the schema over `db`, reading the profile's `tables`.
+pub fn schema(db: PgPool, tables: QueryTables) -> IndexerSchema {
+    let accounts = AccountLoader { db: db.clone(), table: tables.accounts.clone() };
+    let badge_pages = PagesByOwner::<String, Badge, BadgeFilter>::new(&db, &tables, |t| Listing::account_badges(t));
+    let claim_pages = PagesByOwner::<String, BadgeClaim, BadgeFilter>::new(&db, &tables, |t| Listing::badge_claims(t));
+    let badge_claim_pages =
+        PagesByOwner::<AccountBadge, BadgeClaim, BadgeFilter>::new(&db, &tables, |t| Listing::badge_claims(t));
+    let flow_pages = PagesByOwner::<String, VaultTransaction, TransactionFilter>::new(&db, &tables, |_| {
+        Listing::vault_transactions()
+    });
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(Source { db, tables })
+        .data(DataLoader::new(accounts, tokio::spawn))
+        .data(DataLoader::new(badge_pages, tokio::spawn))
+        .data(DataLoader::new(claim_pages, tokio::spawn))
+        .data(DataLoader::new(badge_claim_pages, tokio::spawn))
+        .data(DataLoader::new(flow_pages, tokio::spawn))
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+/// The GraphQL route, to merge with the other `/api` routes. Without a
+/// database it answers 503, like the query endpoints.
+pub fn routes(app: &App) -> Router<Arc<App>> {
+    let schema = app.db().map(|db| schema(db.clone(), app.tables().clone()));
+    Router::new().route("/graphql", get(sdl).post(execute)).layer(Extension(schema))
+}
+
+async fn execute(
+    Extension(schema): Extension<Option<IndexerSchema>>,
+    Json(request): Json<async_graphql::Request>,
+) -> Result<Json<async_graphql::Response>, Failure> {
+    let schema = attached(schema)?;
+    Ok(Json(schema.execute(request).await))
+}
+
+async fn sdl(Extension(schema): Extension<Option<IndexerSchema>>) -> Result<String, Failure> {
+    Ok(attached(schema)?.sdl())
+}
+
+fn attached(schema: Option<IndexerSchema>) -> Result<IndexerSchema, Failure> {
+    schema.ok_or_else(|| {
+        (StatusCode::SERVICE_UNAVAILABLE, Json(crate::api::Resp { ok: false, msg: "no database attached".into() }))
+    })
+}
+
+/// Database and tables, as schema data.
+struct Source {
+    db: PgPool,
+    tables: QueryTables,
+}
+
+/// A REST failure as a GraphQL error (its message, the status dropped).
+fn gql((_, Json(resp)): Failure) -> Error {
+    Error::new(resp.msg)
+}
+
+fn loader_error(e: Arc<sqlx::Error>) -> Error {
+    gql(query::internal(e))
+}
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Row of the profile's accounts table.
+#[derive(Clone, Debug, Deserialize, SimpleObject)]
+#[graphql(complex)]
+pub struct Account {
+    pub account: String,
+    pub username: Option<String>,
+    pub nationality: Option<String>,
+    /// Owners (EOAs) of the account.
+    #[graphql(name = "owners")]
+    #[serde(default)]
+    pub eoas: Vec<String>,
+    pub level: Option<i32>,
+    pub noun: Option<GqlJson<Value>>,
+    pub total_points: Option<i32>,
+    pub total_badges: Option<i32>,
+    pub threshold: Option<i32>,
+    pub module_enabled: Option<bool>,
+    pub last_update_block_number: Option<i64>,
+    pub last_update_tx_hash: Option<String>,
+}
+
+#[ComplexObject]
+impl Account {
+    /// Badges held now, with their current tier. Sorts: `badge_id`, `tier`,
+    /// `points`, `block`.
+    #[allow(clippy::too_many_arguments)]
+    #[graphql(complexity = "listed(first, child_complexity)")]
+    async fn badges(
+        &self,
+        ctx: &Context<'_>,
+        badge_id: Option<i32>,
+        min_tier: Option<i32>,
+        first: Option<u32>,
+        after: Option<String>,
+        sort: Option<String>,
+        order: Option<SortOrder>,
+    ) -> Result<Connection<String, Badge>> {
+        let filter = BadgeFilter { badge_id, min_tier, ..Default::default() };
+        owner_page(ctx, self.account.clone(), filter, page(first, after, sort, order)).await
+    }
+
+    /// Every tier claimed, in block order.
+    #[graphql(complexity = "listed(first, child_complexity)")]
+    async fn claims(
+        &self,
+        ctx: &Context<'_>,
+        badge_id: Option<i32>,
+        first: Option<u32>,
+        after: Option<String>,
+        order: Option<SortOrder>,
+    ) -> Result<Connection<String, BadgeClaim>> {
+        let filter = BadgeFilter { badge_id, ..Default::default() };
+        owner_page(ctx, self.account.clone(), filter, page(first, after, None, order)).await
+    }
+
+    /// Vault deposits and withdrawals of the account. Sorts: `block`, `amount`.
+    #[allow(clippy::too_many_arguments)]
+    #[graphql(complexity = "listed(first, child_complexity)")]
+    async fn vault_transactions(
+        &self,
+        ctx: &Context<'_>,
+        token: Option<String>,
+        direction: Option<String>,
+        event_type: Option<String>,
+        first: Option<u32>,
+        after: Option<String>,
+        sort: Option<String>,
+        order: Option<SortOrder>,
+    ) -> Result<Connection<String, VaultTransaction>> {
+        let filter = TransactionFilter { token, direction, event_type, ..Default::default() };
+        let filter = filter.normalized().map_err(gql)?;
+        owner_page(ctx, self.account.clone(), filter, page(first, after, sort, order)).await
+    }
+}
+
+/// A badge an account holds (`account_badges`).
+#[derive(Clone, Debug, Deserialize, SimpleObject)]
+#[graphql(complex)]
+pub struct Badge {
+    #[graphql(skip)]
+    pub account: String,
+    pub badge_id: i32,
+    pub tier: Option<i32>,
+    pub points: Option<i32>,
+    pub uri: Option<String>,
+    pub last_block_number: i64,
+    pub last_tx_hash: Option<String>,
+}
+
+#[ComplexObject]
+impl Badge {
+    async fn account(&self, ctx: &Context<'_>) -> Result<Option<Account>> {
+        load_account(ctx, &self.account).await
+    }
+
+    /// The claims that got the holder to its tier, in block order.
+    #[graphql(complexity = "listed(first, child_complexity)")]
+    async fn claims(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<u32>,
+        after: Option<String>,
+        order: Option<SortOrder>,
+    ) -> Result<Connection<String, BadgeClaim>> {
+        let owner = AccountBadge { account: self.account.clone(), badge_id: self.badge_id };
+        owner_page(ctx, owner, BadgeFilter::default(), page(first, after, None, order)).await
+    }
+}
+
+/// A tier claimed (`badge_claims`).
+#[derive(Clone, Debug, Deserialize, SimpleObject)]
+#[graphql(complex)]
+pub struct BadgeClaim {
+    #[graphql(skip)]
+    pub account: String,
+    pub badge_id: i32,
+    pub tier: Option<i32>,
+    pub points: Option<i32>,
+    pub uri: Option<String>,
+    pub block_number: i64,
+    pub tx_hash: String,
+    pub claimed_at: Option<String>,
+}
+
+#[ComplexObject]
+impl BadgeClaim {
+    async fn account(&self, ctx: &Context<'_>) -> Result<Option<Account>> {
+        load_account(ctx, &self.account).await
+    }
+}
+
+/// A vault deposit or withdrawal (`vaults_transactions`). `amount` is the
+/// exact `NUMERIC`, as a string.
+#[derive(Clone, Debug, Deserialize, SimpleObject)]
+#[graphql(complex)]
+pub struct VaultTransaction {
+    pub id: i64,
+    #[graphql(skip)]
+    pub account: String,
+    pub token: String,
+    pub amount: String,
+    pub direction: String,
+    pub event_type: String,
+    pub tx_hash: String,
+    pub tx_block: i64,
+    pub block_time: String,
+}
+
+#[ComplexObject]
+impl VaultTransaction {
+    async fn account(&self, ctx: &Context<'_>) -> Result<Option<Account>> {
+        load_account(ctx, &self.account).await
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Enum)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// Filters of `vaultTransactions`; blocks are inclusive.
+#[derive(Debug, Default, InputObject)]
+pub struct VaultTransactionFilter {
+    pub account: Option<String>,
+    pub token: Option<String>,
+    pub direction: Option<String>,
+    pub event_type: Option<String>,
+    pub from_block: Option<u64>,
+    pub to_block: Option<u64>,
+}
+
+// ============================================================================
+// Query root
+// ============================================================================
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    async fn account(&self, ctx: &Context<'_>, address: String) -> Result<Option<Account>> {
+        let address = query::address(&address).map_err(gql)?;
+        load_account(ctx, &address).await
+    }
+
+    /// Accounts by address, in the order given (`null` for unknown ones).
+    #[graphql(complexity = "addresses.len() * child_complexity")]
+    async fn accounts(&self, ctx: &Context<'_>, addresses: Vec<String>) -> Result<Vec<Option<Account>>> {
+        let addresses =
+            addresses.iter().map(|a| query::address(a)).collect::<Result<Vec<_>, _>>().map_err(gql)?;
+        let found = ctx
+            .data_unchecked::<DataLoader<AccountLoader>>()
+            .load_many(addresses.iter().cloned())
+            .await
+            .map_err(loader_error)?;
+        Ok(addresses.iter().map(|a| found.get(a).cloned()).collect())
+    }
+
+    /// Holders of a badge. Sorts: `account`, `tier`, `points`, `block`.
+    #[allow(clippy::too_many_arguments)]
+    #[graphql(complexity = "listed(first, child_complexity)")]
+    async fn badge_holders(
+        &self,
+        ctx: &Context<'_>,
+        badge_id: i32,
+        tier: Option<i32>,
+        min_tier: Option<i32>,
+        first: Option<u32>,
+        after: Option<String>,
+        sort: Option<String>,
+        order: Option<SortOrder>,
+    ) -> Result<Connection<String, Badge>> {
+        let source = ctx.data_unchecked::<Source>();
+        let filter = BadgeFilter { badge_id: Some(badge_id), tier, min_tier };
+        connection(Listing::badge_holders(&source.tables), &source.db, page(first, after, sort, order), |qb| {
+            filter.push(qb)
+        })
+        .await
+    }
+
+    /// Claims in block order, optionally of one badge or account.
+    #[graphql(complexity = "listed(first, child_complexity)")]
+    async fn badge_claims(
+        &self,
+        ctx: &Context<'_>,
+        badge_id: Option<i32>,
+        account: Option<String>,
+        first: Option<u32>,
+        after: Option<String>,
+        order: Option<SortOrder>,
+    ) -> Result<Connection<String, BadgeClaim>> {
+        let source = ctx.data_unchecked::<Source>();
+        let account = account.as_deref().map(query::address).transpose().map_err(gql)?;
+        connection(Listing::badge_claims(&source.tables), &source.db, page(first, after, None, order), |qb| {
+            if let Some(id) = badge_id {
+                qb.push(" AND t.badge_id = ").push_bind(id);
+            }
+            if let Some(account) = account {
+                qb.push(" AND t.account = ").push_bind(account);
+            }
+        })
+        .await
+    }
+
+    /// Vault flows of every account. Sorts: `block`, `amount`.
+    #[graphql(complexity = "listed(first, child_complexity)")]
+    async fn vault_transactions(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<VaultTransactionFilter>,
+        first: Option<u32>,
+        after: Option<String>,
+        sort: Option<String>,
+        order: Option<SortOrder>,
+    ) -> Result<Connection<String, VaultTransaction>> {
+        let f = filter.unwrap_or_default();
+        let filter = TransactionFilter {
+            account: f.account,
+            token: f.token,
+            direction: f.direction,
+            event_type: f.event_type,
+            from_block: f.from_block,
+            to_block: f.to_block,
+        };
+        vault_transactions(ctx, filter, page(first, after, sort, order)).await
+    }
+}
+
+/// Cost of a list of `first` items, each costing `child`.
+fn listed(first: Option<u32>, child: usize) -> usize {
+    query::page_limit(first) as usize * child
+}
+
+fn page(first: Option<u32>, after: Option<String>, sort: Option<String>, order: Option<SortOrder>) -> PageParams {
+    let order = match order {
+        Some(SortOrder::Desc) => Order::Desc,
+        _ => Order::Asc,
+    };
+    PageParams { limit: first, cursor: after, sort, order }
+}
+
+/// One page of `listing` as a connection.
+async fn connection<T, F>(listing: Listing<'_>, db: &PgPool, params: PageParams, filter: F) -> Result<Connection<String, T>>
+where
+    T: OutputType + DeserializeOwned,
+    F: FnOnce(&mut QueryBuilder<'_, Postgres>),
+{
+    let rows = listing.rows(db, &params, filter).await.map_err(gql)?;
+    let mut conn = Connection::new(params.cursor.is_some(), rows.has_next);
+    for (item, cursor) in rows.items {
+        conn.edges.push(Edge::new(cursor, serde_json::from_str(item.get())?));
+    }
+    Ok(conn)
+}
+
+async fn vault_transactions(
+    ctx: &Context<'_>,
+    filter: TransactionFilter,
+    params: PageParams,
+) -> Result<Connection<String, VaultTransaction>> {
+    let source = ctx.data_unchecked::<Source>();
+    let filter = filter.normalized().map_err(gql)?;
+    connection(Listing::vault_transactions(), &source.db, params, |qb| filter.push(qb)).await
+}
+
+/// The page of an owner's relation, through its [`PagesByOwner`] loader.
+async fn owner_page<K, T, F>(ctx: &Context<'_>, owner: K, filter: F, params: PageParams) -> Result<Connection<String, T>>
+where
+    K: Owner,
+    T: OutputType + DeserializeOwned + Clone + Send + Sync + 'static,
+    F: AccountFilter,
+{
+    let key = PageKey {
+        owner,
+        filter,
+        limit: params.limit,
+        cursor: params.cursor,
+        sort: params.sort,
+        order: params.order,
+    };
+    let has_previous = key.cursor.is_some();
+    let loader = ctx.data_unchecked::<DataLoader<PagesByOwner<K, T, F>>>();
+    let page = loader.load_one(key).await?.unwrap_or_else(|| AccountPage { items: Vec::new(), has_next: false });
+    let mut conn = Connection::new(has_previous, page.has_next);
+    conn.edges.extend(page.items.into_iter().map(|(item, cursor)| Edge::new(cursor, item)));
+    Ok(conn)
+}
+
+async fn load_account(ctx: &Context<'_>, address: &str) -> Result<Option<Account>> {
+    let accounts = ctx.data_unchecked::<DataLoader<AccountLoader>>();
+    accounts.load_one(address.to_string()).await.map_err(loader_error)
+}
+
+// ============================================================================
+// Loaders
+// ============================================================================
+
+/// Accounts by address, in one `= ANY($1)` query per batch.
+pub struct AccountLoader {
+    db: PgPool,
+    table: String,
+}
+
+impl Loader<String> for AccountLoader {
+    type Value = Account;
+    type Error = Arc<sqlx::Error>;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, Account>, Self::Error> {
+        let sql = format!("SELECT row_to_json(t)::text FROM {} AS t WHERE t.account = ANY($1)", self.table);
+        let rows: Vec<String> = sqlx::query_scalar(&sql).bind(keys).fetch_all(&self.db).await?;
+        rows.iter()
+            .map(|row| {
+                let account: Account = decode(row)?;
+                Ok((account.account.clone(), account))
+            })
+            .collect()
+    }
+}
+
+fn decode<T: DeserializeOwned>(row: &str) -> Result<T, Arc<sqlx::Error>> {
+    serde_json::from_str(row).map_err(|e| Arc::new(sqlx::Error::Decode(Box::new(e))))
+}
+
+/// Filters of an account's relation, part of its [`PageKey`].
+pub(crate) trait AccountFilter: Clone + Eq + Hash + Send + Sync + 'static {
+    /// Appends the filters as `AND ...` clauses.
+    fn push(&self, qb: &mut QueryBuilder<'_, Postgres>);
+}
+
+impl AccountFilter for BadgeFilter {
+    fn push(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        BadgeFilter::push(self, qb)
+    }
+}
+
+impl AccountFilter for TransactionFilter {
+    fn push(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        TransactionFilter::push(self, qb)
+    }
+}
+
+/// Whose rows a relation pages through: an account, or one of its badges.
+pub(crate) trait Owner: Clone + Default + Eq + Hash + Send + Sync + 'static {
+    /// Columns of the listing's table that pick its rows: `(expr, type)`.
+    const COLUMNS: &'static [(&'static str, &'static str)];
+
+    /// Values of [`Owner::COLUMNS`], as text.
+    fn values(&self) -> Vec<String>;
+}
+
+impl Owner for String {
+    const COLUMNS: &'static [(&'static str, &'static str)] = &[("t.account", "text")];
+
+    fn values(&self) -> Vec<String> {
+        vec![self.clone()]
+    }
+}
+
+/// A badge of an account, owning its claims.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) struct AccountBadge {
+    account: String,
+    badge_id: i32,
+}
+
+impl Owner for AccountBadge {
+    const COLUMNS: &'static [(&'static str, &'static str)] = &[("t.account", "text"), ("t.badge_id", "int")];
+
+    fn values(&self) -> Vec<String> {
+        vec![self.account.clone(), self.badge_id.to_string()]
+    }
+}
+
+/// An owner's page of a relation, with the arguments it was asked for.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct PageKey<K, F> {
+    owner: K,
+    filter: F,
+    limit: Option<u32>,
+    cursor: Option<String>,
+    sort: Option<String>,
+    order: Order,
+}
+
+/// One page, with the cursor of each item.
+#[derive(Clone)]
+pub(crate) struct AccountPage<T> {
+    items: Vec<(T, String)>,
+    has_next: bool,
+}
+
+/// Pages of a [`Listing`] for a batch of owners. Keys asking for the same
+/// page (filters, sort, cursor) share one query, whose lateral join limits
+/// each owner to its page.
+pub(crate) struct PagesByOwner<K, T, F> {
+    db: PgPool,
+    tables: QueryTables,
+    listing: for<'a> fn(&'a QueryTables) -> Listing<'a>,
+    row: PhantomData<fn() -> T>,
+    key: PhantomData<fn() -> (K, F)>,
+}
+
+impl<K, T, F> PagesByOwner<K, T, F> {
+    fn new(db: &PgPool, tables: &QueryTables, listing: for<'a> fn(&'a QueryTables) -> Listing<'a>) -> Self {
+        Self { db: db.clone(), tables: tables.clone(), listing, row: PhantomData, key: PhantomData }
+    }
+}
+
+impl<K, T, F> Loader<PageKey<K, F>> for PagesByOwner<K, T, F>
+where
+    K: Owner,
+    T: DeserializeOwned + Clone + Send + Sync + 'static,
+    F: AccountFilter,
+{
+    type Value = AccountPage<T>;
+    type Error = Error;
+
+    async fn load(&self, keys: &[PageKey<K, F>]) -> Result<HashMap<PageKey<K, F>, AccountPage<T>>, Self::Error> {
+        let mut groups: HashMap<PageKey<K, F>, Vec<K>> = HashMap::new();
+        for key in keys {
+            let shared = PageKey { owner: K::default(), ..key.clone() };
+            groups.entry(shared).or_default().push(key.owner.clone());
+        }
+
+        let listing = (self.listing)(&self.tables);
+        let mut pages = HashMap::with_capacity(keys.len());
+        for (shared, owners) in groups {
+            let params = PageParams {
+                limit: shared.limit,
+                cursor: shared.cursor.clone(),
+                sort: shared.sort.clone(),
+                order: shared.order,
+            };
+            let values: Vec<Vec<String>> = owners.iter().map(Owner::values).collect();
+            let mut rows = listing
+                .rows_by_owner(&self.db, K::COLUMNS, &values, &params, |qb| shared.filter.push(qb))
+                .await
+                .map_err(gql)?;
+            for (owner, values) in owners.into_iter().zip(&values) {
+                let page = match rows.remove(values) {
+                    Some(r) => AccountPage {
+                        items: r
+                            .items
+                            .iter()
+                            .map(|(item, cursor)| Ok((serde_json::from_str(item.get())?, cursor.clone())))
+                            .collect::<Result<_, serde_json::Error>>()?,
+                        has_next: r.has_next,
+                    },
+                    None => AccountPage { items: Vec::new(), has_next: false },
+                };
+                pages.insert(PageKey { owner, ..shared.clone() }, page);
+            }
+        }
+        Ok(pages)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const A: &str = "0x00000000000000000000000000000000000000aa";
+    const B: &str = "0x00000000000000000000000000000000000000bb";
+
+    async fn run(schema: &IndexerSchema, query: &str) -> Value {
+        let res = schema.execute(query).await;
+        assert!(res.errors.is_empty(), "{:?}", res.errors);
+        res.data.into_json().unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn account_relations_page_per_account() {
+        let db = crate::testing::fresh_db("graphql_pages").await;
+        for account in [A, B] {
+            sqlx::query(
+                "INSERT INTO super_accounts (account, username, eoas, level, noun, total_points, total_badges)
+                 VALUES ($1, 'test', '{}', 0, '{}', 0, 0)",
+            )
+            .bind(account)
+            .execute(&db)
+            .await
+            .unwrap();
+        }
+        // Three flows of A, one of B
+        for (account, block) in [(A, 1), (A, 2), (A, 3), (B, 4)] {
+            sqlx::query(
+                "INSERT INTO vaults_transactions (account, token, amount, direction, event_type, tx_hash, tx_block, block_time)
+                 VALUES ($1, $1, $2, 'in', 'supply', $3, $2, NOW())",
+            )
+            .bind(account)
+            .bind(block as i64)
+            .bind(format!("0x{block}"))
+            .execute(&db)
+            .await
+            .unwrap();
+        }
+        sqlx::query(
+            "INSERT INTO account_badges (account, badge_id, tier, last_block_number, last_tx_hash)
+             VALUES ($1, 1, 1, 1, '0x1'), ($1, 2, 3, 1, '0x1'), ($2, 1, 2, 1, '0x1')",
+        )
+        .bind(A)
+        .bind(B)
+        .execute(&db)
+        .await
+        .unwrap();
+
+        let schema = schema(db, QueryTables::default());
+        let page = |after: &str| {
+            format!(
+                r#"{{ accounts(addresses: ["{A}", "{B}"]) {{
+                    vaultTransactions(first: 2{after}) {{ edges {{ cursor node {{ txBlock }} }} pageInfo {{ hasNextPage }} }}
+                    badges(minTier: 2) {{ edges {{ node {{ badgeId }} }} }}
+                }} }}"#
+            )
+        };
+        let data = run(&schema, &page("")).await;
+        let blocks = |data: &Value, i: usize| -> Vec<Value> {
+            data["accounts"][i]["vaultTransactions"]["edges"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| e["node"]["txBlock"].clone())
+                .collect()
+        };
+        assert_eq!(blocks(&data, 0), vec![json!(1), json!(2)]);
+        assert_eq!(data["accounts"][0]["vaultTransactions"]["pageInfo"]["hasNextPage"], json!(true));
+        assert_eq!(blocks(&data, 1), vec![json!(4)]);
+        assert_eq!(data["accounts"][1]["vaultTransactions"]["pageInfo"]["hasNextPage"], json!(false));
+        assert_eq!(data["accounts"][0]["badges"]["edges"], json!([{ "node": { "badgeId": 2 } }]));
+        assert_eq!(data["accounts"][1]["badges"]["edges"], json!([{ "node": { "badgeId": 1 } }]));
+
+        // The cursor is a position in the sort, applied to every account
+        let cursor = data["accounts"][0]["vaultTransactions"]["edges"][1]["cursor"].as_str().unwrap().to_string();
+        let data = run(&schema, &page(&format!(r#", after: "{cursor}""#))).await;
+        assert_eq!(blocks(&data, 0), vec![json!(3)]);
+        assert_eq!(blocks(&data, 1), vec![json!(4)]);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn badge_claims_page_per_badge() {
+        let db = crate::testing::fresh_db("graphql_badge_claims").await;
+        sqlx::query(
+            "INSERT INTO account_badges (account, badge_id, tier, last_block_number, last_tx_hash)
+             VALUES ($1, 1, 2, 5, '0x5'), ($1, 2, 1, 6, '0x6'), ($2, 1, 1, 7, '0x7')",
+        )
+        .bind(A)
+        .bind(B)
+        .execute(&db)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO badge_claims (account, badge_id, tier, block_number, tx_hash)
+             VALUES ($1, 1, 1, 3, '0x3'), ($1, 1, 2, 5, '0x5'), ($1, 2, 1, 6, '0x6'), ($2, 1, 1, 7, '0x7')",
+        )
+        .bind(A)
+        .bind(B)
+        .execute(&db)
+        .await
+        .unwrap();
+
+        let schema = schema(db, QueryTables::default());
+        let data = run(
+            &schema,
+            r#"{ badgeHolders(badgeId: 1) { edges { node { claims(first: 1) {
+                   edges { node { tier blockNumber } } pageInfo { hasNextPage }
+               } } } } }"#,
+        )
+        .await;
+        let claims: Vec<Value> =
+            data["badgeHolders"]["edges"].as_array().unwrap().iter().map(|e| e["node"]["claims"].clone()).collect();
+        // Each holder's own claims of badge 1 only, a page each
+        assert_eq!(
+            claims,
+            [
+                json!({ "edges": [{ "node": { "tier": 1, "blockNumber": 3 } }], "pageInfo": { "hasNextPage": true } }),
+                json!({ "edges": [{ "node": { "tier": 1, "blockNumber": 7 } }], "pageInfo": { "hasNextPage": false } }),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn wide_queries_are_refused() {
+        let db = PgPool::connect_lazy("postgres://unused/db").unwrap();
+        let schema = schema(db, QueryTables::default());
+        let query = r#"{ badgeHolders(badgeId: 1, first: 500) { edges { node {
+            account { vaultTransactions(first: 500) { edges { node { amount } } } }
+        } } } }"#;
+        let res = schema.execute(query).await;
+        assert_eq!(res.errors.len(), 1);
+        assert!(res.errors[0].message.contains("too complex"), "{}", res.errors[0].message);
+    }
+}
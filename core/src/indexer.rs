@@ -1,14 +1,13 @@
-use crate::api::{router_with_dashboard, App, IndexState, Status};
+use crate::api::{App, IndexState, Status};
 use crate::resilience::{AdaptiveChunkManager, RetryConfig, with_retry};
 use crate::strategies::{ChunkProcessor, IndexedRangeDecorator, Stats, StrategyConfig};
 use alloy::providers::Provider;
-use eyre::{Result, ensure};
+use eyre::{Result, WrapErr, ensure};
 use futures_util::future::join_all;
 use indicatif::{ProgressBar, ProgressStyle};
 use sqlx::PgPool;
-use std::net::SocketAddr;
-use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tracing::{debug, error, info, warn};
 
 // ============================================================================
@@ -33,8 +32,34 @@ impl IndexerConfig {
             ),
         }
     }
+
+    pub fn with_bounds(initial_chunk_size: u64, min: u64, max: u64, retry: RetryConfig) -> Self {
+        Self {
+            retry,
+            chunk_manager: AdaptiveChunkManager::new(initial_chunk_size, min, max),
+        }
+    }
+}
+
+/// A strategy chunk that still failed after its retries.
+#[derive(Debug, Clone)]
+pub struct ChunkFailure {
+    pub strategy: &'static str,
+    pub from: u64,
+    pub to: u64,
+    pub error: String,
+}
+
+/// Outcome of [`run_indexer`]: the block it stopped at (`to`, or where it
+/// was interrupted) and the chunks that failed on the way.
+#[derive(Debug, Default)]
+pub struct RunReport {
+    pub last: u64,
+    pub failed: Vec<ChunkFailure>,
 }
 
+/// Indexes `from..=to` chunk by chunk. A failed chunk is logged and
+/// reported, and the run goes on with the next one.
 pub async fn run_indexer<P>(
     provider: P,
     db: &PgPool,
@@ -43,11 +68,12 @@ pub async fn run_indexer<P>(
     config: &IndexerConfig,
     strategies: Vec<StrategyConfig<P>>,
     app: Option<Arc<App>>,
-) -> Result<u64>
+) -> Result<RunReport>
 where
     P: Provider + Clone + Send + Sync + 'static,
 {
     ensure!(from <= to, "from > to");
+    let mut failed = Vec::new();
     let total = to - from + 1;
     let initial_chunk = config.chunk_manager.get();
     info!(from, to, total, chunk_size = initial_chunk, "starting indexer with resilience");
@@ -68,7 +94,7 @@ where
                 if let Some(ref mut idx) = s.index {
                     idx.current = cur;
                 }
-                return Ok(cur);
+                return Ok(RunReport { last: cur, failed });
             }
             // Update current position
             let mut s = a.state.write().await;
@@ -92,7 +118,7 @@ where
             let retry_config = config.retry.clone();
             let chunk_manager = config.chunk_manager.clone();
 
-            tokio::spawn(async move {
+            let task = tokio::spawn(async move {
                 if s.max(c.from_block) > e {
                     return Ok(Stats::default());
                 }
@@ -116,26 +142,31 @@ where
                 }
 
                 result
-            })
+            });
+            (cfg.name, task)
         }).collect();
 
         let mut had_error = false;
-        for r in join_all(tasks).await {
-            match r {
+        let (names, tasks): (Vec<_>, Vec<_>) = tasks.into_iter().unzip();
+        for (strategy, r) in names.into_iter().zip(join_all(tasks).await) {
+            let error = match r {
                 Ok(Ok(s)) => {
                     if s.logs_found > 0 || s.rows_written > 0 {
                         info!(logs = s.logs_found, rows = s.rows_written, "strategy completed");
                     }
+                    continue;
                 }
                 Ok(Err(e)) => {
-                    error!("strategy error: {e}");
-                    had_error = true;
+                    error!(strategy, "strategy error: {e}");
+                    e.to_string()
                 }
                 Err(e) => {
-                    error!("task panic: {e}");
-                    had_error = true;
+                    error!(strategy, "task panic: {e}");
+                    format!("task panic: {e}")
                 }
-            }
+            };
+            had_error = true;
+            failed.push(ChunkFailure { strategy, from: cur, to: end, error });
         }
 
         // If there were errors, we still continue but the chunk may have been reduced
@@ -151,64 +182,44 @@ where
     }
 
     bar.finish_with_message("✅ Done");
-    info!("indexer finished");
-    Ok(to)
+    info!(failed = failed.len(), "indexer finished");
+    Ok(RunReport { last: to, failed })
 }
 
 // ============================================================================
-// Main loop with API
+// Follow loop
 // ============================================================================
 
-pub async fn run_indexer_and_follow<P>(
+/// Calls [`ChunkProcessor::prepare`] on every strategy.
+pub async fn prepare<P>(db: &PgPool, strategies: &[StrategyConfig<P>]) -> Result<()>
+where
+    P: Provider + Clone + Send + Sync + 'static,
+{
+    for s in strategies {
+        s.processor.prepare(db).await.wrap_err_with(|| format!("preparing strategy {}", s.name))?;
+    }
+    Ok(())
+}
+
+/// Follows the chain head forever, serving pause/reindex requests posted to `app`.
+/// Offline reindexes replay through `offline` (see [`crate::replay`]).
+///
+/// Usually driven through [`crate::builder::IndexerBuilder`].
+#[allow(clippy::too_many_arguments)]
+pub async fn follow<P>(
     provider: P,
+    offline: Option<P>,
     db: &PgPool,
     strategies: Vec<StrategyConfig<P>>,
-    chunk_size: u64,
+    config: &IndexerConfig,
     confirmations: u64,
-    poll_secs: u64,
+    poll_interval: Duration,
+    app: Arc<App>,
 ) -> Result<()>
 where
     P: Provider + Clone + Send + Sync + 'static,
 {
-    let port: u16 = std::env::var("API_PORT").ok().and_then(|p| p.parse().ok()).unwrap_or(3000);
-    let api_key = std::env::var("API_KEY").unwrap_or_else(|_| "changeme".into());
-
-    let app = App::new(api_key);
-
-    // Create resilient indexer configuration
-    let config = IndexerConfig::new(chunk_size);
-    info!(
-        chunk_size = config.chunk_manager.get(),
-        min = 100,
-        max = chunk_size * 2,
-        "initialized adaptive chunk manager"
-    );
-
-    // Check for dashboard path
-    let dashboard_path = std::env::var("DASHBOARD_PATH")
-        .ok()
-        .map(PathBuf::from)
-        .or_else(|| {
-            // Try to find dashboard/dist relative to current dir
-            let candidates = [
-                PathBuf::from("dashboard/dist"),
-                PathBuf::from("../dashboard/dist"),
-            ];
-            candidates.into_iter().find(|p| p.exists())
-        });
-
-    // Start API server
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
-    if dashboard_path.is_some() {
-        info!("API: http://0.0.0.0:{port} (endpoints: /api/*, /dashboard)");
-    } else {
-        info!("API: http://0.0.0.0:{port} (endpoints: /status, /pause, /resume, /reindex)");
-        info!("Dashboard not found. Set DASHBOARD_PATH or build dashboard with 'npm run build'");
-    }
-    let r = router_with_dashboard(app.clone(), dashboard_path);
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    tokio::spawn(async move { axum::serve(listener, r).await.ok(); });
-
+    prepare(db, &strategies).await?;
     let mut last = strategies.iter().map(|c| c.from_block).min().unwrap_or(0);
 
     loop {
@@ -237,55 +248,82 @@ where
                 None => strategies.to_vec(),
             };
 
+            let source = match (reindex_req.offline, &offline) {
+                (false, _) => Some(provider.clone()),
+                (true, Some(replay)) => Some(replay.clone()),
+                (true, None) => None,
+            };
+
             if strats.is_empty() {
                 warn!("no matching strategies for reindex");
-            } else {
-                let head = provider.get_block_number().await? as u64;
+            } else if let Some(source) = source {
+                let head = source.get_block_number().await? as u64;
                 let from = if reindex_req.from > 0 { reindex_req.from }
                           else { strats.iter().map(|s| s.from_block).min().unwrap_or(0) };
-                let to = if reindex_req.to > 0 { reindex_req.to } 
-                        else { last.max(head.saturating_sub(confirmations)) };
-
-                // Update state with calculated from/to values
-                {
-                    let mut s = app.state.write().await;
-                    if let Some(ref mut idx) = s.index {
-                        idx.from = from;
-                        idx.to = to;
-                        idx.current = from;
+                let to = match (reindex_req.to, reindex_req.offline) {
+                    (0, false) => last.max(head.saturating_sub(confirmations)),
+                    // The archive holds nothing past its head
+                    (0, true) => head,
+                    (to, _) => to,
+                };
+
+                // indexed_ranges is cleared below, so the archive must hold the whole range
+                let uncovered = if reindex_req.offline {
+                    match crate::replay::archived_range(db, source.get_chain_id().await?).await? {
+                        Some((first, newest)) if first <= from && to <= newest => None,
+                        archived => Some(archived),
+                    }
+                } else {
+                    None
+                };
+
+                if let Some(archived) = uncovered {
+                    warn!(from, to, ?archived, "offline reindex outside the archive, ignored");
+                } else {
+                    // Update state with calculated from/to values
+                    {
+                        let mut s = app.state.write().await;
+                        if let Some(ref mut idx) = s.index {
+                            idx.from = from;
+                            idx.to = to;
+                            idx.current = from;
+                        }
                     }
-                }
 
-                if from <= to {
-                    info!("╔══════════════════════════════════════════════════════════════╗");
-                    info!("║                    🔄 REINDEX STARTED                        ║");
-                    info!("╠══════════════════════════════════════════════════════════════╣");
-                    info!("║  From Block: {:>15}                                ║", from);
-                    info!("║  To Block:   {:>15}                                ║", to);
-                    info!("║  Strategy:   {:?}", reindex_req.strategy.as_deref().unwrap_or("ALL"));
-                    info!("╚══════════════════════════════════════════════════════════════╝");
-                    for mut strat in strats {
-                        strat.force_reindex = true;
+                    if from <= to {
+                        info!("╔══════════════════════════════════════════════════════════════╗");
+                        info!("║                    🔄 REINDEX STARTED                        ║");
+                        info!("╠══════════════════════════════════════════════════════════════╣");
+                        info!("║  From Block: {:>15}                                ║", from);
+                        info!("║  To Block:   {:>15}                                ║", to);
+                        info!("║  Strategy:   {:?}", reindex_req.strategy.as_deref().unwrap_or("ALL"));
+                        info!("║  Source:     {}", if reindex_req.offline { "archive (offline)" } else { "rpc" });
+                        info!("╚══════════════════════════════════════════════════════════════╝");
+                        for mut strat in strats {
+                            strat.force_reindex = true;
                         
-                        // Clear the indexed range for this strategy to force full reindex
-                        if let Err(e) = sqlx::query("DELETE FROM indexed_ranges WHERE strategy_name = $1")
-                            .bind(strat.name)
-                            .execute(db)
-                            .await
-                        {
-                            warn!(strategy = strat.name, error = %e, "failed to clear indexed_ranges");
-                        } else {
-                            info!(strategy = strat.name, "cleared indexed_ranges for reindex");
-                        }
+                            // Clear the indexed range for this strategy to force full reindex
+                            if let Err(e) = sqlx::query("DELETE FROM indexed_ranges WHERE strategy_name = $1")
+                                .bind(strat.name)
+                                .execute(db)
+                                .await
+                            {
+                                warn!(strategy = strat.name, error = %e, "failed to clear indexed_ranges");
+                            } else {
+                                info!(strategy = strat.name, "cleared indexed_ranges for reindex");
+                            }
                         
-                        match run_indexer(provider.clone(), db, from, to, &config, vec![strat], Some(app.clone())).await {
-                            Ok(_) => {}
-                            Err(e) => error!("reindex error: {e}"),
+                            match run_indexer(source.clone(), db, from, to, config, vec![strat], Some(app.clone())).await {
+                                Ok(_) => {}
+                                Err(e) => error!("reindex error: {e}"),
+                            }
+                            // Check if interrupted (pause or another reindex)
+                            if app.should_interrupt().await { break; }
                         }
-                        // Check if interrupted (pause or another reindex)
-                        if app.should_interrupt().await { break; }
                     }
                 }
+            } else {
+                warn!("offline reindex needs archive.enabled, ignored");
             }
 
             // Clear reindex if completed (not interrupted)
@@ -302,24 +340,26 @@ where
 
         // Resume existing index if paused mid-way
         let existing_index = app.state.read().await.index.clone();
-        if let Some(idx) = existing_index {
-            if !idx.is_reindex && idx.current > 0 && idx.current < idx.to {
-                // Resume from where we left off
-                info!(from = idx.current, to = idx.to, "resuming indexing");
-                
-                match run_indexer(provider.clone(), db, idx.current, idx.to, &config, strategies.clone(), Some(app.clone())).await {
-                    Ok(processed) => {
-                        if !app.should_interrupt().await {
-                            last = processed;
-                            let mut s = app.state.write().await;
-                            s.last_block = last;
-                            s.index = None;
-                        }
+        if let Some(idx) = existing_index
+            && !idx.is_reindex
+            && idx.current > 0
+            && idx.current < idx.to
+        {
+            // Resume from where we left off
+            info!(from = idx.current, to = idx.to, "resuming indexing");
+            
+            match run_indexer(provider.clone(), db, idx.current, idx.to, config, strategies.clone(), Some(app.clone())).await {
+                Ok(report) => {
+                    if !app.should_interrupt().await {
+                        last = report.last;
+                        let mut s = app.state.write().await;
+                        s.last_block = last;
+                        s.index = None;
                     }
-                    Err(e) => error!("indexer error: {e}"),
                 }
-                continue;
+                Err(e) => error!("indexer error: {e}"),
             }
+            continue;
         }
 
         // Normal indexing: follow chain head
@@ -345,13 +385,14 @@ where
                     current: from,
                     strategy: None,
                     is_reindex: false,
+                    offline: false,
                 });
             }
 
-            match run_indexer(provider.clone(), db, from, safe, &config, strategies.clone(), Some(app.clone())).await {
-                Ok(processed) => {
+            match run_indexer(provider.clone(), db, from, safe, config, strategies.clone(), Some(app.clone())).await {
+                Ok(report) => {
                     if !app.should_interrupt().await {
-                        last = processed;
+                        last = report.last;
                         let mut s = app.state.write().await;
                         s.last_block = last;
                         s.index = None;
@@ -364,6 +405,6 @@ where
             app.state.write().await.index = None;
         }
 
-        tokio::time::sleep(std::time::Duration::from_secs(poll_secs)).await;
+        tokio::time::sleep(poll_interval).await;
     }
 }
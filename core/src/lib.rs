@@ -1,5 +1,21 @@
 pub mod api;
+pub mod archive;
+pub mod blocks;
+pub mod bundle;
+pub mod builder;
+pub mod cli;
+pub mod config;
 pub mod db;
+pub mod declarative;
+pub mod export;
+pub mod graphql;
 pub mod indexer;
+pub mod quarantine;
+pub mod query;
+pub mod replay;
 pub mod resilience;
+pub mod resolved;
 pub mod strategies;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
+pub mod watched;
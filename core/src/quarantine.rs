@@ -0,0 +1,77 @@
+use alloy::providers::Provider;
+use alloy::rpc::types::Log;
+use eyre::Result;
+use sqlx::PgPool;
+use tracing::warn;
+
+use crate::strategies::StrategyConfig;
+
+/// An error a strategy can classify, so quarantined rows can be grouped.
+pub trait Quarantinable: std::error::Error {
+    /// Short stable identifier, e.g. `missing_receipt`.
+    fn kind(&self) -> &'static str;
+}
+
+/// Records a log that could not be decoded and moves on, instead of failing
+/// the whole chunk. Seeing the same log again refreshes the error.
+pub async fn quarantine<E: Quarantinable>(db: &PgPool, strategy: &str, log: &Log, err: &E) -> Result<()> {
+    let tx_hash = log.transaction_hash.map(|h| format!("{h:#x}")).unwrap_or_default();
+    warn!(strategy, tx = %tx_hash, kind = err.kind(), error = %err, "log quarantined");
+
+    sqlx::query(
+        "INSERT INTO quarantined_logs (strategy_name, tx_hash, log_index, block_number, error_kind, error)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         ON CONFLICT (strategy_name, tx_hash, log_index) DO UPDATE
+         SET error_kind = EXCLUDED.error_kind, error = EXCLUDED.error, last_seen = NOW()",
+    )
+    .bind(strategy)
+    .bind(tx_hash)
+    .bind(log.log_index.unwrap_or_default() as i64)
+    .bind(log.block_number.unwrap_or_default() as i64)
+    .bind(err.kind())
+    .bind(err.to_string())
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Forgets quarantined logs of `strategy` in `from..=to`. Strategies call it
+/// on each chunk before quarantining its logs, so what is processed cleanly
+/// again (e.g. after a fix and a reindex) leaves the quarantine.
+pub async fn release(db: &PgPool, strategy: &str, from: u64, to: u64) -> Result<u64> {
+    let res = sqlx::query(
+        "DELETE FROM quarantined_logs WHERE strategy_name = $1 AND block_number BETWEEN $2 AND $3",
+    )
+    .bind(strategy)
+    .bind(from as i64)
+    .bind(to as i64)
+    .execute(db)
+    .await?;
+    Ok(res.rows_affected())
+}
+
+/// Processes again each block holding quarantined logs of `strategy`, e.g.
+/// after a fix or once a missing receipt can be fetched. Strategies release
+/// the blocks they process, so logs that now go through leave the quarantine
+/// and the others are quarantined again. Returns the number of blocks
+/// retried and of logs still quarantined.
+pub async fn retry<P>(provider: P, db: &PgPool, strategy: &StrategyConfig<P>) -> Result<(usize, i64)>
+where
+    P: Provider + Clone + Send + Sync + 'static,
+{
+    let blocks: Vec<i64> = sqlx::query_scalar(
+        "SELECT DISTINCT block_number FROM quarantined_logs WHERE strategy_name = $1 ORDER BY block_number",
+    )
+    .bind(strategy.name)
+    .fetch_all(db)
+    .await?;
+    for &block in &blocks {
+        strategy.processor.process(provider.clone(), db, block as u64, block as u64).await?;
+    }
+
+    let left: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM quarantined_logs WHERE strategy_name = $1")
+        .bind(strategy.name)
+        .fetch_one(db)
+        .await?;
+    Ok((blocks.len(), left))
+}
@@ -0,0 +1,598 @@
+//! Read-only query endpoints over the indexed tables, mounted under `/api`.
+//!
+//! Lists are paginated with an opaque cursor (keyset pagination on the sort
+//! column plus a unique key), so pages stay stable while the indexer writes.
+//! Each page answers `{ "items": [...], "next_cursor": "..." }`; the last page
+//! has `next_cursor: null`. `NUMERIC` amounts are returned as strings.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::get,
+};
+use eyre::{Result, eyre};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use sqlx::types::BigDecimal;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+use crate::api::{App, Resp};
+
+/// Profile tables the query endpoints read, by `[tables]` key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryTables {
+    /// `accounts` (`super_accounts`, `users`).
+    pub accounts: String,
+    /// `badge_claims`.
+    pub badge_claims: String,
+    /// `account_badges`.
+    pub account_badges: String,
+}
+
+impl Default for QueryTables {
+    fn default() -> Self {
+        Self {
+            accounts: "super_accounts".into(),
+            badge_claims: "badge_claims".into(),
+            account_badges: "account_badges".into(),
+        }
+    }
+}
+
+impl QueryTables {
+    /// Takes the `accounts`, `badge_claims` and `account_badges` keys of a
+    /// profile's `[tables]`, keeping the defaults for missing ones.
+    pub fn from_tables(tables: &BTreeMap<String, String>) -> Self {
+        let d = Self::default();
+        let pick = |key: &str, default: String| tables.get(key).cloned().unwrap_or(default);
+        Self {
+            accounts: pick("accounts", d.accounts),
+            badge_claims: pick("badge_claims", d.badge_claims),
+            account_badges: pick("account_badges", d.account_badges),
+        }
+    }
+}
+
+/// Vault tables are the same for every profile.
+const VAULT_BALANCES_TABLE: &str = "vault_balances";
+const VAULTS_TRANSACTIONS_TABLE: &str = "vaults_transactions";
+
+/// Rows per page unless `limit` says otherwise, and the most it may say.
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 500;
+
+pub(crate) type Failure = (StatusCode, Json<Resp>);
+
+fn fail(code: StatusCode, msg: impl Into<String>) -> Failure {
+    (code, Json(Resp { ok: false, msg: msg.into() }))
+}
+
+pub(crate) fn internal(e: impl std::fmt::Display) -> Failure {
+    tracing::warn!("query failed: {e}");
+    fail(StatusCode::INTERNAL_SERVER_ERROR, "query failed")
+}
+
+// ============================================================================
+// Pagination
+// ============================================================================
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Order {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// `limit`, `cursor`, `sort` and `order`, shared by every list (read from
+/// the same query string as its filters).
+#[derive(Debug, Default, Deserialize)]
+pub struct PageParams {
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub order: Order,
+}
+
+#[derive(Serialize)]
+pub struct Page {
+    pub items: Vec<Box<RawValue>>,
+    pub next_cursor: Option<String>,
+}
+
+/// Items of a page with the cursor resuming after each, and whether more follow.
+pub(crate) struct Rows {
+    pub items: Vec<(Box<RawValue>, String)>,
+    pub has_next: bool,
+}
+
+/// A column a list can be sorted by: its name in `sort=`, the SQL expression
+/// (never NULL, so rows compare) and the type to cast cursor values to.
+pub(crate) struct SortKey {
+    pub name: &'static str,
+    pub expr: &'static str,
+    pub ty: &'static str,
+}
+
+/// A paginated list over one table (aliased `t`).
+pub(crate) struct Listing<'a> {
+    pub table: &'a str,
+    /// Select list of each item; `NUMERIC` columns cast to text.
+    pub columns: &'static str,
+    /// Sortable columns, the first being the default.
+    pub sorts: &'static [SortKey],
+    /// Unique within the filtered rows, breaks ties: `(expr, type)`.
+    pub key: &'static [(&'static str, &'static str)],
+}
+
+impl<'a> Listing<'a> {
+    /// Current badges of one account, keyed by badge.
+    pub(crate) fn account_badges(tables: &'a QueryTables) -> Self {
+        const SORTS: &[SortKey] = &[
+            SortKey { name: "badge_id", expr: "t.badge_id", ty: "int" },
+            SortKey { name: "tier", expr: "COALESCE(t.tier, 0)", ty: "int" },
+            SortKey { name: "points", expr: "COALESCE(t.points, 0)", ty: "int" },
+            SortKey { name: "block", expr: "t.last_block_number", ty: "int" },
+        ];
+        Listing { table: &tables.account_badges, columns: "t.*", sorts: SORTS, key: &[("t.badge_id", "int")] }
+    }
+
+    /// Holders of one badge, keyed by account.
+    pub(crate) fn badge_holders(tables: &'a QueryTables) -> Self {
+        const SORTS: &[SortKey] = &[
+            SortKey { name: "account", expr: "t.account", ty: "text" },
+            SortKey { name: "tier", expr: "COALESCE(t.tier, 0)", ty: "int" },
+            SortKey { name: "points", expr: "COALESCE(t.points, 0)", ty: "int" },
+            SortKey { name: "block", expr: "t.last_block_number", ty: "int" },
+        ];
+        Listing { table: &tables.account_badges, columns: "t.*", sorts: SORTS, key: &[("t.account", "text")] }
+    }
+
+    /// Claims (tier by tier) of `badge_claims`.
+    pub(crate) fn badge_claims(tables: &'a QueryTables) -> Self {
+        const SORTS: &[SortKey] = &[SortKey { name: "block", expr: "t.block_number", ty: "int" }];
+        Listing {
+            table: &tables.badge_claims,
+            columns: "t.*",
+            sorts: SORTS,
+            key: &[("t.badge_id", "int"), ("COALESCE(t.tier, 0)", "int"), ("t.account", "text")],
+        }
+    }
+
+    /// Balances of one account, keyed by token.
+    pub(crate) fn vault_balances() -> Self {
+        const SORTS: &[SortKey] = &[
+            SortKey { name: "token", expr: "t.token", ty: "text" },
+            SortKey { name: "balance", expr: "t.balance", ty: "numeric" },
+            SortKey { name: "block", expr: "t.last_block", ty: "bigint" },
+        ];
+        Listing {
+            table: VAULT_BALANCES_TABLE,
+            columns: "t.account, t.token, t.balance::text AS balance, t.deposited::text AS deposited, \
+                      t.withdrawn::text AS withdrawn, t.last_block",
+            sorts: SORTS,
+            key: &[("t.token", "text")],
+        }
+    }
+
+    /// Vault flows, keyed by id.
+    pub(crate) fn vault_transactions() -> Self {
+        const SORTS: &[SortKey] = &[
+            SortKey { name: "block", expr: "t.tx_block", ty: "bigint" },
+            SortKey { name: "amount", expr: "t.amount", ty: "numeric" },
+        ];
+        Listing {
+            table: VAULTS_TRANSACTIONS_TABLE,
+            columns: "t.id, t.account, t.token, t.amount::text AS amount, t.direction, t.event_type, \
+                      t.tx_hash, t.tx_block, t.block_time",
+            sorts: SORTS,
+            key: &[("t.id", "bigint")],
+        }
+    }
+
+    /// Runs the list with the filters `filter` appends (`AND ...` clauses).
+    async fn page<F>(&self, db: &PgPool, params: &PageParams, filter: F) -> Result<Page, Failure>
+    where
+        F: FnOnce(&mut QueryBuilder<'_, Postgres>),
+    {
+        let rows = self.rows(db, params, filter).await?;
+        let next_cursor = if rows.has_next { rows.items.last().map(|(_, c)| c.clone()) } else { None };
+        Ok(Page { items: rows.items.into_iter().map(|(item, _)| item).collect(), next_cursor })
+    }
+
+    /// Like [`Listing::page`], with a cursor per item.
+    pub(crate) async fn rows<F>(&self, db: &PgPool, params: &PageParams, filter: F) -> Result<Rows, Failure>
+    where
+        F: FnOnce(&mut QueryBuilder<'_, Postgres>),
+    {
+        let plan = self.plan(params)?;
+        let mut qb = QueryBuilder::new("");
+        self.push_query(&mut qb, &plan, filter);
+        let rows: Vec<(String, Vec<String>)> = qb.build_query_as().fetch_all(db).await.map_err(internal)?;
+        plan.finish(rows)
+    }
+
+    /// [`Listing::rows`] of each of `owners`, in one query: every owner's
+    /// page is a lateral subquery over the rows whose `columns` (`(expr,
+    /// type)`, e.g. the account, or the account and badge) equal its values.
+    /// Owners without rows are left out.
+    pub(crate) async fn rows_by_owner<F>(
+        &self,
+        db: &PgPool,
+        columns: &[(&str, &str)],
+        owners: &[Vec<String>],
+        params: &PageParams,
+        filter: F,
+    ) -> Result<HashMap<Vec<String>, Rows>, Failure>
+    where
+        F: FnOnce(&mut QueryBuilder<'_, Postgres>),
+    {
+        let plan = self.plan(params)?;
+        let names: Vec<String> = (0..columns.len()).map(|i| format!("k{i}")).collect();
+        let refs: Vec<String> = names.iter().map(|n| format!("a.{n}")).collect();
+        let mut qb = QueryBuilder::new(format!("SELECT ARRAY[{}], p.item, p.sort_values FROM UNNEST(", refs.join(", ")));
+        let mut sep = qb.separated(", ");
+        for i in 0..columns.len() {
+            let values: Vec<String> = owners.iter().map(|o| o[i].clone()).collect();
+            sep.push_bind(values).push_unseparated("::text[]");
+        }
+        qb.push(format!(") AS a({}) CROSS JOIN LATERAL (", names.join(", ")));
+        self.push_query(&mut qb, &plan, |qb| {
+            for ((expr, ty), name) in columns.iter().zip(&refs) {
+                qb.push(format!(" AND {expr} = {name}::{ty}"));
+            }
+            filter(qb);
+        });
+        qb.push(") AS p");
+        let rows: Vec<(Vec<String>, String, Vec<String>)> =
+            qb.build_query_as().fetch_all(db).await.map_err(internal)?;
+
+        // Each owner's rows come out together, in the page's order
+        let mut grouped: HashMap<Vec<String>, Vec<(String, Vec<String>)>> = HashMap::new();
+        for (owner, item, values) in rows {
+            grouped.entry(owner).or_default().push((item, values));
+        }
+        grouped.into_iter().map(|(owner, rows)| Ok((owner, plan.finish(rows)?))).collect()
+    }
+
+    /// Sort, cursor and limit of `params` against this listing.
+    fn plan(&self, params: &PageParams) -> Result<Plan, Failure> {
+        let limit = page_limit(params.limit);
+        let sort = match &params.sort {
+            None => &self.sorts[0],
+            Some(name) => self.sorts.iter().find(|s| s.name == name).ok_or_else(|| {
+                let known: Vec<_> = self.sorts.iter().map(|s| s.name).collect();
+                fail(StatusCode::BAD_REQUEST, format!("unknown sort '{name}' (one of: {})", known.join(", ")))
+            })?,
+        };
+        let order: Vec<(&'static str, &'static str)> =
+            std::iter::once((sort.expr, sort.ty)).chain(self.key.iter().copied()).collect();
+        // A cursor only resumes the sort it was issued for
+        let tag = format!("{} {:?}", sort.name, params.order);
+        let cursor = match &params.cursor {
+            None => None,
+            Some(c) => {
+                let types: Vec<&str> = order.iter().map(|(_, ty)| *ty).collect();
+                Some(decode_cursor(c, &tag, &types).map_err(|e| fail(StatusCode::BAD_REQUEST, e.to_string()))?)
+            }
+        };
+        Ok(Plan { limit, order, direction: params.order, tag, cursor })
+    }
+
+    /// Appends the page's `SELECT item, sort_values`, with the filters
+    /// `filter` appends.
+    fn push_query<F>(&self, qb: &mut QueryBuilder<'_, Postgres>, plan: &Plan, filter: F)
+    where
+        F: FnOnce(&mut QueryBuilder<'_, Postgres>),
+    {
+        let exprs: Vec<String> = plan.order.iter().map(|(e, _)| format!("({e})::text")).collect();
+        qb.push(format!(
+            "SELECT row_to_json(x)::text AS item, ARRAY[{}] AS sort_values FROM {} AS t \
+             CROSS JOIN LATERAL (SELECT {}) AS x WHERE TRUE",
+            exprs.join(", "),
+            self.table,
+            self.columns
+        ));
+        filter(qb);
+        let (cmp, dir) = match plan.direction {
+            Order::Asc => (">", "ASC"),
+            Order::Desc => ("<", "DESC"),
+        };
+        if let Some(values) = &plan.cursor {
+            let lhs: Vec<&str> = plan.order.iter().map(|(e, _)| *e).collect();
+            qb.push(format!(" AND ({}) {cmp} (", lhs.join(", ")));
+            let mut sep = qb.separated(", ");
+            for ((_, ty), value) in plan.order.iter().zip(values) {
+                sep.push_bind(value.clone()).push_unseparated(format!("::{ty}"));
+            }
+            qb.push(")");
+        }
+        let by: Vec<String> = plan.order.iter().map(|(e, _)| format!("{e} {dir}")).collect();
+        qb.push(format!(" ORDER BY {} LIMIT ", by.join(", "))).push_bind(plan.limit as i64 + 1);
+    }
+}
+
+/// How a [`Listing`] is read for one [`PageParams`].
+struct Plan {
+    limit: u32,
+    /// Sort expression then key columns: `(expr, type)`.
+    order: Vec<(&'static str, &'static str)>,
+    direction: Order,
+    tag: String,
+    cursor: Option<Vec<String>>,
+}
+
+impl Plan {
+    /// Rows fetched (one more than the limit, if there are) as a page.
+    fn finish(&self, mut rows: Vec<(String, Vec<String>)>) -> Result<Rows, Failure> {
+        let has_next = rows.len() > self.limit as usize;
+        rows.truncate(self.limit as usize);
+        let items = rows
+            .into_iter()
+            .map(|(json, values)| Ok((RawValue::from_string(json)?, encode_cursor(&self.tag, &values))))
+            .collect::<Result<_, serde_json::Error>>()
+            .map_err(internal)?;
+        Ok(Rows { items, has_next })
+    }
+}
+
+/// Rows a page holds when asked for `limit`.
+pub(crate) fn page_limit(limit: Option<u32>) -> u32 {
+    limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+}
+
+/// Hex of `[tag, values...]`, the last row's sort and key values.
+fn encode_cursor(tag: &str, values: &[String]) -> String {
+    let all: Vec<&str> = std::iter::once(tag).chain(values.iter().map(String::as_str)).collect();
+    alloy::hex::encode(serde_json::to_vec(&all).unwrap_or_default())
+}
+
+/// Values of a cursor issued for `tag`, each checked against the SQL type of
+/// its column in `types`, so a tampered one is refused before the cast.
+fn decode_cursor(cursor: &str, tag: &str, types: &[&str]) -> Result<Vec<String>> {
+    let raw = alloy::hex::decode(cursor).map_err(|_| eyre!("invalid cursor"))?;
+    let mut values: Vec<String> = serde_json::from_slice(&raw).map_err(|_| eyre!("invalid cursor"))?;
+    if values.len() != types.len() + 1 || values[0] != tag {
+        return Err(eyre!("cursor does not match this sort and order"));
+    }
+    values.remove(0);
+    for (value, ty) in values.iter().zip(types) {
+        let valid = match *ty {
+            "int" => value.parse::<i32>().is_ok(),
+            "bigint" => value.parse::<i64>().is_ok(),
+            "numeric" => value.parse::<BigDecimal>().is_ok(),
+            _ => true,
+        };
+        if !valid {
+            return Err(eyre!("invalid cursor"));
+        }
+    }
+    Ok(values)
+}
+
+/// Lowercased `0x` address from a path or query parameter.
+pub(crate) fn address(raw: &str) -> Result<String, Failure> {
+    raw.parse::<alloy::primitives::Address>()
+        .map(|a| format!("{a:#x}"))
+        .map_err(|_| fail(StatusCode::BAD_REQUEST, format!("invalid address '{raw}'")))
+}
+
+pub(crate) fn db(app: &App) -> Result<PgPool, Failure> {
+    app.db().cloned().ok_or_else(|| fail(StatusCode::SERVICE_UNAVAILABLE, "no database attached"))
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// `GET /accounts/{address}`: the account row of the profile's table.
+async fn account(State(app): State<Arc<App>>, Path(raw): Path<String>) -> Result<Json<Box<RawValue>>, Failure> {
+    let db = db(&app)?;
+    let account = address(&raw)?;
+    let row: Option<String> =
+        sqlx::query_scalar(&format!("SELECT row_to_json(t)::text FROM {} AS t WHERE account = $1", app.tables().accounts))
+            .bind(&account)
+            .fetch_optional(&db)
+            .await
+            .map_err(internal)?;
+    let row = row.ok_or_else(|| fail(StatusCode::NOT_FOUND, format!("account {account} not found")))?;
+    Ok(Json(RawValue::from_string(row).map_err(internal)?))
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Hash)]
+pub(crate) struct BadgeFilter {
+    pub badge_id: Option<i32>,
+    pub tier: Option<i32>,
+    pub min_tier: Option<i32>,
+}
+
+impl BadgeFilter {
+    pub(crate) fn push(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        if let Some(id) = self.badge_id {
+            qb.push(" AND t.badge_id = ").push_bind(id);
+        }
+        if let Some(tier) = self.tier {
+            qb.push(" AND t.tier = ").push_bind(tier);
+        }
+        if let Some(tier) = self.min_tier {
+            qb.push(" AND t.tier >= ").push_bind(tier);
+        }
+    }
+}
+
+/// `GET /accounts/{address}/badges`: current tier of each badge the account holds.
+async fn account_badges(
+    State(app): State<Arc<App>>,
+    Path(raw): Path<String>,
+    Query(f): Query<BadgeFilter>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page>, Failure> {
+    let db = db(&app)?;
+    let account = address(&raw)?;
+    let page = Listing::account_badges(app.tables())
+        .page(&db, &page, |qb| {
+            qb.push(" AND t.account = ").push_bind(account);
+            f.push(qb);
+        })
+        .await?;
+    Ok(Json(page))
+}
+
+/// `GET /badges/{id}/holders`: accounts holding a badge, with their tier.
+async fn badge_holders(
+    State(app): State<Arc<App>>,
+    Path(id): Path<i32>,
+    Query(f): Query<BadgeFilter>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page>, Failure> {
+    let db = db(&app)?;
+    let f = BadgeFilter { badge_id: Some(id), ..f };
+    let page = Listing::badge_holders(app.tables()).page(&db, &page, |qb| f.push(qb)).await?;
+    Ok(Json(page))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct VaultFilter {
+    token: Option<String>,
+}
+
+/// `GET /accounts/{address}/vaults`: the account's balance in each vault token.
+async fn account_vaults(
+    State(app): State<Arc<App>>,
+    Path(raw): Path<String>,
+    Query(f): Query<VaultFilter>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page>, Failure> {
+    let db = db(&app)?;
+    let account = address(&raw)?;
+    let token = f.token.as_deref().map(address).transpose()?;
+    let page = Listing::vault_balances()
+        .page(&db, &page, |qb| {
+            qb.push(" AND t.account = ").push_bind(account);
+            if let Some(token) = token {
+                qb.push(" AND t.token = ").push_bind(token);
+            }
+        })
+        .await?;
+    Ok(Json(page))
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Hash)]
+pub(crate) struct TransactionFilter {
+    pub account: Option<String>,
+    pub token: Option<String>,
+    pub direction: Option<String>,
+    pub event_type: Option<String>,
+    pub from_block: Option<u64>,
+    pub to_block: Option<u64>,
+}
+
+impl TransactionFilter {
+    /// Checks and lowercases the addresses.
+    pub(crate) fn normalized(self) -> Result<Self, Failure> {
+        Ok(Self {
+            account: self.account.as_deref().map(address).transpose()?,
+            token: self.token.as_deref().map(address).transpose()?,
+            ..self
+        })
+    }
+
+    pub(crate) fn push(&self, qb: &mut QueryBuilder<'_, Postgres>) {
+        if let Some(account) = &self.account {
+            qb.push(" AND t.account = ").push_bind(account.clone());
+        }
+        if let Some(token) = &self.token {
+            qb.push(" AND t.token = ").push_bind(token.clone());
+        }
+        if let Some(direction) = &self.direction {
+            qb.push(" AND t.direction = ").push_bind(direction.clone());
+        }
+        if let Some(kind) = &self.event_type {
+            qb.push(" AND t.event_type = ").push_bind(kind.clone());
+        }
+        if let Some(from) = self.from_block {
+            qb.push(" AND t.tx_block >= ").push_bind(from as i64);
+        }
+        if let Some(to) = self.to_block {
+            qb.push(" AND t.tx_block <= ").push_bind(to as i64);
+        }
+    }
+}
+
+/// `GET /vaults/transactions`: vault flows of every account.
+async fn vault_transactions(
+    State(app): State<Arc<App>>,
+    Query(f): Query<TransactionFilter>,
+    Query(page): Query<PageParams>,
+) -> Result<Json<Page>, Failure> {
+    let db = db(&app)?;
+    let f = f.normalized()?;
+    let page = Listing::vault_transactions().page(&db, &page, |qb| f.push(qb)).await?;
+    Ok(Json(page))
+}
+
+/// The query routes, to nest under `/api` with the control ones.
+pub fn routes() -> Router<Arc<App>> {
+    Router::new()
+        .route("/accounts/{address}", get(account))
+        .route("/accounts/{address}/badges", get(account_badges))
+        .route("/accounts/{address}/vaults", get(account_vaults))
+        .route("/badges/{id}/holders", get(badge_holders))
+        .route("/vaults/transactions", get(vault_transactions))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(cursor: Option<String>, sort: Option<&str>, order: Order) -> PageParams {
+        PageParams { limit: Some(2), cursor, sort: sort.map(str::to_string), order }
+    }
+
+    #[test]
+    fn cursor_round_trip() {
+        let values = vec!["12".to_string(), "a \"quoted\", value".to_string()];
+        let cursor = encode_cursor("block Asc", &values);
+        assert!(cursor.bytes().all(|b| b.is_ascii_hexdigit()));
+        let types = ["int", "text"];
+        assert_eq!(decode_cursor(&cursor, "block Asc", &types).unwrap(), values);
+
+        assert_eq!(decode_cursor(&cursor, "block Desc", &types).unwrap_err().to_string(), "cursor does not match this sort and order");
+        assert_eq!(decode_cursor(&cursor, "block Asc", &["int", "text", "int"]).unwrap_err().to_string(), "cursor does not match this sort and order");
+        for bad in ["zz", "", "7b7d", &cursor[..cursor.len() - 2]] {
+            assert_eq!(decode_cursor(bad, "block Asc", &types).unwrap_err().to_string(), "invalid cursor", "{bad:?}");
+        }
+        // Values that would not cast to their column
+        for (tampered, ty) in [("1e3", "int"), ("99999999999", "int"), ("x", "bigint"), ("1;", "numeric")] {
+            let cursor = encode_cursor("block Asc", &[tampered.to_string()]);
+            assert_eq!(decode_cursor(&cursor, "block Asc", &[ty]).unwrap_err().to_string(), "invalid cursor", "{tampered:?}");
+        }
+    }
+
+    #[test]
+    fn plan_resumes_only_its_own_sort() {
+        let listing = Listing::vault_transactions();
+        let plan = listing.plan(&params(None, Some("amount"), Order::Desc)).unwrap();
+        assert_eq!(plan.order, [("t.amount", "numeric"), ("t.id", "bigint")]);
+        let rows = vec![
+            ("{}".to_string(), vec!["5".to_string(), "1".to_string()]),
+            ("{}".to_string(), vec!["3".to_string(), "2".to_string()]),
+            ("{}".to_string(), vec!["1".to_string(), "3".to_string()]),
+        ];
+        let page = plan.finish(rows).unwrap();
+        assert!(page.has_next);
+        let cursor = page.items.last().unwrap().1.clone();
+
+        let resumed = listing.plan(&params(Some(cursor.clone()), Some("amount"), Order::Desc)).unwrap();
+        assert_eq!(resumed.cursor.unwrap(), ["3", "2"]);
+        for (sort, order) in [(Some("amount"), Order::Asc), (None, Order::Desc), (Some("block"), Order::Desc)] {
+            let err = listing.plan(&params(Some(cursor.clone()), sort, order)).err().unwrap();
+            assert_eq!(err.0, StatusCode::BAD_REQUEST);
+        }
+    }
+}
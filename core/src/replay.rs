@@ -0,0 +1,212 @@
+//! Offline replay: a provider whose RPC calls are answered from the archive
+//! (`raw_logs`, `block_cache`, `receipt_cache`), with no network access.
+//!
+//! Strategies run unchanged on top of it, chunk by chunk in block order, so
+//! a reindex rebuilds their projections from what [`crate::archive`] stored.
+//! `eth_getLogs` returns the archived logs matching the filter, so a range is
+//! only as complete as the archive was when it was fetched. Calls the archive
+//! cannot answer (e.g. `eth_call`) fail with "method not found"; strategies
+//! keep what they read from contracts in [`crate::resolved`] instead.
+
+use std::task::{Context, Poll};
+
+use alloy::consensus;
+use alloy::eips::BlockNumberOrTag;
+use alloy::network::Ethereum;
+use alloy::primitives::B256;
+use alloy::providers::{DynProvider, Provider, RootProvider};
+use alloy::rpc::client::RpcClient;
+use alloy::rpc::json_rpc::{ErrorPayload, RequestPacket, Response, ResponsePacket, ResponsePayload, SerializedRequest};
+use alloy::rpc::types::{Block, BlockTransactions, Filter, FilterBlockOption, Header, Log};
+use alloy::transports::{TransportError, TransportErrorKind, TransportFut};
+use eyre::{Result, WrapErr, eyre};
+use serde::Serialize;
+use serde_json::Value;
+use serde_json::value::{RawValue, to_raw_value};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use tower::Service;
+
+use crate::archive::{BLOCK_CACHE_TABLE, RAW_LOG_COLUMNS, RAW_LOGS_TABLE, RECEIPT_CACHE_TABLE, RawLogRow, log_from_row};
+
+/// Provider replaying the archive of `chain_id`.
+pub fn provider(db: PgPool, chain_id: u64) -> DynProvider {
+    let client = RpcClient::new(ReplayTransport::new(db, chain_id), true);
+    RootProvider::<Ethereum>::new(client).erased()
+}
+
+/// First and last block `raw_logs` or `block_cache` hold for `chain_id`;
+/// `None` when nothing is archived.
+pub async fn archived_range(db: &PgPool, chain_id: u64) -> Result<Option<(u64, u64)>> {
+    let (first, last): (Option<i64>, Option<i64>) = sqlx::query_as(&format!(
+        "SELECT LEAST(
+                    (SELECT MIN(block_number) FROM {RAW_LOGS_TABLE} WHERE chain_id = $1),
+                    (SELECT MIN(block_number) FROM {BLOCK_CACHE_TABLE} WHERE chain_id = $1)
+                ),
+                GREATEST(
+                    (SELECT MAX(block_number) FROM {RAW_LOGS_TABLE} WHERE chain_id = $1),
+                    (SELECT MAX(block_number) FROM {BLOCK_CACHE_TABLE} WHERE chain_id = $1)
+                )"
+    ))
+    .bind(chain_id as i64)
+    .fetch_one(db)
+    .await?;
+    Ok(first.zip(last).map(|(f, l)| (f as u64, l as u64)))
+}
+
+/// Transport answering `eth_chainId`, `eth_blockNumber`, `eth_getLogs`,
+/// `eth_getBlockByNumber` and `eth_getTransactionReceipt` from the database.
+#[derive(Clone, Debug)]
+pub struct ReplayTransport {
+    db: PgPool,
+    chain_id: u64,
+}
+
+type Payload = ResponsePayload<Box<RawValue>, Box<RawValue>>;
+
+impl ReplayTransport {
+    pub fn new(db: PgPool, chain_id: u64) -> Self {
+        Self { db, chain_id }
+    }
+
+    async fn answer(&self, req: &SerializedRequest) -> Result<Payload> {
+        let params: Value = match req.params() {
+            Some(p) => serde_json::from_str(p.get())?,
+            None => Value::Array(Vec::new()),
+        };
+        match req.method() {
+            "eth_chainId" => success(&format!("{:#x}", self.chain_id)),
+            "eth_blockNumber" => success(&format!("{:#x}", self.last_block().await?)),
+            "eth_getLogs" => {
+                let filter: Filter = serde_json::from_value(params[0].clone()).wrap_err("eth_getLogs filter")?;
+                success(&self.logs(&filter).await?)
+            }
+            "eth_getBlockByNumber" => {
+                let tag: BlockNumberOrTag = serde_json::from_value(params[0].clone()).wrap_err("block number")?;
+                let number = match tag {
+                    BlockNumberOrTag::Number(n) => n,
+                    BlockNumberOrTag::Earliest => 0,
+                    _ => self.last_block().await?,
+                };
+                success(&self.block(number).await?)
+            }
+            "eth_getTransactionReceipt" => {
+                let hash = params[0].as_str().ok_or_else(|| eyre!("receipt without tx hash"))?.to_lowercase();
+                let receipt: Option<Value> = sqlx::query_scalar(&format!(
+                    "SELECT receipt FROM {RECEIPT_CACHE_TABLE} WHERE chain_id = $1 AND tx_hash = $2"
+                ))
+                .bind(self.chain_id as i64)
+                .bind(hash)
+                .fetch_optional(&self.db)
+                .await?;
+                success(&receipt)
+            }
+            other => Ok(ResponsePayload::Failure(ErrorPayload {
+                code: -32601,
+                message: format!("offline replay: {other} is not archived").into(),
+                data: None,
+            })),
+        }
+    }
+
+    /// Highest archived block.
+    async fn last_block(&self) -> Result<u64> {
+        let last: Option<i64> = sqlx::query_scalar(&format!(
+            "SELECT GREATEST(
+                 (SELECT MAX(block_number) FROM {RAW_LOGS_TABLE} WHERE chain_id = $1),
+                 (SELECT MAX(block_number) FROM {BLOCK_CACHE_TABLE} WHERE chain_id = $1)
+             )"
+        ))
+        .bind(self.chain_id as i64)
+        .fetch_one(&self.db)
+        .await?;
+        Ok(last.unwrap_or(0) as u64)
+    }
+
+    async fn logs(&self, filter: &Filter) -> Result<Vec<Log>> {
+        let mut qb = QueryBuilder::<Postgres>::new(format!("SELECT {RAW_LOG_COLUMNS} FROM {RAW_LOGS_TABLE} WHERE chain_id = "));
+        qb.push_bind(self.chain_id as i64);
+        match &filter.block_option {
+            FilterBlockOption::AtBlockHash(hash) => {
+                qb.push(" AND block_hash = ").push_bind(format!("{hash:#x}"));
+            }
+            FilterBlockOption::Range { from_block, to_block } => {
+                let from = from_block.and_then(|b| b.as_number()).unwrap_or(0);
+                let to = to_block.and_then(|b| b.as_number()).unwrap_or(i64::MAX as u64);
+                qb.push(" AND block_number BETWEEN ").push_bind(from as i64);
+                qb.push(" AND ").push_bind(to as i64);
+            }
+        }
+        if !filter.address.is_empty() {
+            let addresses: Vec<String> = filter.address.iter().map(|a| format!("{a:#x}")).collect();
+            qb.push(" AND address = ANY(").push_bind(addresses).push(")");
+        }
+        for (i, topic) in filter.topics.iter().enumerate() {
+            if topic.is_empty() {
+                continue;
+            }
+            let values: Vec<String> = topic.iter().map(|t: &B256| format!("{t:#x}")).collect();
+            qb.push(format!(" AND topics[{}] = ANY(", i + 1)).push_bind(values).push(")");
+        }
+        qb.push(" ORDER BY block_number, log_index");
+
+        let rows: Vec<RawLogRow> = qb.build_query_as().fetch_all(&self.db).await?;
+        rows.into_iter().map(log_from_row).collect()
+    }
+
+    /// The cached block as a header-only block (number, hash and timestamp).
+    async fn block(&self, number: u64) -> Result<Option<Block>> {
+        let row: Option<(String, i64)> = sqlx::query_as(&format!(
+            "SELECT block_hash, timestamp FROM {BLOCK_CACHE_TABLE} WHERE chain_id = $1 AND block_number = $2"
+        ))
+        .bind(self.chain_id as i64)
+        .bind(number as i64)
+        .fetch_optional(&self.db)
+        .await?;
+        let Some((hash, timestamp)) = row else {
+            return Ok(None);
+        };
+        Ok(Some(Block {
+            header: Header {
+                hash: hash.parse()?,
+                inner: consensus::Header { number, timestamp: timestamp as u64, ..Default::default() },
+                total_difficulty: None,
+                size: None,
+            },
+            uncles: Vec::new(),
+            transactions: BlockTransactions::Hashes(Vec::new()),
+            withdrawals: None,
+        }))
+    }
+}
+
+fn success<T: Serialize>(value: &T) -> Result<Payload> {
+    Ok(ResponsePayload::Success(to_raw_value(value)?))
+}
+
+impl Service<RequestPacket> for ReplayTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: RequestPacket) -> Self::Future {
+        let this = self.clone();
+        Box::pin(async move {
+            let mut responses = Vec::with_capacity(req.len());
+            for r in req.requests() {
+                let payload = this
+                    .answer(r)
+                    .await
+                    .map_err(|e| TransportErrorKind::custom_str(&format!("offline replay: {e}")))?;
+                responses.push(Response { id: r.id().clone(), payload });
+            }
+            Ok(match req {
+                RequestPacket::Single(_) => ResponsePacket::Single(responses.remove(0)),
+                RequestPacket::Batch(_) => ResponsePacket::Batch(responses),
+            })
+        })
+    }
+}
@@ -0,0 +1,74 @@
+//! Tokens read once from a contract (`baseToken()`, `stakedCelo()`, ...) and
+//! kept in `resolved_tokens`, so later runs, rollbacks and offline replays
+//! don't need the `eth_call`.
+
+use std::future::Future;
+
+use alloy::primitives::Address;
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, QueryBuilder};
+
+pub const RESOLVED_TOKENS_TABLE: &str = "resolved_tokens";
+
+/// A row of `resolved_tokens`, addresses lowercased.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ResolvedToken {
+    pub contract: String,
+    pub getter: String,
+    pub token: String,
+}
+
+/// The token `getter` of `contract` returns: the stored one, or `fetch`'s
+/// answer, stored for next time.
+pub async fn resolve<F, Fut>(db: &PgPool, contract: Address, getter: &str, fetch: F) -> Result<Address>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Address>>,
+{
+    let contract = format!("{contract:#x}");
+    let stored: Option<String> = sqlx::query_scalar(&format!(
+        "SELECT token FROM {RESOLVED_TOKENS_TABLE} WHERE contract = $1 AND getter = $2"
+    ))
+    .bind(&contract)
+    .bind(getter)
+    .fetch_optional(db)
+    .await?;
+    if let Some(token) = stored {
+        return Ok(token.parse()?);
+    }
+
+    let token = fetch().await?;
+    sqlx::query(&format!(
+        "INSERT INTO {RESOLVED_TOKENS_TABLE} (contract, getter, token) VALUES ($1, $2, $3)
+         ON CONFLICT (contract, getter) DO UPDATE SET token = EXCLUDED.token"
+    ))
+    .bind(&contract)
+    .bind(getter)
+    .bind(format!("{token:#x}"))
+    .execute(db)
+    .await?;
+    Ok(token)
+}
+
+/// Every token resolved so far.
+pub async fn all(db: &PgPool) -> Result<Vec<ResolvedToken>> {
+    Ok(sqlx::query_as(&format!(
+        "SELECT contract, getter, token FROM {RESOLVED_TOKENS_TABLE} ORDER BY contract, getter"
+    ))
+    .fetch_all(db)
+    .await?)
+}
+
+/// Stores `tokens`, keeping the ones already resolved.
+pub async fn store(db: &PgPool, tokens: &[ResolvedToken]) -> Result<u64> {
+    if tokens.is_empty() {
+        return Ok(0);
+    }
+    let mut qb = QueryBuilder::new(format!("INSERT INTO {RESOLVED_TOKENS_TABLE} (contract, getter, token) "));
+    qb.push_values(tokens, |mut b, t| {
+        b.push_bind(&t.contract).push_bind(&t.getter).push_bind(&t.token);
+    });
+    qb.push(" ON CONFLICT (contract, getter) DO NOTHING");
+    Ok(qb.build().execute(db).await?.rows_affected())
+}
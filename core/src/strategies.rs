@@ -73,6 +73,30 @@ pub struct Stats {
 pub trait ChunkProcessor<P: Provider + Clone + Send + Sync + 'static>: Send + Sync {
     async fn process(&self, provider: P, db: &PgPool, from: u64, to: u64) -> Result<Stats>;
 
+    /// Runs once before the first chunk, e.g. to apply settings that
+    /// change rows already stored.
+    async fn prepare(&self, _db: &PgPool) -> Result<()> {
+        Ok(())
+    }
+
+    /// Deletes what this strategy wrote for blocks after `to_block`.
+    /// Returns the number of rows removed.
+    async fn rollback(&self, _db: &PgPool, _to_block: u64) -> Result<u64> {
+        Ok(0)
+    }
+
+    /// Recomputes values already written from chain data, e.g. after a bug
+    /// fix changed how they are derived. Returns the number of rows updated.
+    async fn repair(&self, _provider: P, _db: &PgPool) -> Result<u64> {
+        Ok(0)
+    }
+
+    /// Rebuilds derived state (aggregates, projections) from rows already
+    /// stored, without touching the chain. Returns the number of rows updated.
+    async fn rebuild(&self, _db: &PgPool) -> Result<u64> {
+        Ok(0)
+    }
+
     fn box_clone(&self) -> Box<dyn ChunkProcessor<P> + Send + Sync>;
 }
 
@@ -127,18 +151,19 @@ where
             .fetch_optional(db)
             .await?;
 
-            if let Some((db_from, db_to)) = row {
-                if (from as i64) >= db_from && (to as i64) <= db_to {
-                    info!(
-                        from,
-                        to,
-                        db_from,
-                        db_to,
-                        strategy = self.strategy_name,
-                        "range already indexed, skipping"
-                    );
-                    return Ok(Stats::default());
-                }
+            if let Some((db_from, db_to)) = row
+                && (from as i64) >= db_from
+                && (to as i64) <= db_to
+            {
+                info!(
+                    from,
+                    to,
+                    db_from,
+                    db_to,
+                    strategy = self.strategy_name,
+                    "range already indexed, skipping"
+                );
+                return Ok(Stats::default());
             }
         }
 
@@ -163,6 +188,36 @@ where
         Ok(result)
     }
 
+    async fn prepare(&self, db: &PgPool) -> Result<()> {
+        self.inner.prepare(db).await
+    }
+
+    async fn rollback(&self, db: &PgPool, to_block: u64) -> Result<u64> {
+        let removed = self.inner.rollback(db, to_block).await?;
+
+        // El rango acumulado ya no cubre lo que se borró
+        sqlx::query("DELETE FROM indexed_ranges WHERE strategy_name = $1 AND from_block > $2")
+            .bind(self.strategy_name)
+            .bind(to_block as i64)
+            .execute(db)
+            .await?;
+        sqlx::query("UPDATE indexed_ranges SET to_block = $2, last_updated = NOW() WHERE strategy_name = $1 AND to_block > $2")
+            .bind(self.strategy_name)
+            .bind(to_block as i64)
+            .execute(db)
+            .await?;
+
+        Ok(removed)
+    }
+
+    async fn repair(&self, provider: P, db: &PgPool) -> Result<u64> {
+        self.inner.repair(provider, db).await
+    }
+
+    async fn rebuild(&self, db: &PgPool) -> Result<u64> {
+        self.inner.rebuild(db).await
+    }
+
     fn box_clone(&self) -> Box<dyn ChunkProcessor<P> + Send + Sync> {
         Box::new(Self {
             inner: self.inner.clone(),
@@ -0,0 +1,38 @@
+//! Helpers for the tests of this crate and of the crates built on it
+//! (feature `test-util`).
+
+use alloy::primitives::{Address, B256, LogData};
+use alloy::rpc::types::Log;
+use sqlx::PgPool;
+
+use crate::db;
+
+/// A migrated database `name`, created afresh on the server of `DATABASE_URL`.
+pub async fn fresh_db(name: &str) -> PgPool {
+    let url = std::env::var("DATABASE_URL").expect("DATABASE_URL");
+    let admin = PgPool::connect(&url).await.unwrap();
+    sqlx::query(&format!("DROP DATABASE IF EXISTS {name} WITH (FORCE)")).execute(&admin).await.unwrap();
+    sqlx::query(&format!("CREATE DATABASE {name}")).execute(&admin).await.unwrap();
+    let (server, _) = url.rsplit_once('/').unwrap();
+    let pool = PgPool::connect(&format!("{server}/{name}")).await.unwrap();
+    db::migrate(&pool).await.unwrap();
+    pool
+}
+
+/// A log of `address` carrying `data`, outside any block.
+pub fn log(address: Address, data: LogData) -> Log {
+    Log { inner: alloy::primitives::Log { address, data }, ..Default::default() }
+}
+
+/// [`log`] mined as log `index` of `tx` (the block's first transaction), in
+/// `block` with hash `0x00..block`.
+pub fn mined_log(address: Address, data: LogData, block: u64, tx: B256, index: u64) -> Log {
+    Log {
+        block_hash: Some(B256::with_last_byte(block as u8)),
+        block_number: Some(block),
+        transaction_hash: Some(tx),
+        transaction_index: Some(0),
+        log_index: Some(index),
+        ..log(address, data)
+    }
+}
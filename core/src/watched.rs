@@ -0,0 +1,279 @@
+//! Addresses discovered on chain that other strategies watch, e.g. the Safe
+//! deployed by each `SuperChainSmartAccountCreated`.
+//!
+//! A producer strategy [`WatchedAddresses::add`]s each address with the block
+//! it appeared at, in the transaction that writes its own rows. A consumer
+//! fetches logs with [`WatchedAddresses::logs`]: address filters are split in
+//! batches, and addresses the consumer has not scanned up to the chunk yet
+//! (registered after the consumer went past their creation block) are
+//! backfilled from there first, a bounded number of spans per chunk. The
+//! consumer then records what it scanned with [`WatchedLogs::mark`], in the
+//! transaction that writes its rows, so a long backfill advances (and
+//! survives a failed chunk) one chunk at a time.
+
+use std::collections::BTreeMap;
+
+use alloy::primitives::Address;
+use alloy::providers::Provider;
+use alloy::rpc::types::{Filter, Log};
+use eyre::Result;
+use sqlx::{PgConnection, PgPool, QueryBuilder};
+
+/// Registered addresses: `(registry, address, from_block, added_by)`.
+pub const WATCHED_ADDRESSES_TABLE: &str = "watched_addresses";
+/// How far each consumer scanned each address.
+pub const WATCHED_SCANS_TABLE: &str = "watched_address_scans";
+
+/// Backfill spans a single chunk fetches at most; addresses still behind
+/// after them are left out of the chunk and continue on the next one.
+const BACKFILL_SPANS: u64 = 10;
+
+#[derive(Clone, Debug)]
+pub struct WatchedAddresses {
+    /// Name of the set, shared by its producers and consumers.
+    pub registry: String,
+    /// Addresses per `eth_getLogs` filter (`indexer.address_batch`).
+    pub batch: usize,
+    /// Blocks per backfill `eth_getLogs` (`indexer.chunk_size`).
+    pub span: u64,
+}
+
+/// Logs of one chunk for a registry, plus what to mark as scanned.
+#[derive(Debug)]
+pub struct WatchedLogs {
+    pub logs: Vec<Log>,
+    registry: String,
+    consumer: String,
+    /// Addresses and the block each one was scanned through.
+    addresses: Vec<String>,
+    scanned_to: Vec<i64>,
+}
+
+impl WatchedAddresses {
+    pub fn new(registry: impl Into<String>, batch: usize, span: u64) -> Self {
+        Self { registry: registry.into(), batch: batch.max(1), span: span.max(1) }
+    }
+
+    /// Registers `(address, from_block)` pairs seen by `added_by`. An address
+    /// registered again keeps the earliest block. Returns the number of new
+    /// or moved addresses.
+    pub async fn add(&self, conn: &mut PgConnection, addresses: &[(Address, u64)], added_by: &str) -> Result<u64> {
+        const BATCH_SIZE: usize = 5_000;
+
+        let mut written = 0;
+        for chunk in addresses.chunks(BATCH_SIZE) {
+            let mut qb = QueryBuilder::new(format!(
+                "INSERT INTO {WATCHED_ADDRESSES_TABLE} AS w (registry, address, from_block, added_by) "
+            ));
+            qb.push_values(chunk, |mut b, (address, from_block)| {
+                b.push_bind(&self.registry)
+                    .push_bind(format!("{address:#x}"))
+                    .push_bind(*from_block as i64)
+                    .push_bind(added_by);
+            });
+            qb.push(
+                " ON CONFLICT (registry, address) DO UPDATE SET from_block = EXCLUDED.from_block, added_by = EXCLUDED.added_by
+                  WHERE EXCLUDED.from_block < w.from_block",
+            );
+            written += qb.build().execute(&mut *conn).await?.rows_affected();
+        }
+        Ok(written)
+    }
+
+    /// Forgets addresses `added_by` registered after `to_block`.
+    pub async fn remove_after(&self, conn: &mut PgConnection, added_by: &str, to_block: u64) -> Result<u64> {
+        let res = sqlx::query(&format!(
+            "DELETE FROM {WATCHED_ADDRESSES_TABLE} WHERE registry = $1 AND added_by = $2 AND from_block > $3"
+        ))
+        .bind(&self.registry)
+        .bind(added_by)
+        .bind(to_block as i64)
+        .execute(conn)
+        .await?;
+        Ok(res.rows_affected())
+    }
+
+    /// Moves the scans of `consumer` back to `to_block`, so the blocks after
+    /// it are fetched again. Run it in the transaction that deletes what the
+    /// consumer wrote after `to_block`.
+    pub async fn rewind(&self, conn: &mut PgConnection, consumer: &str, to_block: u64) -> Result<u64> {
+        let res = sqlx::query(&format!(
+            "UPDATE {WATCHED_SCANS_TABLE} SET scanned_to = $3
+             WHERE registry = $1 AND consumer = $2 AND scanned_to > $3"
+        ))
+        .bind(&self.registry)
+        .bind(consumer)
+        .bind(to_block as i64)
+        .execute(conn)
+        .await?;
+        Ok(res.rows_affected())
+    }
+
+    /// Logs matching `filter` (topics only; its addresses and block range are
+    /// replaced) emitted in `from..=to` by every address registered by `to`.
+    /// Addresses `consumer` has not scanned up to `from` are backfilled first,
+    /// in spans of [`span`](Self::span) blocks, at most [`BACKFILL_SPANS`] of
+    /// them; those that do not reach `from` skip the chunk and are marked
+    /// scanned as far as they got.
+    pub async fn logs<P: Provider>(
+        &self,
+        provider: &P,
+        db: &PgPool,
+        consumer: &str,
+        filter: &Filter,
+        from: u64,
+        to: u64,
+    ) -> Result<WatchedLogs> {
+        let rows: Vec<(String, i64, Option<i64>)> = sqlx::query_as(&format!(
+            "SELECT w.address, w.from_block, s.scanned_to
+             FROM {WATCHED_ADDRESSES_TABLE} AS w
+             LEFT JOIN {WATCHED_SCANS_TABLE} AS s
+                 ON s.registry = w.registry AND s.address = w.address AND s.consumer = $2
+             WHERE w.registry = $1 AND w.from_block <= $3
+             ORDER BY w.address"
+        ))
+        .bind(&self.registry)
+        .bind(consumer)
+        .bind(to as i64)
+        .fetch_all(db)
+        .await?;
+
+        // Addresses behind `from`, grouped by the first block they still need
+        let mut behind: BTreeMap<u64, Vec<Address>> = BTreeMap::new();
+        let mut current = Vec::with_capacity(rows.len());
+        for (address, from_block, scanned_to) in &rows {
+            let parsed: Address = address.parse()?;
+            let start = match scanned_to {
+                Some(s) => (*s + 1).max(*from_block) as u64,
+                None => *from_block as u64,
+            };
+            if start < from {
+                behind.entry(start).or_default().push(parsed);
+            } else {
+                current.push(parsed);
+            }
+        }
+
+        let mut logs = Vec::new();
+        let mut pending: Vec<Address> = Vec::new();
+        // Walk from the oldest start, adding addresses as their start is reached
+        let mut next = behind.first_key_value().map(|(&start, _)| start).unwrap_or(from);
+        let mut spans = 0;
+        while next < from && spans < BACKFILL_SPANS {
+            let end = (next + self.span - 1).min(from - 1);
+            while let Some((&start, _)) = behind.first_key_value()
+                && start <= end
+            {
+                pending.extend(behind.pop_first().map(|(_, a)| a).unwrap_or_default());
+            }
+            logs.extend(self.fetch(provider, filter, &pending, next, end).await?);
+            next = end + 1;
+            spans += 1;
+        }
+
+        let mut scanned: Vec<(Address, u64)> = Vec::with_capacity(rows.len());
+        if !pending.is_empty() && next >= from {
+            tracing::info!(registry = self.registry, consumer, addresses = pending.len(), before = from, "watched addresses backfilled");
+            current.append(&mut pending);
+        } else if !pending.is_empty() {
+            // The rest of the backfill is left to the next chunks
+            tracing::info!(registry = self.registry, consumer, addresses = pending.len(), through = next - 1, "watched addresses partly backfilled");
+            scanned.extend(pending.iter().map(|a| (*a, next - 1)));
+        }
+
+        logs.extend(self.fetch(provider, filter, &current, from, to).await?);
+        scanned.extend(current.iter().map(|a| (*a, to)));
+
+        Ok(WatchedLogs {
+            logs,
+            registry: self.registry.clone(),
+            consumer: consumer.to_string(),
+            addresses: scanned.iter().map(|(a, _)| format!("{a:#x}")).collect(),
+            scanned_to: scanned.iter().map(|&(_, b)| b as i64).collect(),
+        })
+    }
+
+    async fn fetch<P: Provider>(
+        &self,
+        provider: &P,
+        filter: &Filter,
+        addresses: &[Address],
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<Log>> {
+        let mut logs = Vec::new();
+        for batch in addresses.chunks(self.batch) {
+            let f = filter.clone().address(batch.to_vec()).from_block(from).to_block(to);
+            logs.extend(provider.get_logs(&f).await?);
+        }
+        Ok(logs)
+    }
+}
+
+impl WatchedLogs {
+    /// Records how far each address was scanned: through the chunk's last
+    /// block, or the end of its backfill so far.
+    pub async fn mark(&self, conn: &mut PgConnection) -> Result<()> {
+        if self.addresses.is_empty() {
+            return Ok(());
+        }
+        sqlx::query(&format!(
+            "INSERT INTO {WATCHED_SCANS_TABLE} AS s (registry, consumer, address, scanned_to)
+             SELECT $1, $2, address, scanned_to FROM UNNEST($3::text[], $4::bigint[]) AS t(address, scanned_to)
+             ON CONFLICT (registry, consumer, address) DO UPDATE SET scanned_to = GREATEST(s.scanned_to, EXCLUDED.scanned_to)"
+        ))
+        .bind(&self.registry)
+        .bind(&self.consumer)
+        .bind(&self.addresses)
+        .bind(&self.scanned_to)
+        .execute(conn)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{B256, Bytes, LogData};
+
+    use super::*;
+    use crate::archive::LogArchive;
+    use crate::replay;
+    use crate::testing::mined_log;
+
+    const TOPIC: B256 = B256::repeat_byte(7);
+
+    fn log(address: Address, block: u64) -> Log {
+        let data = LogData::new_unchecked(vec![TOPIC], Bytes::new());
+        mined_log(address, data, block, B256::with_last_byte(block as u8), 0)
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn long_backfill_goes_on_over_chunks() {
+        let db = crate::testing::fresh_db("watched_backfill").await;
+        let safe = Address::repeat_byte(1);
+        LogArchive::new(db.clone(), 1).store(&[log(safe, 300), log(safe, 700), log(safe, 1050)]).await.unwrap();
+        let provider = replay::provider(db.clone(), 1);
+        let filter = Filter::new().event_signature(TOPIC);
+
+        // Registered at block 0 while the consumer is at 1000: 20 spans of 50
+        let watched = WatchedAddresses::new("safes", 10, 50);
+        let mut conn = db.acquire().await.unwrap();
+        watched.add(&mut conn, &[(safe, 0)], "test").await.unwrap();
+
+        let mut seen = Vec::new();
+        for from in [1000, 1100, 1200] {
+            let chunk = watched.logs(&provider, &db, "consumer", &filter, from, from + 99).await.unwrap();
+            seen.push(chunk.logs.iter().map(|l| l.block_number.unwrap()).collect::<Vec<_>>());
+            chunk.mark(&mut conn).await.unwrap();
+        }
+        // 0..=499, then 500..=999, then 1000..=1199 and the chunk itself
+        assert_eq!(seen, [vec![300], vec![700], vec![1050]]);
+        let scanned: i64 = sqlx::query_scalar(&format!("SELECT scanned_to FROM {WATCHED_SCANS_TABLE}"))
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(scanned, 1299);
+    }
+}
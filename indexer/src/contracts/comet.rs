@@ -0,0 +1,72 @@
+use alloy::sol;
+
+sol!(
+    #[sol(rpc)]
+    contract Comet {
+
+        #[derive(Debug)]
+        event Supply(
+            address indexed from,
+            address indexed dst,
+            uint amount
+        );
+
+        #[derive(Debug)]
+        event Withdraw(
+            address indexed src,
+            address indexed to,
+            uint amount
+        );
+
+        #[derive(Debug)]
+        event Transfer(
+            address indexed from,
+            address indexed to,
+            uint amount
+        );
+
+        #[derive(Debug)]
+        event SupplyCollateral(
+            address indexed from,
+            address indexed dst,
+            address indexed asset,
+            uint amount
+        );
+
+        #[derive(Debug)]
+        event WithdrawCollateral(
+            address indexed src,
+            address indexed to,
+            address indexed asset,
+            uint amount
+        );
+
+        #[derive(Debug)]
+        event TransferCollateral(
+            address indexed from,
+            address indexed to,
+            address indexed asset,
+            uint amount
+        );
+
+        #[derive(Debug)]
+        event AbsorbDebt(
+            address indexed absorber,
+            address indexed borrower,
+            uint basePaidOut,
+            uint usdValue
+        );
+
+        #[derive(Debug)]
+        event AbsorbCollateral(
+            address indexed absorber,
+            address indexed borrower,
+            address indexed asset,
+            uint collateralAbsorbed,
+            uint usdValue
+        );
+
+        function baseToken() external view returns (address);
+
+    }
+);
@@ -0,0 +1,8 @@
+mod celo;
+mod comet;
+mod st_celo;
+mod st_celo_manager;
+
+pub use comet::Comet;
+pub use st_celo::StCelo;
+pub use st_celo_manager::StCeloManager;
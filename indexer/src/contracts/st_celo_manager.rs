@@ -0,0 +1,21 @@
+use alloy::sol;
+
+sol!(
+    #[sol(rpc)]
+    contract StCeloManager {
+                #[derive(Debug)]
+            event VotesScheduled (
+                address indexed group,
+               uint256 amount
+            );
+
+        #[derive(Debug)]
+        event CeloWithdrawalScheduled(
+            address indexed beneficiary,
+            address indexed group,
+            uint256 amount
+        );
+
+        function stakedCelo() external view returns (address);
+    }
+);
@@ -0,0 +1,18 @@
+mod contracts;
+mod profiles;
+mod strategies;
+
+use dotenv::dotenv;
+use eyre::Result;
+use indexer_core::cli;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv().ok();
+
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
+
+    cli::run(profiles::PROFILES, profiles::build_strategies).await
+}
@@ -0,0 +1,419 @@
+//! Deployment profiles: chain, contratos, tablas y strategies de cada producto.
+//! Se elige con `profile = "..."` en `indexer.toml`, `PROFILE` o `--profile`.
+//! Las variables `STRAT_*` siguen funcionando como overrides.
+
+use alloy::primitives::address;
+use alloy::providers::DynProvider;
+use indexer_core::config::{Config, ConfigSchema, ContractDef, ContractListDef, StrategyDef, TableDef};
+use indexer_core::strategies::StrategyConfig;
+use indexer_core::watched::WatchedAddresses;
+
+use superchain_strategies::{AccountBadges, AccountCreatedProcessor, AccountTotals, OwnerAddedProcessor, SafeSettingsProcessor, SuperChainBadgesMintedProcessor};
+
+use crate::strategies::{VaultBalances, VaultsTransactionsCompoundProcessor, VaultsTransactionsStCeloManagerProcessor};
+
+pub const SUPER_CHAIN_MODULE: &str = "super_chain_module";
+pub const BADGES: &str = "badges";
+pub const COMET: &str = "comet";
+pub const ST_CELO: &str = "st_celo";
+pub const ST_CELO_MANAGER: &str = "st_celo_manager";
+
+pub const ACCOUNTS: &str = "accounts";
+pub const BADGE_CLAIMS: &str = "badge_claims";
+pub const ACCOUNT_BADGES: &str = "account_badges";
+
+/// SuperAccounts on Optimism.
+pub const SUPERACCOUNTS: ConfigSchema = ConfigSchema {
+    name: "superaccounts",
+    chain_id: 10,
+    contracts: &[
+        ContractDef {
+            key: SUPER_CHAIN_MODULE,
+            env: "STRAT_SUPER_ACCOUNT_CREATED_ADDR",
+            default: address!("0x1Ee397850c3CA629d965453B3cF102E9A8806Ded"),
+        },
+        ContractDef {
+            key: BADGES,
+            env: "STRAT_BADGES_MINTED_ADDR",
+            default: address!("0x03e2c563cf77e3Cdc0b7663cEE117dA14ea60848"),
+        },
+    ],
+    contract_lists: &[ContractListDef {
+        key: COMET,
+        env: "STRAT_VAULTS_TRANSACTIONS_COMPOUND_ADDR",
+        default: &[address!("0xE36A30D249f7761327fd973001A32010b521b6Fd")],
+    }],
+    tables: &[
+        TableDef { key: ACCOUNTS, default: "super_accounts" },
+        TableDef { key: BADGE_CLAIMS, default: "badge_claims" },
+        TableDef { key: ACCOUNT_BADGES, default: "account_badges" },
+    ],
+    strategies: &[
+        StrategyDef { name: "super_account_created", from_block: 125_901_332 },
+        StrategyDef { name: "vaults_transactions_compound", from_block: 125_901_332 },
+        StrategyDef { name: "badges_minted", from_block: 125_901_059 },
+        StrategyDef { name: "owner_added", from_block: 125_901_059 },
+        StrategyDef { name: "safe_settings", from_block: 125_901_059 },
+    ],
+};
+
+/// Prosperity accounts on Celo.
+pub const PROSPERITY: ConfigSchema = ConfigSchema {
+    name: "prosperity",
+    chain_id: 42220,
+    contracts: &[
+        ContractDef {
+            key: SUPER_CHAIN_MODULE,
+            env: "STRAT_SUPER_ACCOUNT_CREATED_ADDR",
+            default: address!("0x58f5805b5072C3Dd157805132714E1dF40E79c66"),
+        },
+        ContractDef {
+            key: BADGES,
+            env: "STRAT_BADGES_MINTED_ADDR",
+            default: address!("0xd47C56513E640E394024FaCBBe5032cf604Bb699"),
+        },
+        ContractDef {
+            key: ST_CELO,
+            env: "STRAT_VAULTS_TRANSACTIONS_STCELO_ADDR",
+            default: address!("0xC668583dcbDc9ae6FA3CE46462758188adfdfC24"),
+        },
+        ContractDef {
+            key: ST_CELO_MANAGER,
+            env: "STRAT_VAULTS_TRANSACTIONS_STCELO_MANAGER_ADDR",
+            default: address!("0xeA280B39437a64473a0C77949759E6629eD1Dc73"),
+        },
+    ],
+    contract_lists: &[],
+    tables: &[
+        TableDef { key: ACCOUNTS, default: "users" },
+        TableDef { key: BADGE_CLAIMS, default: "badge_claims" },
+        TableDef { key: ACCOUNT_BADGES, default: "account_badges" },
+    ],
+    strategies: &[
+        StrategyDef { name: "prosperity_account_created", from_block: 29_117_283 },
+        StrategyDef { name: "vaults_transactions_stcelo", from_block: 43_000_000 },
+        StrategyDef { name: "badges_minted", from_block: 29_117_140 },
+        StrategyDef { name: "owner_added", from_block: 29_117_140 },
+        StrategyDef { name: "safe_settings", from_block: 29_117_140 },
+    ],
+};
+
+pub const PROFILES: &[ConfigSchema] = &[SUPERACCOUNTS, PROSPERITY];
+
+/// Compiled strategies of the selected profile, minus the disabled ones.
+///
+/// SuperChainModule and SuperChainBadges strategies are shared; only the
+/// vault strategy and the name the account strategy was registered with differ.
+pub fn build_strategies(config: &Config) -> Vec<StrategyConfig<DynProvider>> {
+    let accounts = config.table(ACCOUNTS).to_string();
+    let claims = config.table(BADGE_CLAIMS).to_string();
+    let thresholds = &config.levels.thresholds;
+    let totals = Some(AccountTotals {
+        level_thresholds: (!thresholds.is_empty()).then(|| thresholds.clone()),
+        ..AccountTotals::new(&accounts, &claims)
+    });
+    let module = config.contract(SUPER_CHAIN_MODULE);
+    // Account Safes, named after the accounts table
+    let watched = WatchedAddresses::new(&accounts, config.indexer.address_batch, config.indexer.chunk_size);
+
+    let (account_created, vaults) = match config.profile.as_str() {
+        "superaccounts" => (
+            "super_account_created",
+            config.strategy_config(
+                "vaults_transactions_compound",
+                VaultBalances::new(VaultsTransactionsCompoundProcessor::new(
+                    config.contract_list(COMET).to_vec(),
+                    accounts.clone(),
+                )),
+            ),
+        ),
+        "prosperity" => (
+            "prosperity_account_created",
+            config.strategy_config(
+                "vaults_transactions_stcelo",
+                VaultBalances::new(VaultsTransactionsStCeloManagerProcessor::new(
+                    config.contract(ST_CELO),
+                    config.contract(ST_CELO_MANAGER),
+                    accounts.clone(),
+                )),
+            ),
+        ),
+        other => unreachable!("profile '{other}' is not in PROFILES"),
+    };
+
+    [
+        config.strategy_config(
+            account_created,
+            AccountCreatedProcessor {
+                address: module,
+                table: accounts.clone(),
+                totals: totals.clone(),
+                watched: Some(watched.clone()),
+            },
+        ),
+        vaults,
+        config.strategy_config(
+            "badges_minted",
+            SuperChainBadgesMintedProcessor {
+                address: config.contract(BADGES),
+                current: Some(AccountBadges::new(config.table(ACCOUNT_BADGES), &claims)),
+                table: claims,
+                totals,
+            },
+        ),
+        config.strategy_config(
+            "owner_added",
+            OwnerAddedProcessor { address: module, table: accounts.clone(), watched: watched.clone() },
+        ),
+        config.strategy_config("safe_settings", SafeSettingsProcessor { module, table: accounts, watched }),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Address, B256, U256, address};
+    use alloy::rpc::types::Log;
+    use alloy::sol_types::SolEvent;
+    use indexer_core::archive::LogArchive;
+    use indexer_core::indexer::{IndexerConfig, prepare, run_indexer};
+    use indexer_core::testing::{fresh_db, mined_log};
+    use indexer_core::{quarantine, replay, resolved};
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::contracts::{Comet, StCelo, StCeloManager};
+
+    const ACCOUNT: Address = address!("0x00000000000000000000000000000000000000aa");
+
+    fn config(profile: &str) -> Config {
+        config_with(profile, "")
+    }
+
+    /// Config of `profile` with `extra` TOML appended.
+    fn config_with(profile: &str, extra: &str) -> Config {
+        let dir = std::env::temp_dir().join(format!("replay-{profile}-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("indexer-{}.toml", extra.len()));
+        std::fs::write(
+            &path,
+            format!(
+                "profile = \"{profile}\"\n\
+                 [chain]\nrpc_url = \"http://127.0.0.1:1\"\n\
+                 [database]\nurl = \"postgres://unused/db\"\n\
+                 [api]\nenabled = false\n{extra}"
+            ),
+        )
+        .unwrap();
+        Config::load_profile(PROFILES, Some(profile), Some(&path)).unwrap()
+    }
+
+    /// Archives `logs` (and their blocks) as an online run would have.
+    async fn archive(db: &PgPool, chain_id: u64, logs: &[Log]) {
+        let archive = LogArchive::new(db.clone(), chain_id);
+        archive.store(logs).await.unwrap();
+        let blocks: Vec<_> = logs
+            .iter()
+            .map(|l| (l.block_number.unwrap(), format!("{:#x}", l.block_hash.unwrap()), 1_700_000_000))
+            .collect();
+        archive.store_blocks(&blocks).await.unwrap();
+    }
+
+    /// Receipt of the tx of `logs`, in the shape `receipt_cache` stores.
+    fn receipt(to: Address, logs: &[Log]) -> (String, u64, serde_json::Value) {
+        let (tx, block) = (logs[0].transaction_hash.unwrap(), logs[0].block_number.unwrap());
+        let receipt = json!({
+            "transactionHash": tx,
+            "transactionIndex": "0x0",
+            "blockHash": logs[0].block_hash,
+            "blockNumber": format!("{block:#x}"),
+            "from": ACCOUNT,
+            "to": to,
+            "cumulativeGasUsed": "0x0",
+            "gasUsed": "0x0",
+            "effectiveGasPrice": "0x0",
+            "contractAddress": null,
+            "logs": logs,
+            "logsBloom": format!("0x{}", "00".repeat(256)),
+            "status": "0x1",
+            "type": "0x0",
+        });
+        (format!("{tx:#x}"), block, receipt)
+    }
+
+    async fn add_account(db: &PgPool, table: &str) {
+        sqlx::query(&format!(
+            "INSERT INTO {table} (account, username, eoas, level, noun, total_points, total_badges)
+             VALUES ($1, 'test', ARRAY[]::text[], 0, '{{}}', 0, 0)"
+        ))
+        .bind(format!("{ACCOUNT:#x}"))
+        .execute(db)
+        .await
+        .unwrap();
+    }
+
+    /// Runs every strategy of `config`, built as a fresh process would, over
+    /// `from..=to` against the archive only.
+    async fn replay_all(db: &PgPool, config: &Config, from: u64, to: u64) {
+        let strategies = build_strategies(config);
+        prepare(db, &strategies).await.unwrap();
+        let indexer = IndexerConfig::new(1_000);
+        let provider = replay::provider(db.clone(), config.chain.chain_id);
+        let report = run_indexer(provider, db, from, to, &indexer, strategies, None).await.unwrap();
+        assert!(report.failed.is_empty(), "failed offline: {:?}", report.failed);
+    }
+
+    async fn count(db: &PgPool, table: &str) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table}")).fetch_one(db).await.unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn superaccounts_replay_offline() {
+        let db = fresh_db("replay_superaccounts").await;
+        let config = config("superaccounts");
+        add_account(&db, config.table(ACCOUNTS)).await;
+
+        // Market of the default config, base token seeded by the migration
+        let market = config.contract_list(COMET)[0];
+        let supply = Comet::Supply { from: ACCOUNT, dst: ACCOUNT, amount: U256::from(5) };
+        archive(&db, 10, &[mined_log(market, supply.encode_log_data(), 130_000_000, B256::repeat_byte(1), 0)]).await;
+
+        replay_all(&db, &config, 129_999_990, 130_000_010).await;
+        assert_eq!(count(&db, "vaults_transactions").await, 1);
+        assert_eq!(count(&db, "vault_balances").await, 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn prosperity_replay_offline() {
+        let db = fresh_db("replay_prosperity").await;
+        let config = config("prosperity");
+        add_account(&db, config.table(ACCOUNTS)).await;
+
+        let (st_celo, manager) = (config.contract(ST_CELO), config.contract(ST_CELO_MANAGER));
+        resolved::resolve(&db, manager, "stakedCelo", || async { Ok(st_celo) }).await.unwrap();
+
+        // A deposit (stCELO minted, CELO scheduled as votes) and a withdrawal
+        // (stCELO sent to the manager, CELO scheduled for the account)
+        let group = Address::repeat_byte(3);
+        let mint = StCelo::Transfer { from: Address::ZERO, to: ACCOUNT, value: U256::from(7) };
+        let votes = StCeloManager::VotesScheduled { group, amount: U256::from(8) };
+        let burn = StCelo::Transfer { from: ACCOUNT, to: manager, value: U256::from(2) };
+        let withdrawal = StCeloManager::CeloWithdrawalScheduled { beneficiary: ACCOUNT, group, amount: U256::from(3) };
+        let (block, deposit_tx, withdraw_tx) = (50_000_000, B256::repeat_byte(2), B256::repeat_byte(4));
+        let minted = mined_log(st_celo, mint.encode_log_data(), block, deposit_tx, 0);
+        let burnt = mined_log(st_celo, burn.encode_log_data(), block + 1, withdraw_tx, 0);
+        archive(&db, 42220, &[minted.clone(), burnt.clone()]).await;
+        let receipts = [
+            receipt(manager, &[minted, mined_log(manager, votes.encode_log_data(), block, deposit_tx, 1)]),
+            receipt(manager, &[burnt, mined_log(manager, withdrawal.encode_log_data(), block + 1, withdraw_tx, 1)]),
+        ];
+        LogArchive::new(db.clone(), 42220).store_receipts(&receipts).await.unwrap();
+
+        replay_all(&db, &config, block - 10, block + 10).await;
+        let flows: Vec<(String, String, String)> = sqlx::query_as(
+            "SELECT account, event_type, amount::text FROM vaults_transactions ORDER BY tx_block",
+        )
+        .fetch_all(&db)
+        .await
+        .unwrap();
+        let account = format!("{ACCOUNT:#x}");
+        assert_eq!(
+            flows,
+            [(account.clone(), "deposit".into(), "8".into()), (account, "withdraw".into(), "3".into())]
+        );
+        assert_eq!(count(&db, "vault_balances").await, 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn quarantined_deposit_is_retried() {
+        let db = fresh_db("retry_quarantined").await;
+        let config = config("prosperity");
+        add_account(&db, config.table(ACCOUNTS)).await;
+
+        let (st_celo, manager) = (config.contract(ST_CELO), config.contract(ST_CELO_MANAGER));
+        resolved::resolve(&db, manager, "stakedCelo", || async { Ok(st_celo) }).await.unwrap();
+        let mint = StCelo::Transfer { from: Address::ZERO, to: ACCOUNT, value: U256::from(7) };
+        let votes = StCeloManager::VotesScheduled { group: Address::repeat_byte(3), amount: U256::from(8) };
+        let (block, tx) = (50_000_000, B256::repeat_byte(2));
+        let minted = mined_log(st_celo, mint.encode_log_data(), block, tx, 0);
+        archive(&db, 42220, std::slice::from_ref(&minted)).await;
+
+        // No receipt archived: the deposit cannot be valued
+        replay_all(&db, &config, block - 10, block + 10).await;
+        assert_eq!(count(&db, "quarantined_logs").await, 1);
+        assert_eq!(count(&db, "vaults_transactions").await, 0);
+
+        let receipts = [receipt(manager, &[minted, mined_log(manager, votes.encode_log_data(), block, tx, 1)])];
+        LogArchive::new(db.clone(), 42220).store_receipts(&receipts).await.unwrap();
+        let strategy = build_strategies(&config).into_iter().find(|s| s.name == "vaults_transactions_stcelo").unwrap();
+        let provider = replay::provider(db.clone(), 42220);
+        assert_eq!(quarantine::retry(provider, &db, &strategy).await.unwrap(), (1, 0));
+        assert_eq!(count(&db, "quarantined_logs").await, 0);
+        assert_eq!(count(&db, "vaults_transactions").await, 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn tx_minting_twice_is_quarantined() {
+        let db = fresh_db("several_deposits").await;
+        let config = config("prosperity");
+        add_account(&db, config.table(ACCOUNTS)).await;
+
+        let (st_celo, manager) = (config.contract(ST_CELO), config.contract(ST_CELO_MANAGER));
+        resolved::resolve(&db, manager, "stakedCelo", || async { Ok(st_celo) }).await.unwrap();
+        let mint = StCelo::Transfer { from: Address::ZERO, to: ACCOUNT, value: U256::from(7) };
+        let votes = StCeloManager::VotesScheduled { group: Address::repeat_byte(3), amount: U256::from(8) };
+        let (block, tx) = (50_000_000, B256::repeat_byte(2));
+        let minted = [0, 2].map(|i| mined_log(st_celo, mint.encode_log_data(), block, tx, i));
+        archive(&db, 42220, &minted).await;
+        let [first, second] = minted;
+        let logs = [
+            first,
+            mined_log(manager, votes.encode_log_data(), block, tx, 1),
+            second,
+            mined_log(manager, votes.encode_log_data(), block, tx, 3),
+        ];
+        LogArchive::new(db.clone(), 42220).store_receipts(&[receipt(manager, &logs)]).await.unwrap();
+
+        // Which votes belong to which mint is unknown
+        replay_all(&db, &config, block - 10, block + 10).await;
+        let kinds: Vec<String> = sqlx::query_scalar("SELECT DISTINCT error_kind FROM quarantined_logs")
+            .fetch_all(&db)
+            .await
+            .unwrap();
+        assert_eq!(kinds, ["several_deposits"]);
+        assert_eq!(count(&db, "quarantined_logs").await, 2);
+        assert_eq!(count(&db, "vaults_transactions").await, 0);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn custom_accounts_table_is_prepared() {
+        let db = fresh_db("custom_accounts").await;
+        sqlx::query("CREATE TABLE members (LIKE users INCLUDING ALL)").execute(&db).await.unwrap();
+        let config = config_with("superaccounts", "[tables]\naccounts = \"members\"\n");
+        add_account(&db, "members").await;
+        sqlx::query("UPDATE members SET eoas = ARRAY['0x00000000000000000000000000000000000000bb'], last_update_block_number = 7")
+            .execute(&db)
+            .await
+            .unwrap();
+
+        // Twice: a second start finds everything in place
+        for _ in 0..2 {
+            prepare(&db, &build_strategies(&config)).await.unwrap();
+        }
+
+        assert_eq!(count(&db, "owner_changes").await, 1);
+        let watched: (String, i64) = sqlx::query_as("SELECT registry, from_block FROM watched_addresses")
+            .fetch_one(&db)
+            .await
+            .unwrap();
+        assert_eq!(watched, ("members".to_string(), 7));
+    }
+}
@@ -0,0 +1,7 @@
+mod vault_balances;
+mod vaults_transactions_compound;
+mod vaults_transactions_stcelo;
+
+pub use vault_balances::{VaultBalances, VaultFlows};
+pub use vaults_transactions_compound::VaultsTransactionsCompoundProcessor;
+pub use vaults_transactions_stcelo::VaultsTransactionsStCeloManagerProcessor;
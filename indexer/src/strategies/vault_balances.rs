@@ -0,0 +1,218 @@
+//! Balances derived from `vaults_transactions`: the current position per
+//! account and token, plus one end-of-day (UTC) snapshot per day since the
+//! account's first flow.
+//!
+//! Wraps a vault strategy: the flows of each chunk are written in the same
+//! transaction as the balances of the accounts they touched, recomputed from
+//! their flows. Every position gets snapshots up to the day of the latest
+//! flow indexed, so days without flows carry the balance.
+
+use alloy::providers::Provider;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use eyre::Result;
+use indexer_core::strategies::{ChunkProcessor, Stats};
+use sqlx::{PgConnection, PgPool};
+
+/// Signed amount of a `vaults_transactions` row.
+const SIGNED: &str = "CASE v.direction WHEN 'in' THEN v.amount ELSE -v.amount END";
+/// UTC day of a `vaults_transactions` row.
+const DAY: &str = "(v.block_time AT TIME ZONE 'UTC')::date";
+
+/// A vault strategy that writes its `vaults_transactions` rows through a
+/// connection it is handed, so they commit together with what is derived
+/// from them.
+#[async_trait]
+pub trait VaultFlows<P: Provider + Clone + Send + Sync + 'static>: ChunkProcessor<P> {
+    /// Like [`ChunkProcessor::process`], inserting the flows through `conn`.
+    async fn process_in(&self, provider: P, db: &PgPool, conn: &mut PgConnection, from: u64, to: u64)
+    -> Result<Stats>;
+}
+
+#[derive(Clone)]
+pub struct VaultBalances<T> {
+    inner: T,
+}
+
+impl<T> VaultBalances<T> {
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+}
+
+#[async_trait]
+impl<P, T> ChunkProcessor<P> for VaultBalances<T>
+where
+    P: Provider + Clone + Send + Sync + 'static,
+    T: VaultFlows<P> + Clone + 'static,
+{
+    async fn process(&self, provider: P, db: &PgPool, from: u64, to: u64) -> Result<Stats> {
+        let mut tx = db.begin().await?;
+        let stats = self.inner.process_in(provider, db, &mut tx, from, to).await?;
+
+        // Day of the chunk's latest flow; without flows nothing changes
+        let through: Option<NaiveDate> = sqlx::query_scalar(&format!(
+            "SELECT MAX({DAY}) FROM vaults_transactions AS v WHERE v.tx_block BETWEEN $1 AND $2"
+        ))
+        .bind(from as i64)
+        .bind(to as i64)
+        .fetch_one(&mut *tx)
+        .await?;
+        if let Some(through) = through {
+            let touched = refresh(&mut tx, from as i64, to as i64, through).await?;
+            let extended = extend(&mut tx, through).await?;
+            tracing::debug!(from, to, touched, extended, "vault balances updated");
+        }
+        tx.commit().await?;
+        Ok(stats)
+    }
+
+    async fn prepare(&self, db: &PgPool) -> Result<()> {
+        self.inner.prepare(db).await
+    }
+
+    async fn rollback(&self, db: &PgPool, to_block: u64) -> Result<u64> {
+        let removed = self.inner.rollback(db, to_block).await?;
+        rebuild(db).await?;
+        Ok(removed)
+    }
+
+    async fn repair(&self, provider: P, db: &PgPool) -> Result<u64> {
+        self.inner.repair(provider, db).await
+    }
+
+    async fn rebuild(&self, db: &PgPool) -> Result<u64> {
+        Ok(self.inner.rebuild(db).await? + rebuild(db).await?)
+    }
+
+    fn box_clone(&self) -> Box<dyn ChunkProcessor<P> + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+/// Recomputes the balances and snapshots of every `(account, token)` with a
+/// flow in `from..=to`, snapshots running until `through` (or further, if
+/// they already did). Returns the number of positions touched.
+async fn refresh(conn: &mut PgConnection, from: i64, to: i64, through: NaiveDate) -> Result<u64> {
+    let keys: Vec<(String, String, NaiveDate)> = sqlx::query_as(&format!(
+        "SELECT lower(v.account), v.token, MIN({DAY})
+         FROM vaults_transactions AS v
+         WHERE v.tx_block BETWEEN $1 AND $2
+         GROUP BY 1, 2"
+    ))
+    .bind(from)
+    .bind(to)
+    .fetch_all(&mut *conn)
+    .await?;
+    if keys.is_empty() {
+        return Ok(0);
+    }
+    let accounts: Vec<&str> = keys.iter().map(|k| k.0.as_str()).collect();
+    let tokens: Vec<&str> = keys.iter().map(|k| k.1.as_str()).collect();
+    let from_days: Vec<NaiveDate> = keys.iter().map(|k| k.2).collect();
+
+    sqlx::query(&format!(
+        "INSERT INTO vault_balances (account, token, balance, deposited, withdrawn, last_block)
+         SELECT lower(v.account), v.token,
+                SUM({SIGNED}),
+                COALESCE(SUM(v.amount) FILTER (WHERE v.direction = 'in'), 0),
+                COALESCE(SUM(v.amount) FILTER (WHERE v.direction = 'out'), 0),
+                MAX(v.tx_block)
+         FROM vaults_transactions AS v
+         WHERE (lower(v.account), v.token) IN (SELECT * FROM UNNEST($1::text[], $2::text[]))
+         GROUP BY 1, 2
+         ON CONFLICT (account, token) DO UPDATE SET
+             balance = EXCLUDED.balance,
+             deposited = EXCLUDED.deposited,
+             withdrawn = EXCLUDED.withdrawn,
+             last_block = EXCLUDED.last_block"
+    ))
+    .bind(&accounts)
+    .bind(&tokens)
+    .execute(&mut *conn)
+    .await?;
+
+    // Running sum of the daily net flows, from the first flow of each position
+    sqlx::query(&format!(
+        "WITH k AS (
+             SELECT * FROM UNNEST($1::text[], $2::text[], $3::date[]) AS k(account, token, from_day)
+         ),
+         daily AS (
+             SELECT lower(v.account) AS account, v.token, {DAY} AS day, SUM({SIGNED}) AS net
+             FROM vaults_transactions AS v
+             JOIN k ON k.account = lower(v.account) AND k.token = v.token
+             GROUP BY 1, 2, 3
+         ),
+         days AS (
+             SELECT k.account, k.token, k.from_day, g::date AS day
+             FROM k
+             CROSS JOIN LATERAL generate_series(
+                 (SELECT MIN(d.day) FROM daily AS d WHERE d.account = k.account AND d.token = k.token),
+                 GREATEST(
+                     $4::date,
+                     (SELECT MAX(s.day) FROM vault_balance_snapshots AS s WHERE s.account = k.account AND s.token = k.token)
+                 ),
+                 interval '1 day'
+             ) AS g
+         )
+         INSERT INTO vault_balance_snapshots (account, token, day, balance)
+         SELECT account, token, day, balance
+         FROM (
+             SELECT days.account, days.token, days.day, days.from_day,
+                    SUM(COALESCE(daily.net, 0)) OVER (PARTITION BY days.account, days.token ORDER BY days.day) AS balance
+             FROM days
+             LEFT JOIN daily ON daily.account = days.account AND daily.token = days.token AND daily.day = days.day
+         ) AS running
+         WHERE day >= from_day
+         ON CONFLICT (account, token, day) DO UPDATE SET balance = EXCLUDED.balance"
+    ))
+    .bind(&accounts)
+    .bind(&tokens)
+    .bind(&from_days)
+    .bind(through)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(keys.len() as u64)
+}
+
+/// Carries every position's balance forward until `through`.
+async fn extend(conn: &mut PgConnection, through: NaiveDate) -> Result<u64> {
+    let res = sqlx::query(
+        "INSERT INTO vault_balance_snapshots (account, token, day, balance)
+         SELECT b.account, b.token, g::date, b.balance
+         FROM vault_balances AS b
+         CROSS JOIN LATERAL generate_series(
+             (SELECT MAX(s.day) + 1 FROM vault_balance_snapshots AS s WHERE s.account = b.account AND s.token = b.token),
+             $1::date,
+             interval '1 day'
+         ) AS g
+         ON CONFLICT (account, token, day) DO NOTHING",
+    )
+    .bind(through)
+    .execute(conn)
+    .await?;
+    Ok(res.rows_affected())
+}
+
+/// Recomputes both tables from the whole ledger, keeping snapshots as far
+/// as they went. Returns the number of positions.
+pub async fn rebuild(db: &PgPool) -> Result<u64> {
+    let mut tx = db.begin().await?;
+    let through: Option<NaiveDate> = sqlx::query_scalar(&format!(
+        "SELECT GREATEST(
+             (SELECT MAX(day) FROM vault_balance_snapshots),
+             (SELECT MAX({DAY}) FROM vaults_transactions AS v)
+         )"
+    ))
+    .fetch_one(&mut *tx)
+    .await?;
+    sqlx::query("DELETE FROM vault_balance_snapshots").execute(&mut *tx).await?;
+    sqlx::query("DELETE FROM vault_balances").execute(&mut *tx).await?;
+    let positions = match through {
+        Some(through) => refresh(&mut tx, 0, i64::MAX, through).await?,
+        None => 0,
+    };
+    tx.commit().await?;
+    Ok(positions)
+}
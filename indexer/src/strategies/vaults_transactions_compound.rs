@@ -0,0 +1,420 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use alloy::{
+    primitives::{Address, B256, U256},
+    rpc::types::{Filter, Log},
+    sol_types::SolEvent,
+};
+use async_trait::async_trait;
+use eyre::Result;
+use indexer_core::blocks::BlockTimestamps;
+use indexer_core::quarantine::{self, Quarantinable};
+use indexer_core::resolved::{self, RESOLVED_TOKENS_TABLE};
+use indexer_core::strategies::{ChunkProcessor, Stats};
+
+use super::VaultFlows;
+use sqlx::{PgConnection, PgPool, QueryBuilder};
+
+use crate::contracts::Comet::{
+    self, AbsorbCollateral, AbsorbDebt, Supply, SupplyCollateral, Transfer, TransferCollateral, Withdraw,
+    WithdrawCollateral,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Direction {
+    In,
+    Out,
+}
+impl Direction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Direction::In => "in",
+            Direction::Out => "out",
+        }
+    }
+}
+
+/// Comet event a flow comes from, stored in `event_type`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum EventType {
+    Supply,
+    Withdraw,
+    Transfer,
+    SupplyCollateral,
+    WithdrawCollateral,
+    TransferCollateral,
+    AbsorbDebt,
+    AbsorbCollateral,
+}
+impl EventType {
+    fn as_str(self) -> &'static str {
+        match self {
+            EventType::Supply => "supply",
+            EventType::Withdraw => "withdraw",
+            EventType::Transfer => "transfer",
+            EventType::SupplyCollateral => "supply_collateral",
+            EventType::WithdrawCollateral => "withdraw_collateral",
+            EventType::TransferCollateral => "transfer_collateral",
+            EventType::AbsorbDebt => "absorb_debt",
+            EventType::AbsorbCollateral => "absorb_collateral",
+        }
+    }
+}
+
+const STRATEGY: &str = "vaults_transactions_compound";
+/// Getter the base tokens are stored under in `resolved_tokens`.
+const BASE_TOKEN: &str = "baseToken";
+
+const EVENT_TYPES: [EventType; 8] = [
+    EventType::Supply,
+    EventType::Withdraw,
+    EventType::Transfer,
+    EventType::SupplyCollateral,
+    EventType::WithdrawCollateral,
+    EventType::TransferCollateral,
+    EventType::AbsorbDebt,
+    EventType::AbsorbCollateral,
+];
+
+const EVENTS: [B256; 8] = [
+    Supply::SIGNATURE_HASH,
+    Withdraw::SIGNATURE_HASH,
+    Transfer::SIGNATURE_HASH,
+    SupplyCollateral::SIGNATURE_HASH,
+    WithdrawCollateral::SIGNATURE_HASH,
+    TransferCollateral::SIGNATURE_HASH,
+    AbsorbDebt::SIGNATURE_HASH,
+    AbsorbCollateral::SIGNATURE_HASH,
+];
+
+/// A Comet log that matched a watched signature but does not decode.
+#[derive(Debug, thiserror::Error)]
+#[error("{event} log does not decode: {source}")]
+struct BadLog {
+    event: &'static str,
+    source: alloy::sol_types::Error,
+}
+
+impl Quarantinable for BadLog {
+    fn kind(&self) -> &'static str {
+        "bad_log"
+    }
+}
+
+/// One side of a Comet event, before the account filter.
+struct Flow {
+    account: Address,
+    token: Address,
+    amount: U256,
+    direction: Direction,
+    event_type: EventType,
+}
+
+#[derive(Clone)]
+pub struct VaultsTransactionsCompoundProcessor {
+    /// Comet markets, one per base asset.
+    pub markets: Vec<Address>,
+    /// Accounts table whose members' transactions are kept.
+    pub accounts_table: String,
+    /// `baseToken()` of each market, resolved on first use (see [`resolved`]).
+    base_tokens: Arc<Mutex<HashMap<Address, Address>>>,
+}
+
+impl VaultsTransactionsCompoundProcessor {
+    pub fn new(markets: Vec<Address>, accounts_table: String) -> Self {
+        Self { markets, accounts_table, base_tokens: Arc::default() }
+    }
+
+    async fn base_token<P>(&self, provider: &P, db: &PgPool, market: Address) -> Result<Address>
+    where
+        P: alloy::providers::Provider + Clone + Send + Sync + 'static,
+    {
+        if let Some(token) = self.base_tokens.lock().expect("base token cache poisoned").get(&market) {
+            return Ok(*token);
+        }
+        let token = resolved::resolve(db, market, BASE_TOKEN, || async {
+            let token = Comet::new(market, provider).baseToken().call().await?;
+            tracing::info!(%market, base_token = %token, "resolved Comet base token");
+            Ok(token)
+        })
+        .await?;
+        self.base_tokens.lock().expect("base token cache poisoned").insert(market, token);
+        Ok(token)
+    }
+}
+
+#[async_trait]
+impl<P: alloy::providers::Provider + Clone + Send + Sync + 'static> ChunkProcessor<P>
+    for VaultsTransactionsCompoundProcessor
+{
+    async fn process(&self, provider: P, db: &PgPool, from: u64, to: u64) -> Result<Stats> {
+        let mut tx = db.begin().await?;
+        let stats = process_vaults_transactions_chunk(provider, db, &mut tx, self, from, to).await?;
+        tx.commit().await?;
+        Ok(stats)
+    }
+
+    async fn rollback(&self, db: &PgPool, to_block: u64) -> Result<u64> {
+        // stCELO also writes `withdraw`; those are told apart by token
+        let markets: Vec<String> = self.markets.iter().map(|m| format!("{m:#x}")).collect();
+        let event_types: Vec<&str> = EVENT_TYPES.iter().map(|e| e.as_str()).collect();
+        let res = sqlx::query(&format!(
+            "DELETE FROM vaults_transactions
+             WHERE tx_block > $1 AND event_type = ANY($2)
+               AND (event_type <> 'withdraw' OR lower(token) IN (
+                   SELECT token FROM {RESOLVED_TOKENS_TABLE} WHERE getter = '{BASE_TOKEN}' AND contract = ANY($3)
+               ))"
+        ))
+        .bind(to_block as i64)
+        .bind(&event_types)
+        .bind(&markets)
+        .execute(db)
+        .await?;
+        Ok(res.rows_affected())
+    }
+
+    fn box_clone(&self) -> Box<dyn ChunkProcessor<P> + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+#[async_trait]
+impl<P: alloy::providers::Provider + Clone + Send + Sync + 'static> VaultFlows<P>
+    for VaultsTransactionsCompoundProcessor
+{
+    async fn process_in(&self, provider: P, db: &PgPool, conn: &mut PgConnection, from: u64, to: u64) -> Result<Stats> {
+        process_vaults_transactions_chunk(provider, db, conn, self, from, to).await
+    }
+}
+
+pub async fn process_vaults_transactions_chunk<P>(
+    provider: P,
+    db: &PgPool,
+    conn: &mut PgConnection,
+    processor: &VaultsTransactionsCompoundProcessor,
+    from: u64,
+    to: u64,
+) -> Result<Stats>
+where
+    P: alloy::providers::Provider + Clone + Send + Sync + 'static,
+{
+    let accounts_table = &processor.accounts_table;
+    let t0 = std::time::Instant::now();
+
+    tracing::info!(from = from, to = to, markets = processor.markets.len(), "processing event range");
+
+    // Todos los mercados y eventos en una sola llamada
+    let filter = Filter::new()
+        .address(processor.markets.clone())
+        .event_signature(EVENTS.to_vec())
+        .from_block(from)
+        .to_block(to);
+    let logs = provider.get_logs(&filter).await?;
+
+    if logs.is_empty() {
+        tracing::info!(from = from, to = to, "no logs found in range");
+        return Ok(Stats::default());
+    }
+
+    quarantine::release(db, STRATEGY, from, to).await?;
+
+    let mut flows: Vec<(Flow, Log)> = Vec::with_capacity(logs.len());
+    let mut quarantined = 0usize;
+    for log in logs {
+        let base = processor.base_token(&provider, db, log.address()).await?;
+        match flows_of(&log, base) {
+            Ok(fs) => flows.extend(fs.into_iter().map(|f| (f, log.clone()))),
+            Err(e) => {
+                quarantine::quarantine(db, STRATEGY, &log, &e).await?;
+                quarantined += 1;
+            }
+        }
+    }
+
+    let mut dsts: Vec<String> = flows.iter().map(|(f, _)| format!("{:#x}", f.account)).collect();
+    dsts.sort_unstable();
+    dsts.dedup();
+
+    // 2) Pide a la DB cuáles cuentas existen en la tabla de cuentas
+    let existing: Vec<String> = sqlx::query_scalar(&format!(
+        "SELECT account FROM {accounts_table} WHERE lower(account) = ANY($1::text[])"
+    ))
+    .bind(&dsts)
+    .fetch_all(db)
+    .await?;
+
+    tracing::info!(matches = existing.len(), table = accounts_table, "account matches");
+
+    let existing_set: HashSet<String> = existing.into_iter().map(|s| s.to_lowercase()).collect();
+    flows.retain(|(f, _)| existing_set.contains(&format!("{:#x}", f.account)));
+
+    if flows.is_empty() {
+        tracing::info!(from = from, to = to, quarantined, "no valid logs found in range");
+        return Ok(Stats::default());
+    }
+
+    let mut timestamps = BlockTimestamps::new(&provider);
+
+    struct Row {
+        amount: U256,
+        txblock: i64,
+        block_time: chrono::DateTime<chrono::Utc>,
+    }
+
+    // Keyed like the unique index, so repeated flows in one tx add up
+    // instead of hitting the same row twice in one INSERT
+    let mut rows: BTreeMap<(String, String, String, Direction, EventType), Row> = BTreeMap::new();
+    let logs_found = flows.len();
+    for (flow, log) in flows {
+        let key = (
+            format!("{:#x}", flow.account),
+            format!("{:#x}", flow.token),
+            log.transaction_hash.map(|h| format!("{:#x}", h)).unwrap_or_default(),
+            flow.direction,
+            flow.event_type,
+        );
+        let block_time = timestamps.for_log(&log).await?;
+        let row = rows.entry(key).or_insert(Row {
+            amount: U256::ZERO,
+            txblock: log.block_number.map(|b| b as i64).unwrap_or_default(),
+            block_time,
+        });
+        row.amount = row.amount.saturating_add(flow.amount);
+    }
+
+    let rows: Vec<_> = rows
+        .into_iter()
+        .map(|(key, row)| Ok((key, row.amount.to_string().parse::<sqlx::types::BigDecimal>()?, row)))
+        .collect::<Result<_>>()?;
+
+    // Process in batches to avoid parameter limit (8 columns per row, max 65535 params)
+    let mut rows_written = 0u64;
+    const BATCH_SIZE: usize = 500;
+
+    for chunk in rows.chunks(BATCH_SIZE) {
+        let mut qb = QueryBuilder::new(
+            "INSERT INTO vaults_transactions (
+                account, token, amount, direction, event_type, tx_hash, tx_block, block_time
+            ) ",
+        );
+        qb.push_values(chunk.iter(), |mut b, ((account, token, tx_hash, direction, event_type), amount, row)| {
+            b.push_bind(account)
+                .push_bind(token)
+                .push_bind(amount)
+                .push_bind(direction.as_str())
+                .push_bind(event_type.as_str())
+                .push_bind(tx_hash)
+                .push_bind(row.txblock)
+                .push_bind(row.block_time);
+        });
+        qb.push(" ON CONFLICT (account, token, tx_hash, direction, event_type) DO UPDATE SET ");
+        qb.push("amount = EXCLUDED.amount, ");
+        qb.push("tx_block = EXCLUDED.tx_block, ");
+        qb.push("block_time = EXCLUDED.block_time");
+        let batch_res = qb.build().execute(&mut *conn).await?;
+        rows_written += batch_res.rows_affected();
+    }
+
+    let took_ms = t0.elapsed().as_millis();
+    if quarantined > 0 {
+        tracing::warn!(from, to, quarantined, "some logs were quarantined");
+    }
+
+    Ok(Stats {
+        logs_found: logs_found + quarantined,
+        rows_written,
+        from_block: from,
+        to_block: to,
+        took_ms,
+    })
+}
+
+/// Accounts moved by a Comet log. Base-asset events are in `base` units,
+/// collateral ones in their `asset`.
+fn flows_of(log: &Log, base: Address) -> Result<Vec<Flow>, BadLog> {
+    use Direction::{In, Out};
+
+    let flow = |account, token, amount, direction, event_type| Flow { account, token, amount, direction, event_type };
+    let topic = log.topic0().copied().unwrap_or_default();
+
+    let flows = if topic == Supply::SIGNATURE_HASH {
+        let ev = decode::<Supply>(log)?;
+        vec![flow(ev.dst, base, ev.amount, In, EventType::Supply)]
+    } else if topic == Withdraw::SIGNATURE_HASH {
+        let ev = decode::<Withdraw>(log)?;
+        vec![flow(ev.src, base, ev.amount, Out, EventType::Withdraw)]
+    } else if topic == Transfer::SIGNATURE_HASH {
+        let ev = decode::<Transfer>(log)?;
+        // Mints and burns mirror a Supply or Withdraw of the same tx
+        if ev.from.is_zero() || ev.to.is_zero() {
+            vec![]
+        } else {
+            vec![
+                flow(ev.from, base, ev.amount, Out, EventType::Transfer),
+                flow(ev.to, base, ev.amount, In, EventType::Transfer),
+            ]
+        }
+    } else if topic == SupplyCollateral::SIGNATURE_HASH {
+        let ev = decode::<SupplyCollateral>(log)?;
+        vec![flow(ev.dst, ev.asset, ev.amount, In, EventType::SupplyCollateral)]
+    } else if topic == WithdrawCollateral::SIGNATURE_HASH {
+        let ev = decode::<WithdrawCollateral>(log)?;
+        vec![flow(ev.src, ev.asset, ev.amount, Out, EventType::WithdrawCollateral)]
+    } else if topic == TransferCollateral::SIGNATURE_HASH {
+        let ev = decode::<TransferCollateral>(log)?;
+        vec![
+            flow(ev.from, ev.asset, ev.amount, Out, EventType::TransferCollateral),
+            flow(ev.to, ev.asset, ev.amount, In, EventType::TransferCollateral),
+        ]
+    } else if topic == AbsorbDebt::SIGNATURE_HASH {
+        // The protocol repays the borrower's debt
+        let ev = decode::<AbsorbDebt>(log)?;
+        vec![flow(ev.borrower, base, ev.basePaidOut, In, EventType::AbsorbDebt)]
+    } else if topic == AbsorbCollateral::SIGNATURE_HASH {
+        // ...and takes the collateral that backed it
+        let ev = decode::<AbsorbCollateral>(log)?;
+        vec![flow(ev.borrower, ev.asset, ev.collateralAbsorbed, Out, EventType::AbsorbCollateral)]
+    } else {
+        vec![]
+    };
+    Ok(flows)
+}
+
+fn decode<E: SolEvent>(log: &Log) -> Result<E, BadLog> {
+    log.log_decode::<E>()
+        .map(|l| l.inner.data)
+        .map_err(|source| BadLog { event: E::SIGNATURE, source })
+}
+
+#[cfg(test)]
+mod tests {
+    use indexer_core::testing::log;
+
+    use super::*;
+
+    #[test]
+    fn absorbs_repay_debt_and_take_collateral() {
+        let (base, asset, borrower) = (Address::repeat_byte(2), Address::repeat_byte(3), Address::repeat_byte(4));
+        let absorber = Address::repeat_byte(5);
+
+        let debt = AbsorbDebt { absorber, borrower, basePaidOut: U256::from(10), usdValue: U256::from(10) };
+        let flows = flows_of(&log(Address::repeat_byte(1), debt.encode_log_data()), base).unwrap();
+        assert_eq!(flows.len(), 1);
+        let f = &flows[0];
+        assert_eq!((f.account, f.token, f.amount, f.direction), (borrower, base, U256::from(10), Direction::In));
+
+        let collateral = AbsorbCollateral {
+            absorber,
+            borrower,
+            asset,
+            collateralAbsorbed: U256::from(3),
+            usdValue: U256::from(12),
+        };
+        let flows = flows_of(&log(Address::repeat_byte(1), collateral.encode_log_data()), base).unwrap();
+        assert_eq!(flows.len(), 1);
+        let f = &flows[0];
+        assert_eq!((f.account, f.token, f.amount, f.direction), (borrower, asset, U256::from(3), Direction::Out));
+        assert_eq!(f.event_type.as_str(), "absorb_collateral");
+    }
+}
@@ -0,0 +1,429 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::{Address, TxHash, U256},
+    rpc::types::{Log, TransactionReceipt},
+};
+use async_trait::async_trait;
+use eyre::{Result, ensure};
+use futures_util::try_join;
+use indexer_core::blocks::{self, BlockTimestamps};
+use indexer_core::quarantine::{self, Quarantinable};
+use indexer_core::resolved;
+use indexer_core::strategies::{ChunkProcessor, Stats};
+
+use super::VaultFlows;
+use sqlx::{PgConnection, PgPool, QueryBuilder};
+
+use crate::contracts::{
+    StCelo::{self, Transfer},
+    StCeloManager,
+};
+
+#[derive(Clone, Copy, Debug)]
+enum Direction {
+    In,
+    Out,
+}
+impl Direction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Direction::In => "in",
+            Direction::Out => "out",
+        }
+    }
+
+    /// Stored in `event_type`; stCELO flows are deposits or withdrawals.
+    fn event_type(self) -> &'static str {
+        match self {
+            Direction::In => "deposit",
+            Direction::Out => "withdraw",
+        }
+    }
+}
+
+const STRATEGY: &str = "vaults_transactions_stcelo";
+const RECEIPT_BATCH_SIZE: usize = 100;
+
+
+enum Event {
+    Deposit(Transfer, Log),
+    Withdraw(Transfer, Log),
+}
+
+impl Event {
+    fn log(&self) -> &Log {
+        match self {
+            Event::Deposit(_, log) | Event::Withdraw(_, log) => log,
+        }
+    }
+}
+
+/// Why a stCELO transfer could not be turned into a vault row.
+#[derive(Debug, thiserror::Error)]
+enum StCeloError {
+    #[error("log has no transaction hash")]
+    NoTxHash,
+    #[error("receipt for {0} not found")]
+    MissingReceipt(TxHash),
+    #[error("no VotesScheduled event in deposit {0}")]
+    MissingVotesScheduled(TxHash),
+    #[error("{0} mints stCELO more than once, its VotesScheduled cannot be told apart")]
+    SeveralDeposits(TxHash),
+    #[error("no CeloWithdrawalScheduled event for {beneficiary} in withdrawal {tx}")]
+    MissingWithdrawalScheduled { tx: TxHash, beneficiary: Address },
+}
+
+impl Quarantinable for StCeloError {
+    fn kind(&self) -> &'static str {
+        match self {
+            StCeloError::NoTxHash => "missing_tx_hash",
+            StCeloError::MissingReceipt(_) => "missing_receipt",
+            StCeloError::MissingVotesScheduled(_) => "missing_votes_scheduled",
+            StCeloError::SeveralDeposits(_) => "several_deposits",
+            StCeloError::MissingWithdrawalScheduled { .. } => "missing_withdrawal_scheduled",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct VaultsTransactionsStCeloManagerProcessor {
+    /// stCELO token.
+    pub address: Address,
+    /// StCeloManager: withdrawn stCELO is sent to it; deposits emit its
+    /// `VotesScheduled`, withdrawals its `CeloWithdrawalScheduled`.
+    pub manager: Address,
+    /// Accounts table whose members' transactions are kept.
+    pub accounts_table: String,
+    /// Set once the manager's `stakedCelo()` matched `address`.
+    checked: Arc<tokio::sync::OnceCell<()>>,
+}
+
+impl VaultsTransactionsStCeloManagerProcessor {
+    pub fn new(address: Address, manager: Address, accounts_table: String) -> Self {
+        Self { address, manager, accounts_table, checked: Arc::default() }
+    }
+
+    /// A manager of another deployment (e.g. Alfajores vs mainnet) would
+    /// silently match nothing. The answer is kept in `resolved_tokens`, so
+    /// offline replays need no `eth_call`.
+    async fn check_manager<P>(&self, provider: &P, db: &PgPool) -> Result<()>
+    where
+        P: alloy::providers::Provider + Clone + Send + Sync + 'static,
+    {
+        self.checked
+            .get_or_try_init(|| async {
+                let token = resolved::resolve(db, self.manager, "stakedCelo", || async {
+                    Ok(StCeloManager::new(self.manager, provider).stakedCelo().call().await?)
+                })
+                .await?;
+                ensure!(
+                    token == self.address,
+                    "stCELO manager {} manages token {token}, but the configured stCELO token is {}",
+                    self.manager,
+                    self.address
+                );
+                Ok(())
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<P: alloy::providers::Provider + Clone + Send + Sync + 'static> ChunkProcessor<P>
+    for VaultsTransactionsStCeloManagerProcessor
+{
+    async fn process(&self, provider: P, db: &PgPool, from: u64, to: u64) -> Result<Stats> {
+        let mut tx = db.begin().await?;
+        let stats = process_vaults_transactions_chunk(provider, db, &mut tx, self, from, to).await?;
+        tx.commit().await?;
+        Ok(stats)
+    }
+
+    async fn rollback(&self, db: &PgPool, to_block: u64) -> Result<u64> {
+        // Compound also writes `withdraw`; only this token's rows are ours
+        let res = sqlx::query(
+            "DELETE FROM vaults_transactions
+             WHERE tx_block > $1 AND event_type IN ('deposit', 'withdraw') AND lower(token) = $2",
+        )
+        .bind(to_block as i64)
+        .bind(format!("{:#x}", self.address))
+        .execute(db)
+        .await?;
+        Ok(res.rows_affected())
+    }
+
+    fn box_clone(&self) -> Box<dyn ChunkProcessor<P> + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+#[async_trait]
+impl<P: alloy::providers::Provider + Clone + Send + Sync + 'static> VaultFlows<P>
+    for VaultsTransactionsStCeloManagerProcessor
+{
+    async fn process_in(&self, provider: P, db: &PgPool, conn: &mut PgConnection, from: u64, to: u64) -> Result<Stats> {
+        process_vaults_transactions_chunk(provider, db, conn, self, from, to).await
+    }
+}
+
+pub async fn process_vaults_transactions_chunk<P>(
+    provider: P,
+    db: &PgPool,
+    conn: &mut PgConnection,
+    processor: &VaultsTransactionsStCeloManagerProcessor,
+    from: u64,
+    to: u64,
+) -> Result<Stats>
+where
+    P: alloy::providers::Provider + Clone + Send + Sync + 'static,
+{
+    processor.check_manager(&provider, db).await?;
+    let VaultsTransactionsStCeloManagerProcessor { address, manager, accounts_table, .. } = processor;
+    let st_celo_contract = StCelo::new(*address, provider.clone());
+
+    let t0 = std::time::Instant::now();
+
+    tracing::info!(from = from, to = to, "processing event range");
+    let supply_filter = st_celo_contract
+        .Transfer_filter()
+        .from_block(BlockNumberOrTag::Number(from))
+        .to_block(BlockNumberOrTag::Number(to))
+        .topic1(Address::ZERO);
+    let withdraw_filter = st_celo_contract
+        .Transfer_filter()
+        .from_block(BlockNumberOrTag::Number(from))
+        .to_block(BlockNumberOrTag::Number(to))
+        .topic2(*manager);
+
+    let (supply_logs, withdraw_logs) = try_join!(supply_filter.query(), withdraw_filter.query())?;
+
+
+    let all_logs: Vec<Event> = supply_logs
+        .into_iter()
+        .map(|(ev, log)| Event::Deposit(ev, log))
+        .chain(
+            withdraw_logs
+                .into_iter()
+                .map(|(ev, log)| Event::Withdraw(ev, log)),
+        )
+        .collect();
+
+    if all_logs.is_empty() {
+        tracing::info!(from = from, to = to, "no logs found in range");
+        return Ok(Stats::default());
+    }
+
+    let mut dsts: Vec<String> = all_logs
+        .iter()
+        .map(|event| match event {
+            Event::Deposit(ev, _) => format!("{:#x}", ev.to).to_lowercase(),
+            Event::Withdraw(ev, _) => format!("{:#x}", ev.from).to_lowercase(),
+        })
+        .collect();
+    dsts.sort_unstable();
+    dsts.dedup();
+
+    // 2) Pide a la DB cuáles 'dst' existen en la tabla de cuentas
+    let existing: Vec<String> = sqlx::query_scalar(&format!(
+        "SELECT account FROM {accounts_table} WHERE lower(account) = ANY($1::text[])"
+    ))
+    .bind(&dsts)
+    .fetch_all(db)
+    .await?;
+
+    tracing::info!(matches = existing.len(), table = accounts_table, "account matches");
+
+    let existing_set: HashSet<String> = existing.into_iter().map(|s| s.to_lowercase()).collect();
+
+    let filtered_logs: Vec<_> = all_logs
+        .into_iter()
+        .filter(|event| match event {
+            Event::Deposit(ev, _) => {
+                let d = format!("{:#x}", ev.to).to_lowercase();
+                existing_set.contains(&d)
+            }
+            Event::Withdraw(ev, _) => {
+                let d = format!("{:#x}", ev.from).to_lowercase();
+                existing_set.contains(&d)
+            }
+        })
+        .collect();
+
+    if filtered_logs.is_empty() {
+        tracing::info!(from = from, to = to, "no valid logs found in range");
+        return Ok(Stats::default());
+    }
+
+    let mut timestamps = BlockTimestamps::new(&provider);
+
+    struct Row {
+        account_hex: String, // TEXT "0x..."
+        token_hex: String,
+        amount: sqlx::types::BigDecimal,
+        direction: Direction,
+        txhash_hex: String,
+        txblock: i64,
+        block_time: chrono::DateTime<chrono::Utc>,
+    }
+
+    // Todos los receipts del chunk en batches JSON-RPC
+    let receipts = blocks::receipts(
+        &provider,
+        filtered_logs.iter().filter_map(|e| e.log().transaction_hash),
+        RECEIPT_BATCH_SIZE,
+    )
+    .await?;
+
+    quarantine::release(db, STRATEGY, from, to).await?;
+
+    let mut rows: Vec<Row> = Vec::with_capacity(filtered_logs.len());
+    let mut quarantined = 0usize;
+
+    for event in filtered_logs {
+        let amount = match &event {
+            Event::Deposit(_, log) => deposit_amount(*manager, log, &receipts),
+            Event::Withdraw(ev, log) => withdraw_amount(*manager, ev, log, &receipts),
+        };
+        let (direction, account_hex, log) = match event {
+            Event::Deposit(ev, log) => (Direction::In, format!("{:#x}", ev.to), log),
+            Event::Withdraw(ev, log) => (Direction::Out, format!("{:#x}", ev.from), log),
+        };
+        let amount = match amount {
+            Ok(a) => a.to_string(),
+            Err(e) => {
+                quarantine::quarantine(db, STRATEGY, &log, &e).await?;
+                quarantined += 1;
+                continue;
+            }
+        };
+        rows.push(Row {
+            account_hex,
+            token_hex: format!("{address:#x}"),
+            amount: amount.parse()?,
+            direction,
+            txhash_hex: log
+                .transaction_hash
+                .map(|h| format!("{:#x}", h))
+                .unwrap_or_default(),
+            txblock: log.block_number.map(|b| b as i64).unwrap_or_default(),
+            block_time: timestamps.for_log(&log).await?,
+        })
+    }
+
+    if rows.is_empty() {
+        tracing::warn!(from, to, quarantined, "every log in range was quarantined");
+        return Ok(Stats::default());
+    }
+
+    // Process in batches to avoid parameter limit (8 columns per row, max 65535 params)
+    let mut rows_written = 0u64;
+    const BATCH_SIZE: usize = 500;
+
+    for chunk in rows.chunks(BATCH_SIZE) {
+        let mut qb: QueryBuilder<'_, sqlx::Postgres> = QueryBuilder::new(
+            "INSERT INTO vaults_transactions (
+                account, token, amount, direction, event_type, tx_hash, tx_block, block_time
+            ) ",
+        );
+        qb.push_values(chunk.iter(), |mut b, row| {
+            b.push_bind(&row.account_hex)
+                .push_bind(&row.token_hex)
+                .push_bind(&row.amount)
+                .push_bind(row.direction.as_str())
+                .push_bind(row.direction.event_type())
+                .push_bind(&row.txhash_hex)
+                .push_bind(row.txblock)
+                .push_bind(row.block_time);
+        });
+        qb.push(
+            " ON CONFLICT (account, token, tx_hash, direction, event_type) DO UPDATE SET
+            amount = EXCLUDED.amount,
+            tx_block = EXCLUDED.tx_block,
+            block_time = EXCLUDED.block_time",
+        );
+        rows_written += qb.build().execute(&mut *conn).await?.rows_affected();
+    }
+    let took_ms = t0.elapsed().as_millis();
+
+    if quarantined > 0 {
+        tracing::warn!(from, to, quarantined, "some logs were quarantined");
+    }
+
+    Ok(Stats {
+        logs_found: rows.len() + quarantined,
+        rows_written,
+        from_block: from,
+        to_block: to,
+        took_ms,
+    })
+}
+
+fn receipt_for<'a>(
+    log: &Log,
+    receipts: &'a HashMap<TxHash, Option<TransactionReceipt>>,
+) -> Result<&'a TransactionReceipt, StCeloError> {
+    let tx = log.transaction_hash.ok_or(StCeloError::NoTxHash)?;
+    receipts
+        .get(&tx)
+        .and_then(Option::as_ref)
+        .ok_or(StCeloError::MissingReceipt(tx))
+}
+
+/// Un depósito mintea stCELO; el CELO depositado está en los `VotesScheduled`
+/// del manager (uno por grupo de validadores). Con varios mints en la misma tx
+/// no se sabe qué votos son de cada uno, así que va a cuarentena.
+fn deposit_amount(
+    manager: Address,
+    log: &Log,
+    receipts: &HashMap<TxHash, Option<TransactionReceipt>>,
+) -> Result<U256, StCeloError> {
+    let receipt = receipt_for(log, receipts)?;
+    let mints = receipt
+        .logs()
+        .iter()
+        .filter(|l| l.address() == log.address())
+        .filter_map(|l| l.log_decode::<Transfer>().ok())
+        .filter(|l| l.inner.data.from == Address::ZERO)
+        .count();
+    if mints > 1 {
+        return Err(StCeloError::SeveralDeposits(receipt.transaction_hash));
+    }
+    let scheduled: Vec<U256> = receipt
+        .logs()
+        .iter()
+        .filter(|l| l.address() == manager)
+        .filter_map(|l| l.log_decode::<StCeloManager::VotesScheduled>().ok())
+        .map(|l| l.inner.data.amount)
+        .collect();
+    if scheduled.is_empty() {
+        return Err(StCeloError::MissingVotesScheduled(receipt.transaction_hash));
+    }
+    Ok(scheduled.into_iter().fold(U256::ZERO, U256::saturating_add))
+}
+
+/// Un retiro quema stCELO; el CELO retirado está en los `CeloWithdrawalScheduled`
+/// del manager para quien retira (uno por grupo de validadores).
+fn withdraw_amount(
+    manager: Address,
+    ev: &Transfer,
+    log: &Log,
+    receipts: &HashMap<TxHash, Option<TransactionReceipt>>,
+) -> Result<U256, StCeloError> {
+    let receipt = receipt_for(log, receipts)?;
+    let scheduled: Vec<U256> = receipt
+        .logs()
+        .iter()
+        .filter(|l| l.address() == manager)
+        .filter_map(|l| l.log_decode::<StCeloManager::CeloWithdrawalScheduled>().ok())
+        .filter(|l| l.inner.data.beneficiary == ev.from)
+        .map(|l| l.inner.data.amount)
+        .collect();
+    if scheduled.is_empty() {
+        return Err(StCeloError::MissingWithdrawalScheduled { tx: receipt.transaction_hash, beneficiary: ev.from });
+    }
+    Ok(scheduled.into_iter().fold(U256::ZERO, U256::saturating_add))
+}
@@ -1,10 +0,0 @@
-mod st_celo;
-mod st_celo_manager;
-mod celo;
-mod super_chain_module;
-mod super_chain_badges;
-
-pub use st_celo::StCelo;
-pub use st_celo_manager::StCeloManager;
-pub use super_chain_module::SuperChainModule;
-pub use super_chain_badges::SuperChainBadges;
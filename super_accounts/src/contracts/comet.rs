@@ -1,22 +0,0 @@
-use alloy::sol;
-
-sol!(
-    #[sol(rpc)]
-    contract Comet {
-
-        #[derive(Debug)]
-        event Supply(
-            address indexed from,
-            address indexed dst,
-            uint amount
-        );
-
-        #[derive(Debug)]
-        event Withdraw(
-            address indexed src,
-            address indexed to,
-            uint amount
-        );
-
-    }
-);
@@ -1,10 +0,0 @@
-mod badges_minted;
-mod super_account_created;
-mod vaults_transactions_compound;
-mod owner_added;
-
-pub use badges_minted::SuperChainBadgesMintedProccesor;
-pub use super_account_created::SuperAccountCreatedProcessor;
-pub use vaults_transactions_compound::VaultsTransactionsCompoundProcessor;
-pub use owner_added::OwnerAddedProcessor;
-
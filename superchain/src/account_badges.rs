@@ -0,0 +1,78 @@
+//! Current state of every `(account, badge_id)`: the latest tier, points and
+//! URI, derived from the claims table.
+//!
+//! Claims are history (`BadgeMinted` and `BadgeTierUpdated` alike); the latest
+//! one by block and log index wins, so chunks reprocessed out of order cannot
+//! move a badge back to an older tier.
+
+use eyre::Result;
+use sqlx::PgConnection;
+
+#[derive(Clone, Debug)]
+pub struct AccountBadges {
+    /// Current state table, `account_badges` in the bundled migrations.
+    pub table: String,
+    /// Claims table, `badge_claims` in the bundled migrations.
+    pub claims_table: String,
+}
+
+impl AccountBadges {
+    pub fn new(table: impl Into<String>, claims_table: impl Into<String>) -> Self {
+        Self { table: table.into(), claims_table: claims_table.into() }
+    }
+
+    /// Updates the given `(account, badge_id)` pairs from their claims.
+    pub async fn refresh(&self, conn: &mut PgConnection, keys: &[(String, i32)]) -> Result<u64> {
+        if keys.is_empty() {
+            return Ok(0);
+        }
+        let accounts: Vec<&str> = keys.iter().map(|(a, _)| a.as_str()).collect();
+        let badges: Vec<i32> = keys.iter().map(|(_, b)| *b).collect();
+        let res = sqlx::query(&self.upsert_sql(
+            "WHERE (account, badge_id) IN (SELECT * FROM UNNEST($1::text[], $2::int[]))",
+        ))
+        .bind(&accounts)
+        .bind(&badges)
+        .execute(conn)
+        .await?;
+        Ok(res.rows_affected())
+    }
+
+    /// Rebuilds the whole table from the claims, dropping pairs that no
+    /// longer have any (e.g. after a rollback). Run it in the transaction
+    /// that changed the claims, so readers never see them disagree.
+    pub async fn rebuild(&self, conn: &mut PgConnection) -> Result<u64> {
+        let (table, claims) = (&self.table, &self.claims_table);
+        let removed = sqlx::query(&format!(
+            "DELETE FROM {table} AS s
+             WHERE NOT EXISTS (SELECT 1 FROM {claims} AS c WHERE c.account = s.account AND c.badge_id = s.badge_id)"
+        ))
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+        let updated = sqlx::query(&self.upsert_sql("")).execute(&mut *conn).await?.rows_affected();
+        Ok(removed + updated)
+    }
+
+    fn upsert_sql(&self, filter: &str) -> String {
+        let (table, claims) = (&self.table, &self.claims_table);
+        format!(
+            "INSERT INTO {table} AS s (account, badge_id, tier, points, uri, last_block_number, last_log_index, last_tx_hash)
+             SELECT DISTINCT ON (account, badge_id)
+                    account, badge_id, tier, points, uri, block_number, log_index, tx_hash
+             FROM {claims} {filter}
+             ORDER BY account, badge_id, block_number DESC, log_index DESC NULLS LAST, tier DESC
+             ON CONFLICT (account, badge_id) DO UPDATE SET
+                 tier = EXCLUDED.tier,
+                 points = EXCLUDED.points,
+                 uri = EXCLUDED.uri,
+                 last_block_number = EXCLUDED.last_block_number,
+                 last_log_index = EXCLUDED.last_log_index,
+                 last_tx_hash = EXCLUDED.last_tx_hash
+             WHERE (s.tier, s.points, s.uri, s.last_block_number, s.last_log_index, s.last_tx_hash)
+                 IS DISTINCT FROM
+                 (EXCLUDED.tier, EXCLUDED.points, EXCLUDED.uri, EXCLUDED.last_block_number,
+                  EXCLUDED.last_log_index, EXCLUDED.last_tx_hash)"
+        )
+    }
+}
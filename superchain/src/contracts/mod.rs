@@ -1,7 +1,7 @@
-mod comet;
+mod safe;
 mod super_chain_badges;
 mod super_chain_module;
 
-pub use comet::Comet;
+pub use safe::{Safe, address_arg};
 pub use super_chain_badges::SuperChainBadges;
 pub use super_chain_module::SuperChainModule;
@@ -0,0 +1,25 @@
+use alloy::primitives::{Address, B256};
+use alloy::rpc::types::Log;
+use alloy::sol;
+
+// Safe 1.3 emits the address events with the address in data, 1.4 with it
+// indexed; the signature is the same, so decode those with `address_arg`.
+sol!(
+    contract Safe {
+        event AddedOwner(address indexed owner);
+        event RemovedOwner(address indexed owner);
+        event EnabledModule(address indexed module);
+        event DisabledModule(address indexed module);
+        event ChangedThreshold(uint256 threshold);
+        event SafeSetup(address indexed initiator, address[] owners, uint256 threshold, address initializer, address fallbackHandler);
+    }
+);
+
+/// Address argument of `AddedOwner`, `RemovedOwner`, `EnabledModule` or
+/// `DisabledModule`, indexed (Safe 1.4) or in data (1.3).
+pub fn address_arg(log: &Log) -> Option<Address> {
+    match log.topics() {
+        [_, arg, ..] => Some(Address::from_word(*arg)),
+        _ => log.data().data.get(..32).map(|w| Address::from_word(B256::from_slice(w))),
+    }
+}
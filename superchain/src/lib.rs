@@ -0,0 +1,55 @@
+//! Strategies for SuperChain deployments (`SuperChainModule` +
+//! `SuperChainBadges`), usable by any product built on `indexer_core`.
+//!
+//! Each processor takes the contract address and the table it writes to, so
+//! the same code serves every deployment:
+//!
+//! ```ignore
+//! let totals = Some(AccountTotals::new("users", "badge_claims"));
+//! let watched = WatchedAddresses::new("users", 500, 100_000);
+//! let strategies = vec![
+//!     StrategyConfig::new(
+//!         AccountCreatedProcessor {
+//!             address: module,
+//!             table: "users".into(),
+//!             totals: totals.clone(),
+//!             watched: Some(watched.clone()),
+//!         },
+//!         "account_created", 29_117_283, false,
+//!     ),
+//!     StrategyConfig::new(
+//!         OwnerAddedProcessor { address: module, table: "users".into(), watched: watched.clone() },
+//!         "owner_added", 29_117_140, false,
+//!     ),
+//!     StrategyConfig::new(
+//!         SafeSettingsProcessor { module, table: "users".into(), watched },
+//!         "safe_settings", 29_117_140, false,
+//!     ),
+//!     StrategyConfig::new(
+//!         SuperChainBadgesMintedProcessor {
+//!             address: badges,
+//!             table: "badge_claims".into(),
+//!             totals,
+//!             current: Some(AccountBadges::new("account_badges", "badge_claims")),
+//!         },
+//!         "badges_minted", 29_117_140, false,
+//!     ),
+//! ];
+//! ```
+//!
+//! The accounts and `badge_claims` tables are created by the `indexer_core`
+//! migrations. With `totals` set, `total_points`, `total_badges` and `level`
+//! follow the claims (see [`totals`]), and with `current` so does the latest
+//! tier of each badge (see [`account_badges`]); `None` leaves them alone.
+//! `eoas` is always derived from the owner history in `owner_changes` (see
+//! [`owners`]), and `threshold` / `module_enabled` from `safe_setting_changes`.
+
+pub mod account_badges;
+pub mod contracts;
+pub mod owners;
+pub mod strategies;
+pub mod totals;
+
+pub use account_badges::AccountBadges;
+pub use strategies::{AccountCreatedProcessor, OwnerAddedProcessor, SafeSettingsProcessor, SuperChainBadgesMintedProcessor};
+pub use totals::AccountTotals;
@@ -0,0 +1,149 @@
+//! Owner history of each account and the `eoas` column derived from it.
+//!
+//! Every addition and removal is kept in `owner_changes`; an owner is current
+//! when its latest change (by block and log index) is an addition. Strategies
+//! record the changes they see and refresh the accounts they touched in the
+//! same transaction.
+
+use eyre::Result;
+use sqlx::{PgConnection, PgPool, QueryBuilder};
+
+/// Additions and removals with the log that caused them.
+pub const OWNER_CHANGES_TABLE: &str = "owner_changes";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+}
+
+impl Change {
+    fn as_str(self) -> &'static str {
+        match self {
+            Change::Added => "added",
+            Change::Removed => "removed",
+        }
+    }
+}
+
+/// Where a change was seen, stored in `source`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    /// Initial owner in `SuperChainSmartAccountCreated`.
+    Created,
+    /// `OwnerAdded` of the SuperChainModule.
+    Module,
+    /// `AddedOwner` / `RemovedOwner` of the Safe itself.
+    Safe,
+}
+
+impl Source {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Source::Created => "created",
+            Source::Module => "module",
+            Source::Safe => "safe",
+        }
+    }
+}
+
+/// One owner change; addresses in lowercase hex.
+#[derive(Clone, Debug)]
+pub struct OwnerChange {
+    pub account: String,
+    pub owner: String,
+    pub change: Change,
+    pub source: Source,
+    pub block_number: i64,
+    pub log_index: i32,
+    pub tx_hash: String,
+}
+
+/// Writes `changes` to the history. Returns the number of new rows.
+pub async fn record(conn: &mut PgConnection, changes: &[OwnerChange]) -> Result<u64> {
+    const BATCH_SIZE: usize = 5_000;
+
+    let mut written = 0;
+    for chunk in changes.chunks(BATCH_SIZE) {
+        let mut qb = QueryBuilder::new(format!(
+            "INSERT INTO {OWNER_CHANGES_TABLE} (account, owner, change, source, block_number, log_index, tx_hash) "
+        ));
+        qb.push_values(chunk, |mut b, c| {
+            b.push_bind(&c.account)
+                .push_bind(&c.owner)
+                .push_bind(c.change.as_str())
+                .push_bind(c.source.as_str())
+                .push_bind(c.block_number)
+                .push_bind(c.log_index)
+                .push_bind(&c.tx_hash);
+        });
+        qb.push(" ON CONFLICT DO NOTHING");
+        written += qb.build().execute(&mut *conn).await?.rows_affected();
+    }
+    Ok(written)
+}
+
+/// Seeds the history with the `eoas` of accounts in `table` that have none
+/// (written before it existed), as additions at block 0 that any indexed
+/// change supersedes. Returns the number of changes written.
+pub async fn seed(db: &PgPool, table: &str) -> Result<u64> {
+    let res = sqlx::query(&format!(
+        "INSERT INTO {OWNER_CHANGES_TABLE} (account, owner, change, source, block_number, log_index, tx_hash)
+         SELECT lower(a.account), lower(e), 'added', 'migration', 0, -1, ''
+         FROM {table} AS a, unnest(a.eoas) AS e
+         WHERE NOT EXISTS (SELECT 1 FROM {OWNER_CHANGES_TABLE} AS c WHERE c.account = lower(a.account))
+         ON CONFLICT DO NOTHING"
+    ))
+    .execute(db)
+    .await?;
+    Ok(res.rows_affected())
+}
+
+/// Recomputes `eoas` of the given accounts (all of them with `None`) from the
+/// history, oldest owner first. Returns the number of accounts that changed.
+pub async fn refresh(conn: &mut PgConnection, table: &str, accounts: Option<&[String]>) -> Result<u64> {
+    let (filter_accounts, filter_changes) = match accounts {
+        Some(_) => ("AND a.account = ANY($1)", "WHERE account = ANY($1)"),
+        None => ("", ""),
+    };
+    let sql = format!(
+        "UPDATE {table} AS a
+         SET eoas = cur.owners
+         FROM (
+             SELECT a.account,
+                    COALESCE(array_agg(l.owner ORDER BY l.added_block, l.owner) FILTER (WHERE l.change = 'added'), '{{}}') AS owners
+             FROM {table} AS a
+             LEFT JOIN (
+                 SELECT DISTINCT ON (account, owner) account, owner, change,
+                        MIN(block_number) FILTER (WHERE change = 'added') OVER (PARTITION BY account, owner) AS added_block
+                 FROM {OWNER_CHANGES_TABLE} {filter_changes}
+                 ORDER BY account, owner, block_number DESC, log_index DESC
+             ) AS l ON l.account = a.account
+             WHERE EXISTS (SELECT 1 FROM {OWNER_CHANGES_TABLE} AS c WHERE c.account = a.account) {filter_accounts}
+             GROUP BY a.account
+         ) AS cur
+         WHERE a.account = cur.account AND a.eoas IS DISTINCT FROM cur.owners"
+    );
+    let query = sqlx::query(&sql);
+    let query = match accounts {
+        Some(accounts) => query.bind(accounts),
+        None => query,
+    };
+    Ok(query.execute(conn).await?.rows_affected())
+}
+
+/// Deletes changes from `sources` after `to_block` and recomputes every
+/// account. Returns the number of changes removed.
+pub async fn rollback(conn: &mut PgConnection, table: &str, sources: &[Source], to_block: u64) -> Result<u64> {
+    let sources: Vec<&str> = sources.iter().map(|s| s.as_str()).collect();
+    let removed = sqlx::query(&format!(
+        "DELETE FROM {OWNER_CHANGES_TABLE} WHERE block_number > $1 AND source = ANY($2)"
+    ))
+    .bind(to_block as i64)
+    .bind(&sources)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    refresh(conn, table, None).await?;
+    Ok(removed)
+}
@@ -1,27 +1,54 @@
-use std::borrow::Cow;
-
 use alloy::{
     eips::BlockNumberOrTag,
     primitives::Address,
 };
 use async_trait::async_trait;
 use eyre::{Ok, Result};
+use indexer_core::blocks::sanitize_text;
 use indexer_core::strategies::{ChunkProcessor, Stats};
+use indexer_core::watched::{WATCHED_ADDRESSES_TABLE, WatchedAddresses};
 use serde_json::json;
 use sqlx::{PgPool, QueryBuilder};
 
-use crate::config::super_account_module_addr;
 use crate::contracts::SuperChainModule;
+use crate::owners::{self, Change, OwnerChange, Source};
+use crate::totals::AccountTotals;
+
+/// Name this strategy registers addresses under, whatever the profile calls it.
+const STRATEGY: &str = "account_created";
 
 #[derive(Clone)]
-pub struct SuperAccountCreatedProcessor;
+pub struct AccountCreatedProcessor {
+    pub address: Address,
+    /// Accounts table of the profile (`super_accounts`, `users`).
+    pub table: String,
+    /// Fills in totals for accounts whose badges were indexed first, if any.
+    pub totals: Option<AccountTotals>,
+    /// Registers each new Safe, so strategies watching accounts see its events.
+    pub watched: Option<WatchedAddresses>,
+}
 
 #[async_trait]
 impl<P: alloy::providers::Provider + Clone + Send + Sync + 'static> ChunkProcessor<P>
-    for SuperAccountCreatedProcessor
+    for AccountCreatedProcessor
 {
     async fn process(&self, provider: P, db: &PgPool, from: u64, to: u64) -> Result<Stats> {
-        process_super_account_created_chunk(provider, db, from, to).await
+        process_account_created_chunk(provider, db, self, from, to).await
+    }
+
+    async fn rollback(&self, db: &PgPool, to_block: u64) -> Result<u64> {
+        let mut tx = db.begin().await?;
+        // Accounts created after `to_block` did not exist yet
+        let res = sqlx::query(&format!("DELETE FROM {} WHERE last_update_block_number > $1", self.table))
+            .bind(to_block as i64)
+            .execute(&mut *tx)
+            .await?;
+        owners::rollback(&mut tx, &self.table, &[Source::Created], to_block).await?;
+        if let Some(watched) = &self.watched {
+            watched.remove_after(&mut tx, STRATEGY, to_block).await?;
+        }
+        tx.commit().await?;
+        Ok(res.rows_affected())
     }
 
     fn box_clone(&self) -> Box<dyn ChunkProcessor<P> + Send + Sync> {
@@ -29,16 +56,42 @@ impl<P: alloy::providers::Provider + Clone + Send + Sync + 'static> ChunkProcess
     }
 }
 
-pub async fn process_super_account_created_chunk<P>(
+/// Registers the accounts already in `table` that `watched` does not know
+/// (written before the registry existed), from the block they were last
+/// updated at, or the table's earliest one when unknown. A table without
+/// any block registers nothing: scanning from genesis is never right.
+pub(crate) async fn watch_existing(db: &PgPool, table: &str, watched: &WatchedAddresses) -> Result<u64> {
+    let res = sqlx::query(&format!(
+        "INSERT INTO {WATCHED_ADDRESSES_TABLE} (registry, address, from_block, added_by)
+         SELECT $1, lower(a.account), a.from_block, 'existing'
+         FROM (
+             SELECT account, COALESCE(
+                 last_update_block_number,
+                 (SELECT MIN(last_update_block_number) FROM {table})
+             ) AS from_block
+             FROM {table}
+         ) AS a
+         WHERE a.from_block IS NOT NULL
+         ON CONFLICT DO NOTHING"
+    ))
+    .bind(&watched.registry)
+    .execute(db)
+    .await?;
+    Ok(res.rows_affected())
+}
+
+pub async fn process_account_created_chunk<P>(
     provider: P,
     db: &PgPool,
+    processor: &AccountCreatedProcessor,
     from: u64,
     to: u64,
 ) -> Result<Stats>
 where
     P: alloy::providers::Provider + Clone + Send + Sync + 'static,
 {
-    let super_chain_module_addr: Address = super_account_module_addr();
+    let super_chain_module_addr = processor.address;
+    let table = processor.table.as_str();
     let contract = SuperChainModule::new(super_chain_module_addr, provider.clone());
     let t0 = std::time::Instant::now();
 
@@ -46,8 +99,8 @@ where
 
     let logs = contract
         .SuperChainSmartAccountCreated_filter()
-        .from_block(BlockNumberOrTag::Number(from.into()))
-        .to_block(BlockNumberOrTag::Number(to.into()))
+        .from_block(BlockNumberOrTag::Number(from))
+        .to_block(BlockNumberOrTag::Number(to))
         .query()
         .await?;
 
@@ -56,12 +109,14 @@ where
         return Ok(Stats::default());
     }
     struct Row {
+        safe: Address,
         account_hex: String,
         username: String,
         eoas: Vec<String>,
         noun_json: serde_json::Value,
         last_update_block_number: Option<i32>,
         last_update_tx_hash: Option<String>,
+        log_index: Option<u64>,
     }
 
     let mut rows = Vec::with_capacity(logs.len());
@@ -91,12 +146,14 @@ where
         });
 
         rows.push(Row {
+            safe: event.safe,
             account_hex: format!("{:#x}", event.safe),
             username: username_cow.into_owned(),
             eoas: vec![format!("{:#x}", event.initialOwner)],
             noun_json,
             last_update_block_number: raw_log.block_number.map(|b| b as i32),
             last_update_tx_hash: raw_log.transaction_hash.map(|h| format!("{:#x}", h)),
+            log_index: raw_log.log_index,
         });
     }
 
@@ -104,14 +161,15 @@ where
     let mut rows_written = 0u64;
     const BATCH_SIZE: usize = 500;
 
+    let mut tx = db.begin().await?;
     for chunk in rows.chunks(BATCH_SIZE) {
-        let mut qb = QueryBuilder::new(
-            "INSERT INTO super_accounts (
+        let mut qb = QueryBuilder::new(format!(
+            "INSERT INTO {table} (
                 account, nationality, username, eoas, level,
                 noun, total_points, total_badges,
                 last_update_block_number, last_update_tx_hash
-            ) ",
-        );
+            ) "
+        ));
 
         qb.push_values(chunk.iter(), |mut b, r| {
             b.push_bind(&r.account_hex)
@@ -127,14 +185,40 @@ where
         });
         qb.push(" ON CONFLICT (account) DO UPDATE SET ");
         qb.push("username = EXCLUDED.username, ");
-        qb.push("eoas = EXCLUDED.eoas, ");
         qb.push("noun = EXCLUDED.noun, ");
         qb.push("last_update_block_number = EXCLUDED.last_update_block_number, ");
         qb.push("last_update_tx_hash = EXCLUDED.last_update_tx_hash");
 
-        let batch_res = qb.build().execute(db).await?;
+        let batch_res = qb.build().execute(&mut *tx).await?;
         rows_written += batch_res.rows_affected();
     }
+    // The initial owner goes to the history; eoas of accounts seen before
+    // (e.g. by owner_added) is derived from it instead of overwritten
+    let changes: Vec<OwnerChange> = rows
+        .iter()
+        .map(|r| OwnerChange {
+            account: r.account_hex.clone(),
+            owner: r.eoas[0].clone(),
+            change: Change::Added,
+            source: Source::Created,
+            block_number: r.last_update_block_number.unwrap_or(0) as i64,
+            log_index: r.log_index.unwrap_or_default() as i32,
+            tx_hash: r.last_update_tx_hash.clone().unwrap_or_default(),
+        })
+        .collect();
+    owners::record(&mut tx, &changes).await?;
+    let accounts: Vec<String> = rows.iter().map(|r| r.account_hex.clone()).collect();
+    owners::refresh(&mut tx, table, Some(&accounts)).await?;
+    if let Some(watched) = &processor.watched {
+        let created: Vec<(Address, u64)> =
+            rows.iter().map(|r| (r.safe, r.last_update_block_number.unwrap_or(0) as u64)).collect();
+        watched.add(&mut tx, &created, STRATEGY).await?;
+    }
+    if let Some(totals) = &processor.totals {
+        // Claims made before the account existed cross their levels too
+        totals.refresh(&mut tx, &accounts, 0).await?;
+    }
+    tx.commit().await?;
 
     let took_ms = t0.elapsed().as_millis();
     tracing::info!(
@@ -153,24 +237,3 @@ where
         took_ms,
     })
 }
-
-fn sanitize_text(s: &str) -> (Cow<'_, str>, usize) {
-    let mut nul_count = 0usize;
-    let cleaned: String = s
-        .chars()
-        .filter(|&ch| {
-            if ch == '\0' {
-                nul_count += 1;
-                return false;
-            }
-            let code = ch as u32;
-            !(code < 0x20 && ch != '\n' && ch != '\r' && ch != '\t')
-        })
-        .collect();
-
-    if nul_count == 0 && cleaned.len() == s.len() {
-        (Cow::Borrowed(s), 0)
-    } else {
-        (Cow::Owned(cleaned), nul_count)
-    }
-}
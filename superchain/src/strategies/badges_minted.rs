@@ -0,0 +1,319 @@
+use alloy::{eips::BlockNumberOrTag, primitives::Address, rpc::types::Log};
+use async_trait::async_trait;
+use eyre::{Ok, Result};
+use futures_util::future::try_join;
+use indexer_core::blocks::{BlockTimestamps, sanitize_text};
+use indexer_core::strategies::{ChunkProcessor, Stats};
+use sqlx::{PgPool, QueryBuilder};
+
+use crate::account_badges::AccountBadges;
+use crate::contracts::SuperChainBadges::{self, BadgeMinted, BadgeTierUpdated};
+use crate::totals::{AccountTotals, LEVEL_UPS_TABLE};
+
+#[derive(Clone)]
+pub struct SuperChainBadgesMintedProcessor {
+    pub address: Address,
+    /// Claims table, `badge_claims` in the bundled migrations.
+    pub table: String,
+    /// Account aggregates kept in step with the claims, if any.
+    pub totals: Option<AccountTotals>,
+    /// Latest claim per `(account, badge_id)`, if kept.
+    pub current: Option<AccountBadges>,
+}
+
+#[async_trait]
+impl<P: alloy::providers::Provider + Clone + Send + Sync + 'static> ChunkProcessor<P>
+    for SuperChainBadgesMintedProcessor
+{
+    async fn process(&self, provider: P, db: &PgPool, from: u64, to: u64) -> Result<Stats> {
+        process_badges_minted_chunk(provider, db, self, from, to).await
+    }
+
+    async fn rollback(&self, db: &PgPool, to_block: u64) -> Result<u64> {
+        // Claims and what derives from them move back together
+        let mut tx = db.begin().await?;
+        let res = sqlx::query(&format!("DELETE FROM {} WHERE block_number > $1", self.table))
+            .bind(to_block as i64)
+            .execute(&mut *tx)
+            .await?;
+        if let Some(totals) = &self.totals {
+            sqlx::query(&format!("DELETE FROM {LEVEL_UPS_TABLE} WHERE block_number > $1"))
+                .bind(to_block as i64)
+                .execute(&mut *tx)
+                .await?;
+            totals.recompute(&mut tx).await?;
+        }
+        if let Some(current) = &self.current {
+            current.rebuild(&mut tx).await?;
+        }
+        tx.commit().await?;
+        Ok(res.rows_affected())
+    }
+
+    async fn repair(&self, provider: P, db: &PgPool) -> Result<u64> {
+        repair_claimed_at(provider, db, &self.table).await
+    }
+
+    async fn prepare(&self, db: &PgPool) -> Result<()> {
+        if let Some(totals) = &self.totals {
+            totals.apply_thresholds(db).await?;
+        }
+        Ok(())
+    }
+
+    async fn rebuild(&self, db: &PgPool) -> Result<u64> {
+        let mut tx = db.begin().await?;
+        let mut updated = 0;
+        if let Some(current) = &self.current {
+            updated += current.rebuild(&mut tx).await?;
+        }
+        if let Some(totals) = &self.totals {
+            updated += totals.recompute(&mut tx).await?;
+        }
+        tx.commit().await?;
+        Ok(updated)
+    }
+
+    fn box_clone(&self) -> Box<dyn ChunkProcessor<P> + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+pub async fn process_badges_minted_chunk<P>(
+    provider: P,
+    db: &PgPool,
+    processor: &SuperChainBadgesMintedProcessor,
+    from: u64,
+    to: u64,
+) -> Result<Stats>
+where
+    P: alloy::providers::Provider + Clone + Send + Sync + 'static,
+{
+    let super_chain_badges_addr = processor.address;
+    let table = &processor.table;
+    let contract = SuperChainBadges::new(super_chain_badges_addr, provider.clone());
+    let t0 = std::time::Instant::now();
+
+    tracing::info!(from = from, to = to, "processing event range");
+
+    let mint_filter = contract
+        .BadgeMinted_filter()
+        .from_block(BlockNumberOrTag::Number(from))
+        .to_block(BlockNumberOrTag::Number(to));
+
+    let update_filter = contract
+        .BadgeTierUpdated_filter()
+        .from_block(BlockNumberOrTag::Number(from))
+        .to_block(BlockNumberOrTag::Number(to));
+
+    let (mint_logs, update_logs) = try_join(mint_filter.query(), update_filter.query()).await?;
+
+    enum Event {
+        Minted(BadgeMinted, Log),
+        Updated(BadgeTierUpdated, Log),
+    }
+
+    let all_logs: Vec<Event> = mint_logs
+        .into_iter()
+        .map(|(ev, log)| Event::Minted(ev, log))
+        .chain(
+            update_logs
+                .into_iter()
+                .map(|(ev, log)| Event::Updated(ev, log)),
+        )
+        .collect();
+
+    if all_logs.is_empty() {
+        tracing::info!(from = from, to = to, "no logs found in range");
+        return Ok(Stats::default());
+    }
+
+    let mut timestamps = BlockTimestamps::new(&provider);
+    #[derive(Debug)]
+    struct Row {
+        badge_id: i32,
+        account: String,
+        tier: i32,
+        points: i32,
+        block_number: i32,
+        log_index: Option<i32>,
+        tx_hash: String,
+        uri: String,
+        claimed_at: chrono::DateTime<chrono::Utc>,
+    }
+
+    let mut rows = Vec::with_capacity(all_logs.len());
+    for event in all_logs {
+        match event {
+            Event::Minted(ev, log) => {
+                let tx_hex = log
+                    .transaction_hash
+                    .map(|h| format!("{:#x}", h))
+                    .unwrap_or_default();
+                let block_num = log.block_number.unwrap_or(0) as i32;
+                rows.push(Row {
+                    badge_id: ev.badgeId.to::<i32>(),
+                    account: format!("{:#x}", ev.user).to_lowercase(),
+                    tier: ev.initialTier.to::<i32>(),
+                    points: ev.points.to::<i32>(),
+                    block_number: block_num,
+                    log_index: log.log_index.map(|i| i as i32),
+                    tx_hash: tx_hex,
+                    uri: sanitize_text(&ev.uri).0.into_owned(),
+                    claimed_at: timestamps.for_log(&log).await?,
+                });
+            }
+            Event::Updated(ev, log) => {
+                let tx_hex = log
+                    .transaction_hash
+                    .map(|h| format!("{:#x}", h))
+                    .unwrap_or_default();
+                let block_num = log.block_number.unwrap_or(0) as i32;
+                rows.push(Row {
+                    badge_id: ev.badgeId.to::<i32>(),
+                    account: format!("{:#x}", ev.user).to_lowercase(),
+                    tier: ev.tier.to::<i32>(),
+                    points: ev.points.to::<i32>(),
+                    block_number: block_num,
+                    log_index: log.log_index.map(|i| i as i32),
+                    tx_hash: tx_hex,
+                    uri: sanitize_text(&ev.uri).0.into_owned(),
+                    claimed_at: timestamps.for_log(&log).await?,
+                });
+            }
+        }
+    }
+
+    const MAX_PARAMS: usize = u16::MAX as usize;
+    const PARAMS_PER_ROW: usize = 9;
+
+    const MAX_ROWS_PER_BATCH: usize = MAX_PARAMS / PARAMS_PER_ROW;
+
+    let mut total_rows_written: u64 = 0;
+
+    // Claims and the aggregates derived from them commit together
+    let mut tx = db.begin().await?;
+    if !rows.is_empty() {
+        for (chunk_idx, chunk) in rows.chunks(MAX_ROWS_PER_BATCH).enumerate() {
+            let mut qb = QueryBuilder::new(format!(
+                "INSERT INTO {table} (
+                    badge_id, account, tier, points, block_number, log_index, tx_hash, uri, claimed_at
+                ) "
+            ));
+
+            qb.push_values(chunk.iter(), |mut b, r| {
+                b.push_bind(r.badge_id)
+                    .push_bind(&r.account)
+                    .push_bind(r.tier)
+                    .push_bind(r.points)
+                    .push_bind(r.block_number)
+                    .push_bind(r.log_index)
+                    .push_bind(&r.tx_hash)
+                    .push_bind(&r.uri)
+                    .push_bind(r.claimed_at.naive_utc());
+            });
+
+            qb.push(
+                " ON CONFLICT (badge_id, tier, account, block_number)
+          DO UPDATE SET
+              tier = EXCLUDED.tier,
+              points = EXCLUDED.points,
+              log_index = EXCLUDED.log_index,
+              tx_hash = EXCLUDED.tx_hash,
+              uri = EXCLUDED.uri,
+              claimed_at = EXCLUDED.claimed_at",
+            );
+
+            let res = qb.build().execute(&mut *tx).await.map_err(|e| {
+                let err_msg = format!(
+                    "Failed to insert badge claims batch (chunk {}, rows {}-{}): {}. \
+                     Sample rows: {:?}",
+                    chunk_idx,
+                    total_rows_written,
+                    total_rows_written + chunk.len() as u64,
+                    e,
+                    chunk.iter().take(3).collect::<Vec<_>>()
+                );
+                tracing::error!("{}", err_msg);
+                eyre::eyre!(err_msg)
+            })?;
+            total_rows_written = total_rows_written.saturating_add(res.rows_affected());
+        }
+    }
+    if let Some(current) = &processor.current {
+        let mut keys: Vec<(String, i32)> = rows.iter().map(|r| (r.account.clone(), r.badge_id)).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        current.refresh(&mut tx, &keys).await?;
+    }
+    if let Some(totals) = &processor.totals {
+        let mut accounts: Vec<String> = rows.iter().map(|r| r.account.clone()).collect();
+        accounts.sort_unstable();
+        accounts.dedup();
+        totals.refresh(&mut tx, &accounts, from).await?;
+    }
+    tx.commit().await?;
+
+    let took_ms = t0.elapsed().as_millis();
+    tracing::info!(
+        from = from,
+        to = to,
+        logs = rows.len(),
+        rows_written = total_rows_written,
+        took_ms,
+        "chunk processed",
+    );
+    Ok(Stats {
+        logs_found: rows.len(),
+        rows_written: total_rows_written,
+        from_block: from,
+        to_block: to,
+        took_ms,
+    })
+}
+
+/// Resets `claimed_at` to the block time of every stored claim.
+///
+/// Tier updates used to be stamped with the time they were indexed, so each
+/// reindex moved them to "today". Rows already correct are left untouched.
+pub async fn repair_claimed_at<P>(provider: P, db: &PgPool, table: &str) -> Result<u64>
+where
+    P: alloy::providers::Provider + Clone + Send + Sync + 'static,
+{
+    const BLOCKS_PER_BATCH: usize = 500;
+
+    let blocks: Vec<i32> = sqlx::query_scalar(&format!("SELECT DISTINCT block_number FROM {table} ORDER BY 1"))
+        .fetch_all(db)
+        .await?;
+    tracing::info!(blocks = blocks.len(), table, "repairing claimed_at");
+
+    let mut timestamps = BlockTimestamps::new(&provider);
+    let mut updated = 0u64;
+    for (i, chunk) in blocks.chunks(BLOCKS_PER_BATCH).enumerate() {
+        timestamps.prefetch(chunk.iter().map(|&b| b as u64), 16).await?;
+        let mut times = Vec::with_capacity(chunk.len());
+        for &b in chunk {
+            times.push(timestamps.get(b as u64).await?);
+        }
+
+        // claimed_at is a TIMESTAMP in UTC, whatever the session time zone
+        let res = sqlx::query(&format!(
+            "UPDATE {table} AS c SET claimed_at = v.ts AT TIME ZONE 'UTC'
+             FROM UNNEST($1::int[], $2::timestamptz[]) AS v(block_number, ts)
+             WHERE c.block_number = v.block_number AND c.claimed_at IS DISTINCT FROM v.ts AT TIME ZONE 'UTC'"
+        ))
+        .bind(chunk)
+        .bind(&times)
+        .execute(db)
+        .await?;
+        updated += res.rows_affected();
+
+        tracing::info!(
+            done = (i * BLOCKS_PER_BATCH + chunk.len()),
+            total = blocks.len(),
+            updated,
+            "claimed_at repair progress"
+        );
+    }
+    Ok(updated)
+}
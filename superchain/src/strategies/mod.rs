@@ -0,0 +1,9 @@
+mod account_created;
+mod badges_minted;
+mod owner_added;
+mod safe_settings;
+
+pub use account_created::AccountCreatedProcessor;
+pub use badges_minted::SuperChainBadgesMintedProcessor;
+pub use owner_added::OwnerAddedProcessor;
+pub use safe_settings::{SAFE_SETTING_CHANGES_TABLE, SafeSettingsProcessor};
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::Address,
+    rpc::types::{Filter, Log},
+    sol_types::SolEvent,
+};
+use async_trait::async_trait;
+use eyre::{Ok, Result};
+use indexer_core::blocks::sanitize_text;
+use indexer_core::strategies::{ChunkProcessor, Stats};
+use indexer_core::watched::WatchedAddresses;
+use sqlx::{PgPool, QueryBuilder};
+
+use crate::contracts::Safe::{AddedOwner, RemovedOwner};
+use crate::contracts::address_arg;
+use crate::contracts::SuperChainModule;
+use crate::owners::{self, Change, OwnerChange, Source};
+use super::account_created::watch_existing;
+
+/// Name this strategy registers addresses and scans under.
+const STRATEGY: &str = "owner_added";
+
+/// Owners of every account: `OwnerAdded` of the SuperChainModule, plus
+/// `AddedOwner` / `RemovedOwner` of the account's Safe (a swap emits both).
+/// Keeps `owner_changes` and the derived `eoas` current.
+#[derive(Clone)]
+pub struct OwnerAddedProcessor {
+    pub address: Address,
+    /// Accounts table of the profile (`super_accounts`, `users`).
+    pub table: String,
+    /// Account Safes, registered by the account-creation strategy and here.
+    pub watched: WatchedAddresses,
+}
+
+#[async_trait]
+impl<P: alloy::providers::Provider + Clone + Send + Sync + 'static> ChunkProcessor<P>
+    for OwnerAddedProcessor
+{
+    async fn process(&self, provider: P, db: &PgPool, from: u64, to: u64) -> Result<Stats> {
+        process_owner_added_chunk(provider, db, self, from, to).await
+    }
+
+    async fn prepare(&self, db: &PgPool) -> Result<()> {
+        owners::seed(db, &self.table).await?;
+        watch_existing(db, &self.table, &self.watched).await?;
+        Ok(())
+    }
+
+    async fn rollback(&self, db: &PgPool, to_block: u64) -> Result<u64> {
+        let mut tx = db.begin().await?;
+        self.watched.remove_after(&mut tx, STRATEGY, to_block).await?;
+        self.watched.rewind(&mut tx, STRATEGY, to_block).await?;
+        let removed = owners::rollback(&mut tx, &self.table, &[Source::Module, Source::Safe], to_block).await?;
+        tx.commit().await?;
+        Ok(removed)
+    }
+
+    async fn rebuild(&self, db: &PgPool) -> Result<u64> {
+        let mut conn = db.acquire().await?;
+        owners::refresh(&mut conn, &self.table, None).await
+    }
+
+    fn box_clone(&self) -> Box<dyn ChunkProcessor<P> + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+pub async fn process_owner_added_chunk<P>(
+    provider: P,
+    db: &PgPool,
+    processor: &OwnerAddedProcessor,
+    from: u64,
+    to: u64,
+) -> Result<Stats>
+where
+    P: alloy::providers::Provider + Clone + Send + Sync + 'static,
+{
+    let super_chain_module_addr = processor.address;
+    let table = processor.table.as_str();
+    let contract = SuperChainModule::new(super_chain_module_addr, provider.clone());
+    let t0 = std::time::Instant::now();
+
+    tracing::info!(from = from, to = to, "processing event range");
+
+    let logs = contract
+        .OwnerAdded_filter()
+        .from_block(BlockNumberOrTag::Number(from))
+        .to_block(BlockNumberOrTag::Number(to))
+        .query()
+        .await?;
+
+    // Accounts first seen here are watched from this block on, like created ones
+    let seen: Vec<(Address, u64)> =
+        logs.iter().map(|(ev, raw_log)| (ev.safe, raw_log.block_number.unwrap_or(from))).collect();
+    if !seen.is_empty() {
+        let mut conn = db.acquire().await?;
+        processor.watched.add(&mut conn, &seen, STRATEGY).await?;
+    }
+
+    let safe_filter = Filter::new().event_signature(vec![AddedOwner::SIGNATURE_HASH, RemovedOwner::SIGNATURE_HASH]);
+    let safe_logs = processor.watched.logs(&provider, db, STRATEGY, &safe_filter, from, to).await?;
+
+    if logs.is_empty() && safe_logs.logs.is_empty() {
+        let mut conn = db.acquire().await?;
+        safe_logs.mark(&mut conn).await?;
+        tracing::info!(from = from, to = to, "no logs found in range");
+        return Ok(Stats::default());
+    }
+
+    let mut changes: Vec<OwnerChange> = Vec::with_capacity(logs.len() + safe_logs.logs.len());
+
+    // Group owners by account to avoid "ON CONFLICT cannot affect row a second time" error
+    let mut account_owners: HashMap<String, (Vec<String>, String)> = HashMap::new();
+    for (event, raw_log) in &logs {
+        let account_hex = format!("{:#x}", event.safe);
+        let new_owner_hex = format!("{:#x}", event.newOwner);
+        let (username_clean, _) = sanitize_text(&event.superChainId);
+
+        changes.push(change(&account_hex, &new_owner_hex, Change::Added, Source::Module, raw_log));
+        account_owners
+            .entry(account_hex)
+            .or_insert_with(|| (Vec::new(), username_clean.into_owned()))
+            .0
+            .push(new_owner_hex);
+    }
+
+    let mut safe_changes = 0usize;
+    for log in &safe_logs.logs {
+        let account_hex = format!("{:#x}", log.address());
+        let Some(owner) = address_arg(log) else {
+            tracing::warn!(tx = ?log.transaction_hash, account = account_hex, "owner event without owner, skipped");
+            continue;
+        };
+        let kind = if log.topic0() == Some(&RemovedOwner::SIGNATURE_HASH) { Change::Removed } else { Change::Added };
+        changes.push(change(&account_hex, &format!("{owner:#x}"), kind, Source::Safe, log));
+        safe_changes += 1;
+    }
+
+    // Convert to Vec for batching
+    let rows: Vec<_> = account_owners.into_iter().collect();
+
+    // Process in smaller batches to avoid parameter limit
+    let mut rows_written = 0u64;
+    const BATCH_SIZE: usize = 500;
+
+    let mut tx = db.begin().await?;
+    for chunk in rows.chunks(BATCH_SIZE) {
+        let mut qb = QueryBuilder::new(format!(
+            "INSERT INTO {table} (account, eoas, nationality, username, level, noun, total_points, total_badges) "
+        ));
+
+        qb.push_values(chunk.iter(), |mut b, (account_hex, (owners, username))| {
+            b.push_bind(account_hex)
+                .push_bind(owners)
+                .push_bind(Option::<&str>::None) // nationality
+                .push_bind(username)              // username
+                .push_bind(0i32)                  // level
+                .push_bind(serde_json::json!({})) // noun
+                .push_bind(0i32)                  // total_points
+                .push_bind(0i32);                 // total_badges
+        });
+
+        // eoas of existing accounts comes from the history below
+        qb.push(" ON CONFLICT (account) DO NOTHING");
+
+        let batch_res = qb.build().execute(&mut *tx).await?;
+        rows_written += batch_res.rows_affected();
+    }
+
+    owners::record(&mut tx, &changes).await?;
+    let mut touched: Vec<String> = changes.iter().map(|c| c.account.clone()).collect();
+    touched.sort_unstable();
+    touched.dedup();
+    rows_written += owners::refresh(&mut tx, table, Some(&touched)).await?;
+    safe_logs.mark(&mut tx).await?;
+    tx.commit().await?;
+
+    let took_ms = t0.elapsed().as_millis();
+    tracing::info!(
+        from = from,
+        to = to,
+        logs = logs.len(),
+        safe_changes,
+        rows_written = rows_written,
+        took_ms,
+        "chunk processed",
+    );
+    Ok(Stats {
+        logs_found: logs.len() + safe_changes,
+        rows_written,
+        from_block: from,
+        to_block: to,
+        took_ms,
+    })
+}
+
+fn change(account: &str, owner: &str, change: Change, source: Source, log: &Log<impl Sized>) -> OwnerChange {
+    OwnerChange {
+        account: account.to_string(),
+        owner: owner.to_string(),
+        change,
+        source,
+        block_number: log.block_number.unwrap_or_default() as i64,
+        log_index: log.log_index.unwrap_or_default() as i32,
+        tx_hash: log.transaction_hash.map(|h| format!("{h:#x}")).unwrap_or_default(),
+    }
+}
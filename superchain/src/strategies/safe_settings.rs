@@ -0,0 +1,275 @@
+use alloy::{
+    primitives::Address,
+    rpc::types::{Filter, Log},
+    sol_types::SolEvent,
+};
+use async_trait::async_trait;
+use eyre::Result;
+use indexer_core::quarantine::{self, Quarantinable};
+use indexer_core::strategies::{ChunkProcessor, Stats};
+use indexer_core::watched::WatchedAddresses;
+use sqlx::{PgConnection, PgPool, QueryBuilder};
+
+use crate::contracts::Safe::{ChangedThreshold, DisabledModule, EnabledModule, SafeSetup};
+use crate::contracts::address_arg;
+use super::account_created::watch_existing;
+
+/// Name this strategy scans watched addresses under.
+const STRATEGY: &str = "safe_settings";
+
+/// Threshold and module changes, with the log that caused them.
+pub const SAFE_SETTING_CHANGES_TABLE: &str = "safe_setting_changes";
+
+/// Threshold and enabled modules of every account's Safe: `SafeSetup` and
+/// `ChangedThreshold` set `threshold`, `EnabledModule` / `DisabledModule` of
+/// the SuperChainModule set `module_enabled` (and so `module_detached`).
+/// Every change is kept in `safe_setting_changes`; the columns follow the
+/// latest one.
+#[derive(Clone)]
+pub struct SafeSettingsProcessor {
+    /// SuperChainModule, the module whose state is tracked per account.
+    pub module: Address,
+    /// Accounts table of the profile (`super_accounts`, `users`).
+    pub table: String,
+    /// Account Safes, registered by the account-creation strategy.
+    pub watched: WatchedAddresses,
+}
+
+/// A watched Safe log that cannot become a change.
+#[derive(Debug, thiserror::Error)]
+enum SafeLogError {
+    #[error("{event} log does not decode: {source}")]
+    Decode { event: &'static str, source: alloy::sol_types::Error },
+    #[error("{event} log carries no module")]
+    NoModule { event: &'static str },
+}
+
+impl Quarantinable for SafeLogError {
+    fn kind(&self) -> &'static str {
+        match self {
+            SafeLogError::Decode { .. } => "bad_log",
+            SafeLogError::NoModule { .. } => "missing_module",
+        }
+    }
+}
+
+struct Row {
+    account: String,
+    kind: &'static str,
+    module: Option<String>,
+    threshold: Option<i32>,
+    block_number: i64,
+    log_index: i32,
+    tx_hash: String,
+}
+
+#[async_trait]
+impl<P: alloy::providers::Provider + Clone + Send + Sync + 'static> ChunkProcessor<P>
+    for SafeSettingsProcessor
+{
+    async fn process(&self, provider: P, db: &PgPool, from: u64, to: u64) -> Result<Stats> {
+        process_safe_settings_chunk(provider, db, self, from, to).await
+    }
+
+    async fn prepare(&self, db: &PgPool) -> Result<()> {
+        watch_existing(db, &self.table, &self.watched).await?;
+        Ok(())
+    }
+
+    async fn rollback(&self, db: &PgPool, to_block: u64) -> Result<u64> {
+        let mut tx = db.begin().await?;
+        self.watched.rewind(&mut tx, STRATEGY, to_block).await?;
+        let removed = sqlx::query(&format!("DELETE FROM {SAFE_SETTING_CHANGES_TABLE} WHERE block_number > $1"))
+            .bind(to_block as i64)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        refresh(&mut tx, &self.table, self.module, None).await?;
+        tx.commit().await?;
+        Ok(removed)
+    }
+
+    async fn rebuild(&self, db: &PgPool) -> Result<u64> {
+        let mut conn = db.acquire().await?;
+        refresh(&mut conn, &self.table, self.module, None).await
+    }
+
+    fn box_clone(&self) -> Box<dyn ChunkProcessor<P> + Send + Sync> {
+        Box::new(self.clone())
+    }
+}
+
+pub async fn process_safe_settings_chunk<P>(
+    provider: P,
+    db: &PgPool,
+    processor: &SafeSettingsProcessor,
+    from: u64,
+    to: u64,
+) -> Result<Stats>
+where
+    P: alloy::providers::Provider + Clone + Send + Sync + 'static,
+{
+    let t0 = std::time::Instant::now();
+
+    tracing::info!(from = from, to = to, "processing event range");
+
+    let filter = Filter::new().event_signature(vec![
+        SafeSetup::SIGNATURE_HASH,
+        ChangedThreshold::SIGNATURE_HASH,
+        EnabledModule::SIGNATURE_HASH,
+        DisabledModule::SIGNATURE_HASH,
+    ]);
+    let watched = processor.watched.logs(&provider, db, STRATEGY, &filter, from, to).await?;
+
+    quarantine::release(db, STRATEGY, from, to).await?;
+
+    let mut rows: Vec<Row> = Vec::with_capacity(watched.logs.len());
+    for log in &watched.logs {
+        match row_of(log) {
+            Ok(Some(row)) => rows.push(row),
+            Ok(None) => {}
+            Err(e) => quarantine::quarantine(db, STRATEGY, log, &e).await?,
+        }
+    }
+
+    let mut rows_written = 0u64;
+    const BATCH_SIZE: usize = 5_000;
+
+    let mut tx = db.begin().await?;
+    for chunk in rows.chunks(BATCH_SIZE) {
+        let mut qb = QueryBuilder::new(format!(
+            "INSERT INTO {SAFE_SETTING_CHANGES_TABLE} (account, kind, module, threshold, block_number, log_index, tx_hash) "
+        ));
+        qb.push_values(chunk, |mut b, r| {
+            b.push_bind(&r.account)
+                .push_bind(r.kind)
+                .push_bind(&r.module)
+                .push_bind(r.threshold)
+                .push_bind(r.block_number)
+                .push_bind(r.log_index)
+                .push_bind(&r.tx_hash);
+        });
+        qb.push(" ON CONFLICT DO NOTHING");
+        qb.build().execute(&mut *tx).await?;
+    }
+    if !rows.is_empty() {
+        let mut touched: Vec<String> = rows.iter().map(|r| r.account.clone()).collect();
+        touched.sort_unstable();
+        touched.dedup();
+        rows_written = refresh(&mut tx, &processor.table, processor.module, Some(&touched)).await?;
+    }
+    watched.mark(&mut tx).await?;
+    tx.commit().await?;
+
+    let took_ms = t0.elapsed().as_millis();
+    tracing::info!(
+        from = from,
+        to = to,
+        logs = watched.logs.len(),
+        rows_written = rows_written,
+        took_ms,
+        "chunk processed",
+    );
+    Ok(Stats {
+        logs_found: watched.logs.len(),
+        rows_written,
+        from_block: from,
+        to_block: to,
+        took_ms,
+    })
+}
+
+/// The change a log records; `None` for other events.
+fn row_of(log: &Log) -> Result<Option<Row>, SafeLogError> {
+    let (kind, module, threshold) = match log.topic0() {
+        Some(&SafeSetup::SIGNATURE_HASH) => {
+            let ev = log
+                .log_decode::<SafeSetup>()
+                .map_err(|source| SafeLogError::Decode { event: "SafeSetup", source })?;
+            ("threshold", None, Some(ev.inner.threshold))
+        }
+        Some(&ChangedThreshold::SIGNATURE_HASH) => {
+            let ev = log
+                .log_decode::<ChangedThreshold>()
+                .map_err(|source| SafeLogError::Decode { event: "ChangedThreshold", source })?;
+            ("threshold", None, Some(ev.inner.threshold))
+        }
+        Some(&EnabledModule::SIGNATURE_HASH) => {
+            let module = address_arg(log).ok_or(SafeLogError::NoModule { event: "EnabledModule" })?;
+            ("module_enabled", Some(module), None)
+        }
+        Some(&DisabledModule::SIGNATURE_HASH) => {
+            let module = address_arg(log).ok_or(SafeLogError::NoModule { event: "DisabledModule" })?;
+            ("module_disabled", Some(module), None)
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(Row {
+        account: format!("{:#x}", log.address()),
+        kind,
+        module: module.map(|m| format!("{m:#x}")),
+        threshold: threshold.map(|t| t.saturating_to::<i32>()),
+        block_number: log.block_number.unwrap_or_default() as i64,
+        log_index: log.log_index.unwrap_or_default() as i32,
+        tx_hash: log.transaction_hash.map(|h| format!("{h:#x}")).unwrap_or_default(),
+    }))
+}
+
+/// Recomputes `threshold` and `module_enabled` of the given accounts (all of
+/// them with `None`) from their latest changes. Accounts without changes keep
+/// NULL. Returns the number of accounts that changed.
+async fn refresh(conn: &mut PgConnection, table: &str, module: Address, accounts: Option<&[String]>) -> Result<u64> {
+    let filter = if accounts.is_some() { "WHERE a.account = ANY($2)" } else { "" };
+    let sql = format!(
+        "UPDATE {table} AS a
+         SET threshold = s.threshold, module_enabled = s.module_enabled
+         FROM (
+             SELECT a.account,
+                    (SELECT c.threshold FROM {SAFE_SETTING_CHANGES_TABLE} AS c
+                     WHERE c.account = a.account AND c.kind = 'threshold'
+                     ORDER BY c.block_number DESC, c.log_index DESC LIMIT 1) AS threshold,
+                    (SELECT c.kind = 'module_enabled' FROM {SAFE_SETTING_CHANGES_TABLE} AS c
+                     WHERE c.account = a.account AND c.module = $1
+                     ORDER BY c.block_number DESC, c.log_index DESC LIMIT 1) AS module_enabled
+             FROM {table} AS a {filter}
+         ) AS s
+         WHERE a.account = s.account AND (a.threshold, a.module_enabled) IS DISTINCT FROM (s.threshold, s.module_enabled)"
+    );
+    let query = sqlx::query(&sql).bind(format!("{module:#x}"));
+    let query = match accounts {
+        Some(accounts) => query.bind(accounts),
+        None => query,
+    };
+    Ok(query.execute(conn).await?.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::{Bytes, LogData, U256};
+
+    use indexer_core::testing::log;
+
+    use super::*;
+
+    const SAFE: Address = Address::repeat_byte(1);
+
+    #[test]
+    fn changes_decode() {
+        let threshold = ChangedThreshold { threshold: U256::from(2) }.encode_log_data();
+        let row = row_of(&log(SAFE, threshold)).unwrap().unwrap();
+        assert_eq!((row.kind, row.threshold), ("threshold", Some(2)));
+
+        // Safe 1.3: module in data
+        let module = Address::repeat_byte(9);
+        let row = row_of(&log(SAFE, LogData::new_unchecked(vec![EnabledModule::SIGNATURE_HASH], module.into_word().into()))).unwrap().unwrap();
+        assert_eq!(row.module, Some(format!("{module:#x}")));
+    }
+
+    #[test]
+    fn undecodable_logs_are_errors() {
+        let err = row_of(&log(SAFE, LogData::new_unchecked(vec![ChangedThreshold::SIGNATURE_HASH], Bytes::from(vec![1, 2])))).err().unwrap();
+        assert_eq!(err.kind(), "bad_log");
+        let err = row_of(&log(SAFE, LogData::new_unchecked(vec![DisabledModule::SIGNATURE_HASH], Bytes::new()))).err().unwrap();
+        assert_eq!(err.kind(), "missing_module");
+    }
+}
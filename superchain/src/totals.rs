@@ -0,0 +1,210 @@
+//! `total_points`, `total_badges` and `level` of the accounts table, derived
+//! from the claims table.
+//!
+//! Each badge counts once per account, with the points of its latest claim
+//! (highest block, then highest tier). The level is the highest one in
+//! `level_thresholds` whose `min_points` the account reaches, 0 below level 1.
+//! Strategies refresh the accounts they touch inside the transaction that
+//! writes the claims, recording in `level_ups` each level crossed at the
+//! claim that crossed it;
+//! [`AccountTotals::recompute`] rebuilds every account from scratch.
+
+use eyre::Result;
+use sqlx::{PgConnection, PgPool};
+
+/// Points needed per level, seeded with 50, 100, 250, 500, 1000, 2500, 5000.
+pub const LEVEL_THRESHOLDS_TABLE: &str = "level_thresholds";
+/// One row per level crossed, with the block and tx of the claim that crossed it.
+pub const LEVEL_UPS_TABLE: &str = "level_ups";
+
+#[derive(Clone, Debug)]
+pub struct AccountTotals {
+    /// Accounts table of the profile (`super_accounts`, `users`).
+    pub accounts_table: String,
+    /// Claims table, `badge_claims` in the bundled migrations.
+    pub claims_table: String,
+    /// Points needed for level 1, 2, ... (`[levels] thresholds`). When set
+    /// and different from `level_thresholds`, [`AccountTotals::apply_thresholds`]
+    /// replaces the table and re-levels every account; `None` uses the table as is.
+    pub level_thresholds: Option<Vec<i32>>,
+}
+
+impl AccountTotals {
+    pub fn new(accounts_table: impl Into<String>, claims_table: impl Into<String>) -> Self {
+        Self {
+            accounts_table: accounts_table.into(),
+            claims_table: claims_table.into(),
+            level_thresholds: None,
+        }
+    }
+
+    /// Recomputes the given accounts (lowercase hex) and records in
+    /// `level_ups` every level their claims from `from_block` on crossed, at
+    /// the claim that crossed it. Accounts without a row in the accounts
+    /// table yet are skipped; their creation refreshes them from block 0.
+    /// Returns the number of level changes recorded.
+    pub async fn refresh(&self, conn: &mut PgConnection, accounts: &[String], from_block: u64) -> Result<u64> {
+        if accounts.is_empty() {
+            return Ok(0);
+        }
+
+        let (accounts_table, claims) = (&self.accounts_table, &self.claims_table);
+        // Claims in chain order; each one replaces the points of the
+        // previous claim of its badge, as the latest claim counts
+        let order = "block_number, log_index NULLS FIRST, tier, badge_id";
+        let recorded = sqlx::query(&format!(
+            "WITH running AS (
+                 SELECT d.account, d.block_number, d.tx_hash, d.log_index, d.tier, d.badge_id,
+                        SUM(d.delta) OVER (PARTITION BY d.account ORDER BY {order} ROWS UNBOUNDED PRECEDING)::int AS points
+                 FROM (
+                     SELECT c.*, COALESCE(c.points, 0)
+                                 - COALESCE(LAG(c.points) OVER (PARTITION BY c.account, c.badge_id ORDER BY {order}), 0) AS delta
+                     FROM {claims} AS c
+                     WHERE c.account = ANY($1)
+                 ) AS d
+             ),
+             levelled AS (
+                 SELECT r.*, COALESCE((SELECT MAX(level) FROM {LEVEL_THRESHOLDS_TABLE} WHERE min_points <= r.points), 0) AS level
+                 FROM running AS r
+             ),
+             steps AS (
+                 SELECT l.*, LAG(l.level, 1, 0) OVER (PARTITION BY l.account ORDER BY {order}) AS previous_level
+                 FROM levelled AS l
+             )
+             INSERT INTO {LEVEL_UPS_TABLE} (account, level, previous_level, total_points, block_number, tx_hash)
+             SELECT s.account, x.level, x.level - d.step, s.points, s.block_number, s.tx_hash
+             FROM steps AS s
+             JOIN {accounts_table} AS a ON a.account = s.account
+             CROSS JOIN LATERAL (SELECT CASE WHEN s.level > s.previous_level THEN 1 ELSE -1 END AS step) AS d
+             CROSS JOIN LATERAL generate_series(s.previous_level + d.step, s.level, d.step) AS x(level)
+             WHERE s.level <> s.previous_level AND s.block_number >= $2
+             ON CONFLICT DO NOTHING"
+        ))
+        .bind(accounts)
+        .bind(from_block as i64)
+        .execute(&mut *conn)
+        .await?
+        .rows_affected();
+
+        sqlx::query(&self.update_sql(true)).bind(accounts).execute(&mut *conn).await?;
+        Ok(recorded)
+    }
+
+    /// Recomputes every account, without recording level changes. Returns
+    /// the number of rows that changed.
+    pub async fn recompute(&self, conn: &mut PgConnection) -> Result<u64> {
+        let res = sqlx::query(&self.update_sql(false)).execute(conn).await?;
+        Ok(res.rows_affected())
+    }
+
+    /// Makes `level_thresholds` match [`AccountTotals::level_thresholds`] and,
+    /// if it changed, re-levels every account in the same transaction.
+    /// Returns whether the thresholds changed.
+    pub async fn apply_thresholds(&self, db: &PgPool) -> Result<bool> {
+        let Some(wanted) = &self.level_thresholds else {
+            return Ok(false);
+        };
+
+        let mut tx = db.begin().await?;
+        sqlx::query(&format!("LOCK TABLE {LEVEL_THRESHOLDS_TABLE} IN EXCLUSIVE MODE"))
+            .execute(&mut *tx)
+            .await?;
+        let current: Vec<i32> =
+            sqlx::query_scalar(&format!("SELECT min_points FROM {LEVEL_THRESHOLDS_TABLE} ORDER BY level"))
+                .fetch_all(&mut *tx)
+                .await?;
+        if &current == wanted {
+            return Ok(false);
+        }
+
+        sqlx::query(&format!("DELETE FROM {LEVEL_THRESHOLDS_TABLE}")).execute(&mut *tx).await?;
+        sqlx::query(&format!(
+            "INSERT INTO {LEVEL_THRESHOLDS_TABLE} (level, min_points)
+             SELECT level::int, min_points FROM UNNEST($1::int[]) WITH ORDINALITY AS t(min_points, level)"
+        ))
+        .bind(wanted)
+        .execute(&mut *tx)
+        .await?;
+        let relevelled = sqlx::query(&self.update_sql(false)).execute(&mut *tx).await?.rows_affected();
+        tx.commit().await?;
+
+        tracing::info!(from = ?current, to = ?wanted, relevelled, "level thresholds changed");
+        Ok(true)
+    }
+
+    /// `UPDATE` of the accounts table; with `only_some`, restricted to `$1::text[]`.
+    fn update_sql(&self, only_some: bool) -> String {
+        let (accounts, claims) = (&self.accounts_table, &self.claims_table);
+        let (filter_accounts, filter_claims) = if only_some {
+            ("WHERE a.account = ANY($1)", "WHERE account = ANY($1)")
+        } else {
+            ("", "")
+        };
+        format!(
+            "UPDATE {accounts} AS a
+             SET total_points = t.points, total_badges = t.badges, level = t.level
+             FROM (
+                 SELECT s.*,
+                        COALESCE((SELECT MAX(level) FROM {LEVEL_THRESHOLDS_TABLE} WHERE min_points <= s.points), 0) AS level
+                 FROM (
+                     SELECT a.account, a.level AS previous_level,
+                            COALESCE(SUM(l.points), 0)::int AS points, COUNT(l.badge_id)::int AS badges
+                     FROM {accounts} AS a
+                     LEFT JOIN (
+                         SELECT DISTINCT ON (account, badge_id) account, badge_id, points
+                         FROM {claims} {filter_claims}
+                         ORDER BY account, badge_id, block_number DESC, log_index DESC NULLS LAST, tier DESC
+                     ) AS l ON l.account = a.account
+                     {filter_accounts}
+                     GROUP BY a.account, a.level
+                 ) AS s
+             ) AS t
+             WHERE a.account = t.account
+               AND (a.total_points, a.total_badges, a.level) IS DISTINCT FROM (t.points, t.badges, t.level)"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indexer_core::testing::fresh_db;
+
+    use super::*;
+
+    #[tokio::test]
+    #[ignore = "needs DATABASE_URL pointing at a Postgres server"]
+    async fn every_level_crossed_is_recorded_at_its_claim() {
+        let db = fresh_db("totals_level_ups").await;
+        let account = "0x01".to_string();
+        sqlx::query(
+            "INSERT INTO super_accounts (account, username, eoas, level, noun, total_points, total_badges)
+             VALUES ($1, 'a', '{}', 0, '{}', 0, 0)",
+        )
+        .bind(&account)
+        .execute(&db)
+        .await
+        .unwrap();
+        // 60 points (level 1) at block 10, then 200 more (levels 2 and 3) at block 20
+        sqlx::query(
+            "INSERT INTO badge_claims (badge_id, account, tier, points, block_number, tx_hash, log_index)
+             VALUES (1, $1, 1, 60, 10, '0xa', 0), (2, $1, 1, 200, 20, '0xb', 0)",
+        )
+        .bind(&account)
+        .execute(&db)
+        .await
+        .unwrap();
+
+        let totals = AccountTotals::new("super_accounts", "badge_claims");
+        let mut conn = db.acquire().await.unwrap();
+        assert_eq!(totals.refresh(&mut conn, std::slice::from_ref(&account), 0).await.unwrap(), 3);
+        let ups: Vec<(i32, i32, i32, String)> = sqlx::query_as(&format!(
+            "SELECT level, previous_level, block_number, tx_hash FROM {LEVEL_UPS_TABLE} ORDER BY level"
+        ))
+        .fetch_all(&db)
+        .await
+        .unwrap();
+        assert_eq!(ups, [(1, 0, 10, "0xa".into()), (2, 1, 20, "0xb".into()), (3, 2, 20, "0xb".into())]);
+        let level: i32 = sqlx::query_scalar("SELECT level FROM super_accounts").fetch_one(&db).await.unwrap();
+        assert_eq!(level, 3);
+    }
+}